@@ -1,10 +1,9 @@
-use clap;
-use clap::{command, Parser};
+use clap::{Parser, Subcommand, ValueEnum};
 use near_da_http_api_data::ConfigureClientRequest;
 use near_da_primitives::Mode;
 use near_da_rpc::near::config::Config;
 use near_da_rpc::near::Client;
-use near_da_rpc::{CryptoHash, DataAvailability};
+use near_da_rpc::{AccountId, CryptoHash, DataAvailability};
 use serde::{Deserialize, Serialize};
 use std::fmt::Display as FmtDisplay;
 use std::str;
@@ -29,31 +28,41 @@ struct AppState {
     client: Option<Client>,
 }
 
-fn config_request_to_config(request: ConfigureClientRequest) -> Result<Config, anyhow::Error> {
-    Ok(Config {
-        key: near_da_rpc::near::config::KeyType::SecretKey(request.account_id, request.secret_key),
-        contract: request.contract_id,
-        network: request
-            .network
-            .as_str()
-            .try_into()
-            .map_err(|e: String| anyhow::anyhow!(e))?,
-        namespace: request
-            .namespace
-            .map(|ns| near_da_primitives::Namespace::new(ns.version, ns.id)),
-        mode: request.mode.unwrap_or_default(),
-    })
-}
-
 fn hex_to_bytes(hex: String) -> Result<Vec<u8>, anyhow::Error> {
     let bytes = hex::decode(hex)?;
     Ok(bytes)
 }
 
+const STORE_WASM: &str = "./target/wasm32-unknown-unknown/release/near_da_blob_store.wasm";
+const REGISTRY_WASM: &str = "./target/wasm32-unknown-unknown/release/blob_registry.wasm";
+
 #[derive(Parser, Debug)]
 enum Commands {
     Submit(SubmitArgs),
     Get(GetArgs),
+    #[command(flatten)]
+    Manage(ManageCommand),
+}
+
+// Commands that send a transaction to deploy or manage the contracts. Not a
+// doc comment, which clap would show as the CLI's about.
+#[derive(Subcommand, Debug)]
+enum ManageCommand {
+    /// Deploy the blob store to the signer's account
+    DeployStore(DeployArgs),
+    /// Deploy the blob registry to the signer's account
+    DeployRegistry(DeployRegistryArgs),
+    /// Initialize the configured blob store or registry, if it was deployed
+    /// without --init
+    Init(InitArgs),
+    /// Propose the next owner of the blob store, or withdraw the proposal
+    ProposeOwner(ProposeOwnerArgs),
+    /// Accept the ownership of the blob store proposed to the signer
+    AcceptOwner,
+    /// Register a namespace on the registry, maintained by the signer
+    RegisterNamespace(RegisterNamespaceArgs),
+    /// Let another account submit for a namespace on the registry
+    AddMaintainer(AddMaintainerArgs),
 }
 
 #[derive(Parser, Debug, Serialize, Deserialize)]
@@ -66,6 +75,58 @@ struct GetArgs {
     pub transaction_id: String,
 }
 
+#[derive(Parser, Debug)]
+struct DeployArgs {
+    /// Path to the contract, defaults to where `make build-contracts` puts it
+    #[clap(long)]
+    pub wasm: Option<String>,
+    /// Initialize the contract in the deploy transaction, so nobody else can
+    /// first
+    #[clap(long)]
+    pub init: bool,
+}
+
+#[derive(Parser, Debug)]
+struct DeployRegistryArgs {
+    #[command(flatten)]
+    pub deploy: DeployArgs,
+    /// Owner of the registry when it is initialized, defaults to the signer
+    #[clap(long, requires = "init")]
+    pub owner: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Contract {
+    Store,
+    Registry,
+}
+
+#[derive(Parser, Debug)]
+struct InitArgs {
+    pub contract: Contract,
+    /// Owner of the registry, defaults to the signer. The blob store is always
+    /// owned by the signer.
+    #[clap(long)]
+    pub owner: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+struct ProposeOwnerArgs {
+    /// Withdraws the current proposal when left out
+    pub account_id: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+struct RegisterNamespaceArgs {
+    pub namespace: u32,
+}
+
+#[derive(Parser, Debug)]
+struct AddMaintainerArgs {
+    pub namespace: u32,
+    pub maintainer: String,
+}
+
 struct AppError(anyhow::Error);
 
 impl<E> From<E> for AppError
@@ -127,6 +188,81 @@ async fn get_blob(
     Ok(blob)
 }
 
+fn account_id(account_id: &str) -> anyhow::Result<AccountId> {
+    account_id
+        .parse()
+        .map_err(|e| anyhow::anyhow!("invalid account id {account_id}: {e}"))
+}
+
+async fn manage(state: AppState, command: ManageCommand) -> anyhow::Result<CryptoHash, AppError> {
+    let client = state
+        .client
+        .as_ref()
+        .ok_or(anyhow::anyhow!("client is not configured"))?;
+
+    let transaction_id = match command {
+        ManageCommand::DeployStore(deploy) => {
+            let code = read_wasm(deploy.wasm.as_deref().unwrap_or(STORE_WASM)).await?;
+            if deploy.init {
+                client.deploy_store(code).await
+            } else {
+                client.deploy(code).await
+            }
+        }
+        ManageCommand::DeployRegistry(DeployRegistryArgs { deploy, owner }) => {
+            let code = read_wasm(deploy.wasm.as_deref().unwrap_or(REGISTRY_WASM)).await?;
+            if deploy.init {
+                client
+                    .deploy_registry(code, &registry_owner(client, owner).await?)
+                    .await
+            } else {
+                client.deploy(code).await
+            }
+        }
+        ManageCommand::Init(init) => match init.contract {
+            Contract::Store => client.init_store().await,
+            Contract::Registry => {
+                client
+                    .init_registry(&registry_owner(client, init.owner).await?)
+                    .await
+            }
+        },
+        ManageCommand::ProposeOwner(propose) => {
+            let account_id = propose.account_id.as_deref().map(account_id).transpose()?;
+            client.propose_owner(account_id.as_ref()).await
+        }
+        ManageCommand::AcceptOwner => client.accept_owner().await,
+        ManageCommand::RegisterNamespace(register) => {
+            client.register_namespace(register.namespace).await
+        }
+        ManageCommand::AddMaintainer(add) => {
+            client
+                .add_maintainer(add.namespace, &account_id(&add.maintainer)?)
+                .await
+        }
+    };
+    Ok(transaction_id.map_err(|e| anyhow::anyhow!("failed to send transaction: {}", e))?)
+}
+
+/// The given owner, or the signer.
+async fn registry_owner(client: &Client, owner: Option<String>) -> anyhow::Result<AccountId> {
+    match owner {
+        Some(owner) => account_id(&owner),
+        None => Ok(client
+            .signer()
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to load signer: {}", e))?
+            .account_id()
+            .clone()),
+    }
+}
+
+async fn read_wasm(path: &str) -> anyhow::Result<Vec<u8>> {
+    tokio::fs::read(path)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to read {path}: {e}"))
+}
+
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt()
@@ -141,9 +277,7 @@ async fn main() {
         let file_contents = tokio::fs::read_to_string(path).await.unwrap();
         let config_parse = serde_json::from_str::<ConfigureClientRequest>(&file_contents)
             .unwrap_or_else(|e| panic!("failed to parse config: {}", e));
-        state.client = Some(Client::new(
-            &config_request_to_config(config_parse).unwrap(),
-        ));
+        state.client = Some(Client::new(&Config::try_from(config_parse).unwrap()));
     }
 
     match args.command {
@@ -155,5 +289,9 @@ async fn main() {
             Ok(blob) => println!("{:?}", blob),
             Err(e) => println!("{}", e),
         },
+        Commands::Manage(command) => match manage(state, command).await {
+            Ok(transaction_id) => println!("{}", transaction_id),
+            Err(e) => println!("{}", e),
+        },
     };
 }
@@ -0,0 +1,56 @@
+//! Alerts for submissions that are still missing past the deadline. They are
+//! always logged, and POSTed as JSON to a webhook if one is set.
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use crate::monitor::Id;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndpointError {
+    pub endpoint: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alert {
+    #[serde(with = "hex::serde")]
+    pub transaction_id: Id,
+    pub missing_for_secs: u64,
+    /// Why each endpoint failed to serve it.
+    pub errors: Vec<EndpointError>,
+}
+
+#[derive(Default)]
+pub struct Alerter {
+    webhook: Option<(reqwest::Client, String)>,
+}
+
+impl Alerter {
+    pub fn webhook(url: String) -> Self {
+        Self {
+            webhook: Some((reqwest::Client::new(), url)),
+        }
+    }
+
+    /// A webhook that fails is logged, the alert is not retried.
+    pub async fn send(&self, alert: &Alert) {
+        error!(
+            "0x{} has been missing for {}s: {:?}",
+            hex::encode(alert.transaction_id),
+            alert.missing_for_secs,
+            alert.errors
+        );
+        if let Some((http, url)) = &self.webhook {
+            if let Err(e) = http
+                .post(url)
+                .json(alert)
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+            {
+                warn!("failed to send alert to {url}: {e}");
+            }
+        }
+    }
+}
@@ -0,0 +1,8 @@
+//! A fisherman for NEAR DA: it watches submissions, retrieves their data from
+//! several NEAR endpoints and alerts when a submission can't be retrieved.
+
+pub mod alert;
+pub mod monitor;
+pub mod source;
+
+pub type Result<T> = anyhow::Result<T>;
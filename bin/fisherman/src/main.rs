@@ -0,0 +1,147 @@
+use clap::Parser;
+use near_da_fisherman::{
+    alert::Alerter,
+    monitor::{Endpoint, Monitor},
+    source::{Refs, Source, Submitted},
+    Result,
+};
+use near_da_rpc::near::config::Network;
+use std::{path::PathBuf, time::Duration};
+use tracing::{error, info};
+use tracing_subscriber::EnvFilter;
+
+/// Watches NEAR DA submissions and alerts when their data can't be retrieved.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct CliArgs {
+    /// Ethereum RPC endpoint, to watch `Submitted` events.
+    #[arg(
+        long,
+        env = "NEAR_DA_L1_RPC",
+        requires = "l1_contract",
+        required_unless_present = "refs"
+    )]
+    l1_rpc: Option<String>,
+
+    /// Address of the `NearDataAvailability` contract.
+    #[arg(long, env = "NEAR_DA_L1_CONTRACT", requires = "l1_rpc")]
+    l1_contract: Option<String>,
+
+    /// L1 block to start reading `Submitted` events from.
+    #[arg(long, default_value_t = 0)]
+    from_block: u64,
+
+    /// File of hex transaction ids to check, one per line.
+    #[arg(long)]
+    refs: Option<PathBuf>,
+
+    /// NEAR account that signs the submissions.
+    #[arg(long, env = "NEAR_DA_ACCOUNT_ID")]
+    submitter: String,
+
+    /// NEAR network, for the default endpoints: mainnet, testnet or an RPC url.
+    #[arg(long, env = "NEAR_DA_NETWORK", default_value = "testnet")]
+    network: String,
+
+    /// NEAR RPC or archival endpoint to retrieve from, can be repeated.
+    /// Defaults to the network's RPC and archival nodes.
+    #[arg(long = "endpoint")]
+    endpoints: Vec<String>,
+
+    /// Seconds a submission may be missing before alerting.
+    #[arg(long, value_name = "SECONDS", default_value_t = 300)]
+    deadline: u64,
+
+    /// Seconds between checks.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 30,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    poll: u64,
+
+    /// POST alerts as JSON to this URL.
+    #[arg(long, env = "NEAR_DA_FISHERMAN_WEBHOOK")]
+    webhook: Option<String>,
+
+    /// Append every check, with its latency, to this file.
+    #[arg(long)]
+    records: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = CliArgs::parse();
+
+    tracing_subscriber::fmt()
+        .with_target(false)
+        .with_env_filter(EnvFilter::from_default_env())
+        .compact()
+        .init();
+
+    let endpoints = if args.endpoints.is_empty() {
+        let network: Network = args
+            .network
+            .as_str()
+            .try_into()
+            .map_err(|e: String| anyhow::anyhow!(e))?;
+        vec![network.to_endpoint(), network.archive_endpoint()]
+    } else {
+        args.endpoints
+    };
+    info!("retrieving from {}", endpoints.join(", "));
+    let endpoints = endpoints
+        .iter()
+        .map(|url| Endpoint::near(url, &args.submitter))
+        .collect();
+
+    let alerter = args.webhook.map(Alerter::webhook).unwrap_or_default();
+    let mut monitor = Monitor::new(endpoints, Duration::from_secs(args.deadline), alerter);
+    if let Some(path) = args.records {
+        monitor = monitor.with_records(path);
+    }
+
+    let mut sources: Vec<Box<dyn Source>> = vec![];
+    if let Some(path) = args.refs {
+        sources.push(Box::new(Refs::read(&path).await?));
+    }
+    if let (Some(rpc), Some(contract)) = (args.l1_rpc, args.l1_contract) {
+        let contract = contract
+            .parse()
+            .map_err(|_| anyhow::anyhow!("invalid L1 contract address {contract}"))?;
+        let client = near_da_eth::Client::read_only(&rpc, contract)
+            .map_err(|e| anyhow::anyhow!("failed to connect to L1: {e:#}"))?;
+        sources.push(Box::new(Submitted::new(client, args.from_block)));
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_secs(args.poll));
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                info!("stopping with {} submissions unconfirmed", monitor.pending().len());
+                return Ok(());
+            }
+            _ = tick(&mut interval, &mut sources, &mut monitor) => {}
+        }
+    }
+}
+
+/// Wait for the next poll, then read the sources and check what's pending.
+/// Polled alongside ctrl-c, so a slow check doesn't hold up stopping.
+async fn tick(
+    interval: &mut tokio::time::Interval,
+    sources: &mut [Box<dyn Source>],
+    monitor: &mut Monitor,
+) {
+    interval.tick().await;
+    for source in sources {
+        match source.next().await {
+            Ok(ids) => ids.into_iter().for_each(|id| monitor.watch(id)),
+            Err(e) => error!("failed to read submissions: {e:#}"),
+        }
+    }
+    if let Err(e) = monitor.check().await {
+        error!("check failed: {e:#}");
+    }
+}
@@ -0,0 +1,356 @@
+//! Retrieves each watched submission from every endpoint, recording whether
+//! and how quickly it was served, and alerts on the ones that stay missing.
+
+use anyhow::Context;
+use futures::{future::join_all, stream, StreamExt};
+use near_da_rpc::{
+    near::{
+        config::{Config, KeyType, Network},
+        Client,
+    },
+    DataAvailability,
+};
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, sync::Arc, time::Duration};
+use tokio::{io::AsyncWriteExt, time::Instant};
+use tracing::{debug, info};
+
+use crate::{
+    alert::{Alert, Alerter, EndpointError},
+    Result,
+};
+
+/// How long one endpoint may take to return a blob.
+pub const GET_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How many submissions are checked at once.
+pub const CONCURRENCY: usize = 16;
+
+pub type Id = [u8; 32];
+
+pub struct Endpoint {
+    pub name: String,
+    pub da: Arc<dyn DataAvailability + Send + Sync>,
+}
+
+impl Endpoint {
+    /// A NEAR RPC or archival node, reading the submissions of `submitter`.
+    pub fn near(url: &str, submitter: &str) -> Self {
+        let config = Config {
+            key: KeyType::ReadOnly(submitter.to_string()),
+            contract: String::new(),
+            network: Network::Custom(url.to_string()),
+            namespace: None,
+            mode: Default::default(),
+            registry: None,
+        };
+        Self {
+            name: url.to_string(),
+            da: Arc::new(Client::new(&config)),
+        }
+    }
+}
+
+/// One attempt to retrieve a submission from an endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Check {
+    #[serde(with = "hex::serde")]
+    pub transaction_id: Id,
+    pub endpoint: String,
+    pub available: bool,
+    pub latency_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+struct Watched {
+    id: Id,
+    since: Instant,
+    alerted: bool,
+}
+
+pub struct Monitor {
+    endpoints: Vec<Endpoint>,
+    /// How long a submission may be missing before alerting.
+    deadline: Duration,
+    alerter: Alerter,
+    /// Checks are appended here, one JSON object per line.
+    records: Option<PathBuf>,
+    watched: Vec<Watched>,
+}
+
+impl Monitor {
+    pub fn new(endpoints: Vec<Endpoint>, deadline: Duration, alerter: Alerter) -> Self {
+        Self {
+            endpoints,
+            deadline,
+            alerter,
+            records: None,
+            watched: vec![],
+        }
+    }
+
+    pub fn with_records(mut self, path: PathBuf) -> Self {
+        self.records = Some(path);
+        self
+    }
+
+    /// Start watching `id`, from now.
+    pub fn watch(&mut self, id: Id) {
+        if !self.watched.iter().any(|watched| watched.id == id) {
+            self.watched.push(Watched {
+                id,
+                since: Instant::now(),
+                alerted: false,
+            });
+        }
+    }
+
+    /// Submissions that no endpoint has served yet.
+    pub fn pending(&self) -> Vec<Id> {
+        self.watched.iter().map(|watched| watched.id).collect()
+    }
+
+    /// Try every pending submission on every endpoint. Available ones stop
+    /// being watched, missing ones past the deadline are alerted on once.
+    ///
+    /// Up to [`CONCURRENCY`] submissions are retrieved at once. Nothing is
+    /// dropped from the watch list until they've all been retrieved, so
+    /// cancelling a check leaves every submission watched.
+    pub async fn check(&mut self) -> Result<Vec<Check>> {
+        let endpoints = &self.endpoints;
+        let all_results: Vec<Vec<Check>> = stream::iter(self.watched.iter().map(|w| w.id))
+            .map(|id| join_all(endpoints.iter().map(move |endpoint| check(endpoint, id))))
+            .buffered(CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut checks = vec![];
+        let mut still_missing = vec![];
+        for (mut watched, results) in std::mem::take(&mut self.watched)
+            .into_iter()
+            .zip(all_results)
+        {
+            let id = hex::encode(watched.id);
+
+            if results.iter().any(|check| check.available) {
+                if watched.alerted {
+                    info!("0x{id} is available again");
+                } else {
+                    debug!("0x{id} is available");
+                }
+            } else {
+                if !watched.alerted && watched.since.elapsed() >= self.deadline {
+                    self.alerter
+                        .send(&Alert {
+                            transaction_id: watched.id,
+                            missing_for_secs: watched.since.elapsed().as_secs(),
+                            errors: results
+                                .iter()
+                                .map(|check| EndpointError {
+                                    endpoint: check.endpoint.clone(),
+                                    error: check.error.clone().unwrap_or_default(),
+                                })
+                                .collect(),
+                        })
+                        .await;
+                    watched.alerted = true;
+                }
+                still_missing.push(watched);
+            }
+            checks.extend(results);
+        }
+        self.watched = still_missing;
+
+        self.record(&checks).await?;
+        Ok(checks)
+    }
+
+    async fn record(&self, checks: &[Check]) -> Result<()> {
+        let Some(path) = &self.records else {
+            return Ok(());
+        };
+        let mut lines = vec![];
+        for check in checks {
+            serde_json::to_writer(&mut lines, check)?;
+            lines.push(b'\n');
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        file.write_all(&lines).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+async fn check(endpoint: &Endpoint, id: Id) -> Check {
+    let start = Instant::now();
+    let error = match tokio::time::timeout(GET_TIMEOUT, endpoint.da.get(id.into())).await {
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) => Some(format!("{e:#}")),
+        Err(_) => Some(format!("timed out after {GET_TIMEOUT:?}")),
+    };
+    let latency_ms = start.elapsed().as_millis() as u64;
+    if let Some(error) = &error {
+        debug!("0x{} is not on {}: {error}", hex::encode(id), endpoint.name);
+    }
+    Check {
+        transaction_id: id,
+        endpoint: endpoint.name.clone(),
+        available: error.is_none(),
+        latency_ms,
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_da_rpc::{Blob, CryptoHash, NotFound, Read, SubmitResult};
+    use std::collections::HashMap;
+
+    /// Serves the blobs it holds after `delay`, or fails every request when
+    /// `down`.
+    struct Fake {
+        blobs: HashMap<Id, Vec<u8>>,
+        down: bool,
+        delay: Duration,
+    }
+
+    #[async_trait::async_trait]
+    impl DataAvailability for Fake {
+        async fn submit(&self, _blob: Blob) -> eyre::Result<SubmitResult> {
+            eyre::bail!("read only")
+        }
+
+        async fn get(&self, transaction_id: CryptoHash) -> eyre::Result<Read> {
+            tokio::time::sleep(self.delay).await;
+            if self.down {
+                eyre::bail!("connection refused");
+            }
+            self.blobs
+                .get(&transaction_id.0)
+                .map(|data| Read(Blob::new(data.clone())))
+                .ok_or_else(|| NotFound(transaction_id).into())
+        }
+    }
+
+    fn endpoint(name: &str, blobs: &[Id], down: bool) -> Endpoint {
+        Endpoint {
+            name: name.to_string(),
+            da: Arc::new(Fake {
+                blobs: blobs.iter().map(|id| (*id, vec![1, 2, 3])).collect(),
+                down,
+                delay: Duration::ZERO,
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_available() {
+        let records = std::env::temp_dir().join("near-da-fisherman-test-available.jsonl");
+        let _ = std::fs::remove_file(&records);
+        let mut monitor = Monitor::new(
+            vec![endpoint("rpc", &[[1; 32]], false)],
+            Duration::from_secs(60),
+            Alerter::default(),
+        )
+        .with_records(records.clone());
+
+        monitor.watch([1; 32]);
+        monitor.watch([1; 32]);
+        let checks = monitor.check().await.unwrap();
+        assert_eq!(checks.len(), 1);
+        assert!(checks[0].available);
+        assert_eq!(checks[0].error, None);
+        assert!(monitor.pending().is_empty());
+
+        let recorded: Vec<Check> = std::fs::read_to_string(&records)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(recorded, checks);
+        std::fs::remove_file(&records).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failover() {
+        let mut monitor = Monitor::new(
+            vec![
+                endpoint("rpc", &[], true),
+                endpoint("archive", &[[1; 32]], false),
+            ],
+            Duration::ZERO,
+            Alerter::default(),
+        );
+
+        monitor.watch([1; 32]);
+        let checks = monitor.check().await.unwrap();
+        assert!(!checks[0].available);
+        assert!(checks[0].error.as_ref().unwrap().contains("refused"));
+        assert!(checks[1].available);
+        assert!(monitor.pending().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_concurrent() {
+        let ids: Vec<Id> = (0..CONCURRENCY as u8 * 2).map(|i| [i; 32]).collect();
+        let mut monitor = Monitor::new(
+            vec![Endpoint {
+                name: "slow".to_string(),
+                da: Arc::new(Fake {
+                    blobs: ids.iter().map(|id| (*id, vec![1])).collect(),
+                    down: false,
+                    delay: Duration::from_secs(10),
+                }),
+            }],
+            Duration::from_secs(60),
+            Alerter::default(),
+        );
+        ids.iter().for_each(|id| monitor.watch(*id));
+
+        let start = Instant::now();
+        let checks = monitor.check().await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(20));
+        assert_eq!(
+            checks.iter().map(|c| c.transaction_id).collect::<Vec<_>>(),
+            ids
+        );
+        assert!(monitor.pending().is_empty());
+
+        // Cancelled part way, every submission is still watched.
+        ids.iter().for_each(|id| monitor.watch(*id));
+        let cancelled = tokio::time::timeout(Duration::from_secs(15), monitor.check()).await;
+        assert!(cancelled.is_err());
+        assert_eq!(monitor.pending(), ids);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_missing_past_deadline() {
+        let mut monitor = Monitor::new(
+            vec![endpoint("rpc", &[], false)],
+            Duration::from_secs(60),
+            Alerter::default(),
+        );
+
+        monitor.watch([1; 32]);
+        let checks = monitor.check().await.unwrap();
+        assert!(!checks[0].available);
+        assert!(!monitor.watched[0].alerted);
+
+        tokio::time::advance(Duration::from_secs(60)).await;
+        monitor.check().await.unwrap();
+        assert!(monitor.watched[0].alerted);
+        assert_eq!(monitor.pending(), vec![[1; 32]]);
+
+        // Once it turns up, it is no longer watched.
+        monitor.endpoints = vec![endpoint("rpc", &[[1; 32]], false)];
+        monitor.check().await.unwrap();
+        assert!(monitor.pending().is_empty());
+    }
+}
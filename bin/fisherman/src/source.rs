@@ -0,0 +1,111 @@
+//! Where the submissions to watch come from.
+
+use async_trait::async_trait;
+use ethers::providers::Middleware;
+use std::path::Path;
+
+use crate::{monitor::Id, Result};
+
+/// Most blocks to read events from in one request.
+pub const MAX_RANGE: u64 = 5_000;
+
+#[async_trait]
+pub trait Source: Send {
+    /// Submissions seen since the last call.
+    async fn next(&mut self) -> Result<Vec<Id>>;
+}
+
+/// `Submitted` events on `NearDataAvailability.sol`.
+pub struct Submitted<M> {
+    client: near_da_eth::Client<M>,
+    next_block: u64,
+}
+
+impl<M: Middleware + 'static> Submitted<M> {
+    pub fn new(client: near_da_eth::Client<M>, from_block: u64) -> Self {
+        Self {
+            client,
+            next_block: from_block,
+        }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> Source for Submitted<M> {
+    async fn next(&mut self) -> Result<Vec<Id>> {
+        let head = self
+            .client
+            .block_number()
+            .await
+            .map_err(|e| anyhow::anyhow!("{e:#}"))?;
+        if self.next_block > head {
+            return Ok(vec![]);
+        }
+        // Catch up at most MAX_RANGE blocks a call, the rest on the next ones.
+        let to = head.min(self.next_block + MAX_RANGE - 1);
+        let events = self
+            .client
+            .submitted_events(self.next_block, Some(to))
+            .await
+            .map_err(|e| anyhow::anyhow!("{e:#}"))?;
+        self.next_block = to + 1;
+        Ok(events
+            .into_iter()
+            .map(|event| event.transaction_id)
+            .collect())
+    }
+}
+
+/// A fixed list of transaction ids, returned once.
+pub struct Refs(Vec<Id>);
+
+impl Refs {
+    /// Read hex transaction ids, one per line.
+    pub async fn read(path: &Path) -> Result<Self> {
+        let contents = tokio::fs::read_to_string(path).await?;
+        Self::parse(&contents)
+    }
+
+    fn parse(contents: &str) -> Result<Self> {
+        contents
+            .lines()
+            .map(str::trim)
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(i, line)| {
+                let mut id = [0; 32];
+                hex::decode_to_slice(line.trim_start_matches("0x"), &mut id)
+                    .map_err(|e| anyhow::anyhow!("line {}: invalid transaction id: {e}", i + 1))?;
+                Ok(id)
+            })
+            .collect::<Result<_>>()
+            .map(Self)
+    }
+}
+
+#[async_trait]
+impl Source for Refs {
+    async fn next(&mut self) -> Result<Vec<Id>> {
+        Ok(std::mem::take(&mut self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_refs() {
+        let mut refs = Refs::parse(&format!(
+            "# rollup batches\n0x{}\n\n{}\n",
+            "01".repeat(32),
+            "02".repeat(32)
+        ))
+        .unwrap();
+        assert_eq!(refs.next().await.unwrap(), vec![[1; 32], [2; 32]]);
+        assert!(refs.next().await.unwrap().is_empty());
+
+        let err = Refs::parse("# ok\n0x0102\n").err().unwrap();
+        assert!(err.to_string().starts_with("line 2:"), "{err}");
+    }
+}
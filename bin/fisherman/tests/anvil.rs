@@ -0,0 +1,68 @@
+//! Reads `Submitted` events from `NearDataAvailability.sol` on a local anvil
+//! node. Needs `anvil` on the path and the contract built with `forge build`
+//! in `eth/`, so the tests are ignored by default:
+//!
+//! ```sh
+//! (cd eth && forge build) && cargo test -p near-da-fisherman -- --ignored
+//! ```
+
+use ethers::{
+    abi::Abi,
+    contract::ContractFactory,
+    middleware::SignerMiddleware,
+    providers::{Http, Provider},
+    signers::{LocalWallet, Signer},
+    types::Bytes,
+    utils::Anvil,
+};
+use near_da_eth::Client;
+use near_da_fisherman::source::{Source, Submitted};
+use near_da_primitives::BlobRef;
+use std::{path::Path, sync::Arc, time::Duration};
+
+#[tokio::test]
+#[ignore = "needs anvil and `forge build`"]
+async fn test_submitted() {
+    let anvil = Anvil::new().spawn();
+    let wallet = LocalWallet::from(anvil.keys()[0].clone()).with_chain_id(anvil.chain_id());
+    let provider = Provider::<Http>::try_from(anvil.endpoint())
+        .unwrap()
+        .interval(Duration::from_millis(10));
+    let middleware = Arc::new(SignerMiddleware::new(provider, wallet));
+
+    let artifact = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../eth/out/NearDataAvailability.sol/NearDataAvailability.json");
+    let artifact: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(&artifact).expect("contract not built, run `forge build` in eth/"),
+    )
+    .unwrap();
+    let abi: Abi = serde_json::from_value(artifact["abi"].clone()).unwrap();
+    let bytecode = artifact["bytecode"]["object"].as_str().unwrap();
+    let bytecode = Bytes::from(hex::decode(bytecode.trim_start_matches("0x")).unwrap());
+    let contract = ContractFactory::new(abi, bytecode, middleware.clone())
+        .deploy(())
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    let owner = Client::with_middleware(contract.address(), middleware);
+
+    let mut source = Submitted::new(
+        Client::read_only(&anvil.endpoint(), contract.address()).unwrap(),
+        0,
+    );
+    assert!(source.next().await.unwrap().is_empty());
+
+    owner
+        .notify_submitted(&[BlobRef::new([1; 32]), BlobRef::new([2; 32])])
+        .await
+        .unwrap();
+    assert_eq!(source.next().await.unwrap(), vec![[1; 32], [2; 32]]);
+
+    owner
+        .notify_submitted(&[BlobRef::new([3; 32])])
+        .await
+        .unwrap();
+    assert_eq!(source.next().await.unwrap(), vec![[3; 32]]);
+    assert!(source.next().await.unwrap().is_empty());
+}
@@ -0,0 +1,188 @@
+//! Runs the monitor against a local stand-in for a NEAR JSON-RPC node, which
+//! answers `tx` for the submissions it knows and `UNKNOWN_TRANSACTION`
+//! otherwise.
+
+use axum::{extract::State, routing::post, Json, Router};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use near_da_fisherman::{
+    alert::{Alert, Alerter},
+    monitor::{Endpoint, Monitor},
+};
+use near_da_primitives::SubmitRequest;
+use near_da_rpc::CryptoHash;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+const SUBMITTER: &str = "submitter.testnet";
+
+/// Bind `app` to a free local port.
+fn serve(app: Router) -> String {
+    let server =
+        axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(app.into_make_service());
+    let url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+    url
+}
+
+/// A NEAR node holding `blobs`, keyed by the base58 transaction id.
+fn near(blobs: &[([u8; 32], Vec<u8>)]) -> String {
+    let blobs: HashMap<String, Vec<u8>> = blobs
+        .iter()
+        .map(|(id, data)| (CryptoHash(*id).to_string(), data.clone()))
+        .collect();
+    serve(
+        Router::new()
+            .route("/", post(rpc))
+            .with_state(Arc::new(blobs)),
+    )
+}
+
+async fn rpc(
+    State(blobs): State<Arc<HashMap<String, Vec<u8>>>>,
+    Json(req): Json<Value>,
+) -> Json<Value> {
+    assert_eq!(req["method"], "tx");
+    assert_eq!(req["params"]["sender_account_id"], SUBMITTER);
+    let hash = req["params"]["tx_hash"].as_str().unwrap();
+    let Some(data) = blobs.get(hash) else {
+        return Json(json!({
+            "jsonrpc": "2.0",
+            "id": req["id"],
+            "error": {
+                "name": "HANDLER_ERROR",
+                "cause": {
+                    "name": "UNKNOWN_TRANSACTION",
+                    "info": { "requested_transaction_hash": hash }
+                },
+                "code": -32000,
+                "message": "Server error",
+                "data": format!("Transaction {hash} doesn't exist")
+            }
+        }));
+    };
+
+    let args = borsh::to_vec(&SubmitRequest {
+        namespace: None,
+        data: data.clone(),
+    })
+    .unwrap();
+    let block_hash = CryptoHash([9; 32]).to_string();
+    let outcome = json!({
+        "logs": [],
+        "receipt_ids": [],
+        "gas_burnt": 1,
+        "tokens_burnt": "0",
+        "executor_id": SUBMITTER,
+        "status": { "SuccessValue": "" },
+        "metadata": { "version": 1, "gas_profile": null }
+    });
+    Json(json!({
+        "jsonrpc": "2.0",
+        "id": req["id"],
+        "result": {
+            "final_execution_status": "FINAL",
+            "status": { "SuccessValue": "" },
+            "transaction": {
+                "signer_id": SUBMITTER,
+                "public_key": "ed25519:11111111111111111111111111111111",
+                "nonce": 1,
+                "receiver_id": "blob.testnet",
+                "actions": [{
+                    "FunctionCall": {
+                        "method_name": "submit",
+                        "args": STANDARD.encode(args),
+                        "gas": 1,
+                        "deposit": "0"
+                    }
+                }],
+                "signature": format!("ed25519:{}", "1".repeat(64)),
+                "hash": hash
+            },
+            "transaction_outcome": {
+                "proof": [],
+                "block_hash": block_hash,
+                "id": hash,
+                "outcome": outcome
+            },
+            "receipts_outcome": []
+        }
+    }))
+}
+
+/// A webhook that keeps the alerts it receives.
+fn webhook() -> (String, Arc<Mutex<Vec<Alert>>>) {
+    let alerts = Arc::new(Mutex::new(vec![]));
+    let url = serve(
+        Router::new()
+            .route(
+                "/",
+                post(
+                    |State(alerts): State<Arc<Mutex<Vec<Alert>>>>, Json(alert): Json<Alert>| async move {
+                        alerts.lock().unwrap().push(alert);
+                    },
+                ),
+            )
+            .with_state(alerts.clone()),
+    );
+    (url, alerts)
+}
+
+#[tokio::test]
+async fn test_retrieves_from_either_endpoint() {
+    let rpc = near(&[([1; 32], vec![1, 2, 3])]);
+    let archive = near(&[([1; 32], vec![1, 2, 3]), ([2; 32], vec![4, 5, 6])]);
+    let mut monitor = Monitor::new(
+        vec![
+            Endpoint::near(&rpc, SUBMITTER),
+            Endpoint::near(&archive, SUBMITTER),
+        ],
+        Duration::from_secs(60),
+        Alerter::default(),
+    );
+
+    monitor.watch([1; 32]);
+    monitor.watch([2; 32]);
+    let checks = monitor.check().await.unwrap();
+    assert_eq!(checks.len(), 4);
+    assert!(checks
+        .iter()
+        .filter(|check| check.endpoint == archive)
+        .all(|check| check.available));
+    let missing = checks
+        .iter()
+        .find(|check| check.endpoint == rpc && check.transaction_id == [2; 32])
+        .unwrap();
+    assert!(!missing.available);
+    assert!(
+        missing.error.as_ref().unwrap().contains("not found"),
+        "{missing:?}"
+    );
+    assert!(monitor.pending().is_empty());
+}
+
+#[tokio::test]
+async fn test_alerts_webhook() {
+    let rpc = near(&[]);
+    let (url, alerts) = webhook();
+    let mut monitor = Monitor::new(
+        vec![Endpoint::near(&rpc, SUBMITTER)],
+        Duration::ZERO,
+        Alerter::webhook(url),
+    );
+
+    monitor.watch([3; 32]);
+    monitor.check().await.unwrap();
+    monitor.check().await.unwrap();
+
+    let alerts = alerts.lock().unwrap();
+    assert_eq!(alerts.len(), 1, "alerted more than once");
+    assert_eq!(alerts[0].transaction_id, [3; 32]);
+    assert_eq!(alerts[0].errors.len(), 1);
+    assert_eq!(alerts[0].errors[0].endpoint, rpc);
+    assert_eq!(monitor.pending(), vec![[3; 32]]);
+}
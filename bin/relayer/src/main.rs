@@ -0,0 +1,181 @@
+use clap::Parser;
+use near_da_primitives::Secret;
+use near_da_rpc::near::{config::Network, status::Observer};
+use std::{path::PathBuf, time::Duration};
+use tokio::signal::unix::{signal, SignalKind};
+use tracing::{error, info, warn};
+use tracing_subscriber::EnvFilter;
+
+use relay::{Near, Relayer, L1};
+use store::Store;
+use verifier::{Finality, LightClient, Verifier};
+
+mod relay;
+mod store;
+mod verifier;
+
+pub type Result<T> = anyhow::Result<T>;
+
+/// Posts `notifyAvailable` to `NearDataAvailability.sol` for each submission
+/// the contract was notified of, once it is final on NEAR and verified.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct CliArgs {
+    /// Ethereum RPC endpoint.
+    #[arg(long, env = "NEAR_DA_L1_RPC")]
+    l1_rpc: String,
+
+    /// Address of the `NearDataAvailability` contract.
+    #[arg(long, env = "NEAR_DA_L1_CONTRACT")]
+    l1_contract: String,
+
+    /// Hex encoded key of an L1 account with the verifier role.
+    #[arg(long, env = "NEAR_DA_L1_KEY", hide_env_values = true, required_unless_present = "dry_run", value_parser = parse_secret)]
+    l1_key: Option<Secret<String>>,
+
+    /// NEAR network: mainnet, testnet or an RPC url.
+    #[arg(long, env = "NEAR_DA_NETWORK", default_value = "testnet")]
+    network: String,
+
+    /// NEAR account that signs the submissions, usually the sidecar's.
+    #[arg(long, env = "NEAR_DA_ACCOUNT_ID")]
+    submitter: String,
+
+    /// Light client verification endpoint. Without it, submissions are posted
+    /// as soon as they are final on NEAR.
+    #[arg(long, env = "NEAR_DA_LIGHT_CLIENT")]
+    light_client: Option<String>,
+
+    /// Where progress is recorded, so restarts never post twice.
+    #[arg(long, default_value = "relayer-state.jsonl")]
+    state: PathBuf,
+
+    /// L1 block to start reading `Submitted` events from, when the state file
+    /// has not read any.
+    #[arg(long, default_value_t = 0)]
+    from_block: u64,
+
+    /// Blocks to stay behind the L1 head.
+    #[arg(long, default_value_t = 2)]
+    confirmations: u64,
+
+    /// Seconds between polls.
+    #[arg(long, value_name = "SECONDS", default_value_t = 12)]
+    poll: u64,
+
+    /// Log the batches that would be posted, without sending anything or
+    /// writing to the state file.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+fn parse_secret(value: &str) -> std::result::Result<Secret<String>, std::convert::Infallible> {
+    Ok(value.to_string().into())
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = CliArgs::parse();
+
+    tracing_subscriber::fmt()
+        .with_target(false)
+        .with_env_filter(EnvFilter::from_default_env())
+        .compact()
+        .init();
+
+    let network: Network = args
+        .network
+        .as_str()
+        .try_into()
+        .map_err(|e: String| anyhow::anyhow!(e))?;
+    let near = Observer::new(&network, &args.submitter).map_err(|e| anyhow::anyhow!("{e:#}"))?;
+
+    let verifier: Box<dyn Verifier> = match args.light_client {
+        Some(url) => Box::new(LightClient::new(url, args.submitter)),
+        None => {
+            warn!("no --light-client, trusting NEAR finality alone");
+            Box::new(Finality)
+        }
+    };
+
+    let mut store = Store::open(&args.state).await?;
+    if args.dry_run {
+        info!("dry run, nothing is posted");
+        store = store.detach();
+    }
+
+    let contract = args
+        .l1_contract
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid L1 contract address {}", args.l1_contract))?;
+    let poll = Duration::from_secs(args.poll);
+    match args.l1_key {
+        Some(key) if !args.dry_run => {
+            let config = near_da_eth::Config {
+                rpc: args.l1_rpc,
+                contract,
+                key,
+            };
+            let l1 = near_da_eth::Client::new(&config)
+                .await
+                .map_err(|e| anyhow::anyhow!("failed to connect to L1: {e:#}"))?;
+            info!("posting to {:?}", l1.address());
+            run(
+                Relayer {
+                    near,
+                    verifier,
+                    l1,
+                    store,
+                    start_block: args.from_block,
+                    confirmations: args.confirmations,
+                    dry_run: false,
+                },
+                poll,
+            )
+            .await
+        }
+        _ => {
+            let l1 = near_da_eth::Client::read_only(&args.l1_rpc, contract)
+                .map_err(|e| anyhow::anyhow!("failed to connect to L1: {e:#}"))?;
+            run(
+                Relayer {
+                    near,
+                    verifier,
+                    l1,
+                    store,
+                    start_block: args.from_block,
+                    confirmations: args.confirmations,
+                    dry_run: true,
+                },
+                poll,
+            )
+            .await
+        }
+    }
+}
+
+async fn run<N: Near, V: Verifier, L: L1>(
+    mut relayer: Relayer<N, V, L>,
+    poll: Duration,
+) -> Result<()> {
+    let mut interval = tokio::time::interval(poll);
+    let mut terminate = signal(SignalKind::terminate())?;
+    loop {
+        // A poll in progress finishes first, so a post is never cut short
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                info!("received SIGINT, stopping");
+                return Ok(());
+            }
+            _ = terminate.recv() => {
+                info!("received SIGTERM, stopping");
+                return Ok(());
+            }
+            _ = interval.tick() => {
+                if let Err(e) = relayer.poll().await {
+                    error!("poll failed: {e:#}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,653 @@
+//! Moves each submission the contract was notified of through NEAR finality
+//! and verification to `notifyAvailable`.
+
+use async_trait::async_trait;
+use ethers::{providers::Middleware, types::TxHash};
+use near_da_eth::VerifiedBatch;
+use near_da_rpc::{near::status::Status, NotFound};
+use tracing::{debug, info, warn};
+
+use crate::{
+    store::{Id, Record, Store},
+    verifier::Verifier,
+    Result,
+};
+
+/// Most L1 blocks to read events from at once, RPC providers limit the range.
+pub const MAX_RANGE: u64 = 5_000;
+
+#[async_trait]
+pub trait Near: Send + Sync {
+    /// Fails with [`NotFound`] if NEAR does not know the transaction.
+    async fn status(&self, id: Id) -> Result<Status>;
+}
+
+#[async_trait]
+impl Near for near_da_rpc::near::status::Observer {
+    async fn status(&self, id: Id) -> Result<Status> {
+        near_da_rpc::near::status::Observer::status(self, id.into())
+            .await
+            .map_err(|e| match e.downcast() {
+                Ok(not_found) => anyhow::Error::new::<NotFound>(not_found),
+                Err(e) => anyhow::anyhow!("{e:#}"),
+            })
+    }
+}
+
+#[async_trait]
+pub trait L1: Send + Sync {
+    async fn block_number(&self) -> Result<u64>;
+    /// Ids from `Submitted` events between the blocks, inclusive.
+    async fn submitted(&self, from: u64, to: u64) -> Result<Vec<Id>>;
+    /// Ids from `IsAvailable` events between the blocks, inclusive.
+    async fn available(&self, from: u64, to: u64) -> Result<Vec<Id>>;
+    /// The sender's next nonce as of the latest block, and counting its
+    /// transactions still in the mempool.
+    async fn nonces(&self) -> Result<(u64, u64)>;
+    /// Send `notifyAvailable` with `nonce`, without waiting for it.
+    async fn send_available(&self, batch: VerifiedBatch, nonce: u64) -> Result<TxHash>;
+    /// Wait until `tx` is included, failing if it reverted.
+    async fn confirm(&self, tx: TxHash) -> Result<()>;
+    /// Whether `tx` succeeded, or `None` if it is not included.
+    async fn receipt(&self, tx: TxHash) -> Result<Option<bool>>;
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> L1 for near_da_eth::Client<M> {
+    async fn block_number(&self) -> Result<u64> {
+        near_da_eth::Client::block_number(self).await.map_err(eyre)
+    }
+
+    async fn submitted(&self, from: u64, to: u64) -> Result<Vec<Id>> {
+        Ok(self
+            .submitted_events(from, Some(to))
+            .await
+            .map_err(eyre)?
+            .into_iter()
+            .map(|event| event.transaction_id)
+            .collect())
+    }
+
+    async fn available(&self, from: u64, to: u64) -> Result<Vec<Id>> {
+        Ok(self
+            .available_events(from, Some(to))
+            .await
+            .map_err(eyre)?
+            .into_iter()
+            .map(|event| event.batch.id)
+            .collect())
+    }
+
+    async fn nonces(&self) -> Result<(u64, u64)> {
+        near_da_eth::Client::nonces(self).await.map_err(eyre)
+    }
+
+    async fn send_available(&self, batch: VerifiedBatch, nonce: u64) -> Result<TxHash> {
+        self.send_notify_available(batch, nonce).await.map_err(eyre)
+    }
+
+    async fn confirm(&self, tx: TxHash) -> Result<()> {
+        near_da_eth::Client::confirm(self, tx)
+            .await
+            .map(drop)
+            .map_err(eyre)
+    }
+
+    async fn receipt(&self, tx: TxHash) -> Result<Option<bool>> {
+        self.receipt_status(tx).await.map_err(eyre)
+    }
+}
+
+fn eyre(e: eyre::Report) -> anyhow::Error {
+    anyhow::anyhow!("{e:#}")
+}
+
+pub struct Relayer<N, V, L> {
+    pub near: N,
+    pub verifier: V,
+    pub l1: L,
+    pub store: Store,
+    /// Where to start reading L1 when the store has not read any blocks.
+    pub start_block: u64,
+    /// Blocks to stay behind the L1 head, in case of reorgs.
+    pub confirmations: u64,
+    /// Log the batches instead of posting them.
+    pub dry_run: bool,
+}
+
+impl<N: Near, V: Verifier, L: L1> Relayer<N, V, L> {
+    /// Settle posts that were interrupted, read new `Submitted` events and
+    /// post every batch that is ready.
+    pub async fn poll(&mut self) -> Result<()> {
+        self.recover().await?;
+        self.scan().await?;
+
+        for id in self.store.queued() {
+            if let Err(e) = self.relay(id).await {
+                warn!("failed to relay 0x{}: {e:#}", hex::encode(id));
+            }
+        }
+        self.store.compact_order();
+        Ok(())
+    }
+
+    /// A post may have landed even though the relayer did not see it finish,
+    /// so it is only queued again once it definitely didn't: its transaction
+    /// reverted, or its nonce was taken by another transaction or never
+    /// reached L1. A post queued again reuses the nonce, so if the lost
+    /// transaction turns up after all only one of them lands.
+    async fn recover(&mut self) -> Result<()> {
+        let posting = self.store.posting();
+        if posting.is_empty() {
+            return Ok(());
+        }
+        // Before the receipts, so a used nonce without a receipt means the
+        // transaction was replaced
+        let (mined, pending) = self.l1.nonces().await?;
+
+        // Posts only IsAvailable events can settle, from the block they started
+        let mut unsure = vec![];
+        for (id, post) in posting {
+            if let Some(tx) = post.tx {
+                match self.l1.receipt(tx).await? {
+                    Some(true) => {
+                        info!("0x{} was posted in {tx:?}", hex::encode(id));
+                        self.store
+                            .record(Record::Posted {
+                                id,
+                                tx: Some(format!("{tx:?}")),
+                            })
+                            .await?;
+                        continue;
+                    }
+                    Some(false) => {
+                        warn!("posting 0x{} reverted in {tx:?}", hex::encode(id));
+                        self.store.record(Record::Queued { id }).await?;
+                        continue;
+                    }
+                    None => {}
+                }
+            }
+            match post.nonce {
+                Some(nonce) if nonce < mined && post.tx.is_none() => unsure.push((id, post.block)),
+                Some(nonce) if nonce < mined => {
+                    warn!("posting 0x{} was replaced", hex::encode(id));
+                    self.store.record(Record::Queued { id }).await?;
+                }
+                Some(nonce) if nonce < pending => {
+                    debug!("posting 0x{} is still pending", hex::encode(id));
+                }
+                Some(_) => {
+                    warn!("posting 0x{} never reached L1", hex::encode(id));
+                    self.store.record(Record::Queued { id }).await?;
+                }
+                // Recorded before nonces were
+                None => unsure.push((id, post.block)),
+            }
+        }
+
+        let Some(from) = unsure.iter().map(|(_, block)| *block).min() else {
+            return Ok(());
+        };
+        let available = self.available_since(from).await?;
+        for (id, _) in unsure {
+            if available.contains(&id) {
+                info!("0x{} was already posted", hex::encode(id));
+                self.store.record(Record::Posted { id, tx: None }).await?;
+            } else {
+                self.store.record(Record::Queued { id }).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Ids from `IsAvailable` events since `from`, read [`MAX_RANGE`] blocks
+    /// at a time.
+    async fn available_since(&self, from: u64) -> Result<Vec<Id>> {
+        let head = self.l1.block_number().await?;
+        let mut ids = vec![];
+        let mut start = from;
+        while start <= head {
+            let end = head.min(start + MAX_RANGE - 1);
+            ids.extend(self.l1.available(start, end).await?);
+            start = end + 1;
+        }
+        Ok(ids)
+    }
+
+    async fn scan(&mut self) -> Result<()> {
+        let head = self
+            .l1
+            .block_number()
+            .await?
+            .saturating_sub(self.confirmations);
+        let from = self.store.next_block().unwrap_or(self.start_block);
+        if from > head {
+            return Ok(());
+        }
+        let to = head.min(from + MAX_RANGE - 1);
+
+        let ids = self.l1.submitted(from, to).await?;
+        debug!("{} submissions in L1 blocks {from}..={to}", ids.len());
+        for id in ids {
+            if self.store.stage(&id).is_none() {
+                self.store.record(Record::Queued { id }).await?;
+            }
+        }
+        self.store
+            .record(Record::Scanned { next_block: to + 1 })
+            .await
+    }
+
+    async fn relay(&mut self, id: Id) -> Result<()> {
+        match self.near.status(id).await {
+            Ok(Status::Final) => {}
+            Ok(Status::Pending) => return Ok(()),
+            Ok(Status::Failed(error)) => {
+                warn!("0x{} failed on NEAR, not posting: {error}", hex::encode(id));
+                return self.store.record(Record::Failed { id, error }).await;
+            }
+            Err(e) if e.is::<NotFound>() => {
+                debug!("0x{} is not on NEAR yet", hex::encode(id));
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        }
+
+        let Some(verify_tx_hash) = self.verifier.verify(id).await? else {
+            debug!("0x{} is not verified yet", hex::encode(id));
+            return Ok(());
+        };
+        let batch = VerifiedBatch {
+            id,
+            verify_tx_hash,
+            submit_tx_id: id,
+        };
+
+        if self.dry_run {
+            info!("would post {batch:?}");
+            return self.store.record(Record::Posted { id, tx: None }).await;
+        }
+
+        let block = self.l1.block_number().await?;
+        // Counting the mempool, so this can't replace a post still pending
+        let (_, nonce) = self.l1.nonces().await?;
+        self.store
+            .record(Record::Posting {
+                id,
+                block,
+                nonce: Some(nonce),
+            })
+            .await?;
+        let tx = self.l1.send_available(batch, nonce).await?;
+        self.store.record(Record::Sent { id, tx }).await?;
+        self.l1.confirm(tx).await?;
+        info!("posted 0x{} in {tx:?}", hex::encode(id));
+        self.store
+            .record(Record::Posted {
+                id,
+                tx: Some(format!("{tx:?}")),
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::Stage;
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    #[derive(Default)]
+    struct FakeNear(HashMap<Id, Status>);
+
+    #[async_trait]
+    impl Near for FakeNear {
+        async fn status(&self, id: Id) -> Result<Status> {
+            self.0
+                .get(&id)
+                .cloned()
+                .ok_or_else(|| NotFound(id.into()).into())
+        }
+    }
+
+    /// Verifies everything but `[9; 32]`.
+    struct FakeVerifier;
+
+    #[async_trait]
+    impl Verifier for FakeVerifier {
+        async fn verify(&self, id: Id) -> Result<Option<[u8; 32]>> {
+            Ok((id != [9; 32]).then_some([0xee; 32]))
+        }
+    }
+
+    /// L1 as seen by the relayer's account.
+    #[derive(Default)]
+    struct Chain {
+        /// The next nonce as of the latest block.
+        nonce: u64,
+        /// (nonce, tx, id) sent but not mined.
+        mempool: Vec<(u64, TxHash, Id)>,
+        receipts: HashMap<TxHash, bool>,
+        /// (block, id)
+        available: Vec<(u64, Id)>,
+        sent: u64,
+    }
+
+    impl Chain {
+        /// Mine everything in the mempool.
+        fn mine(&mut self, block: u64) {
+            for (nonce, tx, id) in std::mem::take(&mut self.mempool) {
+                assert_eq!(nonce, self.nonce, "nonce gap");
+                self.nonce += 1;
+                self.receipts.insert(tx, true);
+                self.available.push((block, id));
+            }
+        }
+    }
+
+    #[derive(Default, Clone)]
+    struct FakeL1 {
+        head: u64,
+        /// (block, id)
+        submitted: Vec<(u64, Id)>,
+        chain: Arc<Mutex<Chain>>,
+        /// Leave sent transactions in the mempool.
+        hold: bool,
+        /// Mine the transaction but fail waiting for it.
+        fail_confirm: bool,
+    }
+
+    #[async_trait]
+    impl L1 for FakeL1 {
+        async fn block_number(&self) -> Result<u64> {
+            Ok(self.head)
+        }
+
+        async fn submitted(&self, from: u64, to: u64) -> Result<Vec<Id>> {
+            Ok(self
+                .submitted
+                .iter()
+                .filter(|(block, _)| (from..=to).contains(block))
+                .map(|(_, id)| *id)
+                .collect())
+        }
+
+        async fn available(&self, from: u64, to: u64) -> Result<Vec<Id>> {
+            assert!(to - from < MAX_RANGE, "asked for {from}..={to}");
+            Ok(self
+                .chain
+                .lock()
+                .unwrap()
+                .available
+                .iter()
+                .filter(|(block, _)| (from..=to).contains(block))
+                .map(|(_, id)| *id)
+                .collect())
+        }
+
+        async fn nonces(&self) -> Result<(u64, u64)> {
+            let chain = self.chain.lock().unwrap();
+            Ok((chain.nonce, chain.nonce + chain.mempool.len() as u64))
+        }
+
+        async fn send_available(&self, batch: VerifiedBatch, nonce: u64) -> Result<TxHash> {
+            assert_eq!(batch.verify_tx_hash, [0xee; 32]);
+            let mut chain = self.chain.lock().unwrap();
+            chain.sent += 1;
+            let tx = TxHash::from_low_u64_be(chain.sent);
+            chain.mempool.push((nonce, tx, batch.id));
+            if !self.hold {
+                chain.mine(self.head);
+            }
+            Ok(tx)
+        }
+
+        async fn confirm(&self, tx: TxHash) -> Result<()> {
+            if self.fail_confirm || !self.chain.lock().unwrap().receipts.contains_key(&tx) {
+                anyhow::bail!("receipt timed out");
+            }
+            Ok(())
+        }
+
+        async fn receipt(&self, tx: TxHash) -> Result<Option<bool>> {
+            Ok(self.chain.lock().unwrap().receipts.get(&tx).copied())
+        }
+    }
+
+    fn relayer(near: FakeNear, l1: FakeL1) -> Relayer<FakeNear, FakeVerifier, FakeL1> {
+        Relayer {
+            near,
+            verifier: FakeVerifier,
+            l1,
+            store: Store::default(),
+            start_block: 0,
+            confirmations: 0,
+            dry_run: false,
+        }
+    }
+
+    fn posted(l1: &FakeL1) -> Vec<Id> {
+        l1.chain
+            .lock()
+            .unwrap()
+            .available
+            .iter()
+            .map(|(_, id)| *id)
+            .collect()
+    }
+
+    fn sent(l1: &FakeL1) -> u64 {
+        l1.chain.lock().unwrap().sent
+    }
+
+    #[tokio::test]
+    async fn test_posts_final_and_verified() {
+        let near = FakeNear(HashMap::from([
+            ([1; 32], Status::Final),
+            ([2; 32], Status::Pending),
+            ([3; 32], Status::Failed("panicked".into())),
+            ([9; 32], Status::Final),
+        ]));
+        let l1 = FakeL1 {
+            head: 10,
+            submitted: vec![(1, [1; 32]), (2, [2; 32]), (3, [3; 32]), (4, [9; 32])],
+            ..Default::default()
+        };
+        let mut relayer = relayer(near, l1.clone());
+        relayer.poll().await.unwrap();
+
+        assert_eq!(posted(&l1), vec![[1; 32]]);
+        assert_eq!(relayer.store.stage(&[3; 32]), Some(Stage::Failed));
+        assert_eq!(relayer.store.queued(), vec![[2; 32], [9; 32]]);
+        assert_eq!(relayer.store.next_block(), Some(11));
+
+        // Once final, the pending one is posted, and nothing is posted twice
+        relayer.near.0.insert([2; 32], Status::Final);
+        relayer.poll().await.unwrap();
+        assert_eq!(posted(&l1), vec![[1; 32], [2; 32]]);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_stays_queued() {
+        let l1 = FakeL1 {
+            head: 1,
+            submitted: vec![(1, [1; 32])],
+            ..Default::default()
+        };
+        let mut relayer = relayer(FakeNear::default(), l1.clone());
+        relayer.poll().await.unwrap();
+        assert!(posted(&l1).is_empty());
+        assert_eq!(relayer.store.queued(), vec![[1; 32]]);
+    }
+
+    #[tokio::test]
+    async fn test_confirmations_and_range() {
+        let l1 = FakeL1 {
+            head: MAX_RANGE * 2,
+            submitted: vec![(MAX_RANGE + 1, [1; 32])],
+            ..Default::default()
+        };
+        let near = FakeNear(HashMap::from([([1; 32], Status::Final)]));
+        let mut relayer = relayer(near, l1.clone());
+        relayer.confirmations = 5;
+
+        relayer.poll().await.unwrap();
+        assert_eq!(relayer.store.next_block(), Some(MAX_RANGE));
+        assert!(posted(&l1).is_empty());
+
+        relayer.poll().await.unwrap();
+        assert_eq!(relayer.store.next_block(), Some(MAX_RANGE * 2 - 4));
+        assert_eq!(posted(&l1), vec![[1; 32]]);
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_post_not_resent() {
+        let near = FakeNear(HashMap::from([([1; 32], Status::Final)]));
+        let l1 = FakeL1 {
+            head: 1,
+            submitted: vec![(1, [1; 32])],
+            fail_confirm: true,
+            ..Default::default()
+        };
+        let mut relayer = relayer(near, l1.clone());
+        relayer.poll().await.unwrap();
+        // Waiting for the receipt failed, but the batch landed
+        let posting = relayer.store.posting();
+        assert_eq!(posting.len(), 1);
+        assert_eq!(posting[0].1.tx, Some(TxHash::from_low_u64_be(1)));
+
+        relayer.poll().await.unwrap();
+        assert_eq!(relayer.store.stage(&[1; 32]), Some(Stage::Posted));
+        assert_eq!(posted(&l1), vec![[1; 32]]);
+        assert_eq!(sent(&l1), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pending_post_not_resent() {
+        let near = FakeNear(HashMap::from([([1; 32], Status::Final)]));
+        let l1 = FakeL1 {
+            head: 1,
+            submitted: vec![(1, [1; 32])],
+            hold: true,
+            ..Default::default()
+        };
+        let mut relayer = relayer(near, l1.clone());
+        relayer.poll().await.unwrap();
+        relayer.poll().await.unwrap();
+        assert_eq!(relayer.store.posting().len(), 1);
+        assert_eq!(sent(&l1), 1);
+
+        l1.chain.lock().unwrap().mine(2);
+        relayer.poll().await.unwrap();
+        assert_eq!(relayer.store.stage(&[1; 32]), Some(Stage::Posted));
+        assert_eq!(posted(&l1), vec![[1; 32]]);
+        assert_eq!(sent(&l1), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_post_resent() {
+        let near = FakeNear(HashMap::from([([1; 32], Status::Final)]));
+        let l1 = FakeL1 {
+            head: 1,
+            submitted: vec![(1, [1; 32])],
+            hold: true,
+            ..Default::default()
+        };
+        let mut relayer = relayer(near, l1.clone());
+        relayer.poll().await.unwrap();
+
+        // Evicted from the mempool, so it is sent again with the same nonce
+        l1.chain.lock().unwrap().mempool.clear();
+        relayer.l1.hold = false;
+        relayer.poll().await.unwrap();
+        assert_eq!(relayer.store.stage(&[1; 32]), Some(Stage::Posted));
+        assert_eq!(posted(&l1), vec![[1; 32]]);
+        assert_eq!(sent(&l1), 2);
+        assert_eq!(l1.chain.lock().unwrap().nonce, 1);
+    }
+
+    #[tokio::test]
+    async fn test_replaced_post_resent() {
+        let near = FakeNear(HashMap::from([([1; 32], Status::Final)]));
+        let l1 = FakeL1 {
+            head: 1,
+            submitted: vec![(1, [1; 32])],
+            hold: true,
+            ..Default::default()
+        };
+        let mut relayer = relayer(near, l1.clone());
+        relayer.poll().await.unwrap();
+
+        // Another transaction from the account took the nonce
+        {
+            let mut chain = l1.chain.lock().unwrap();
+            chain.mempool.clear();
+            chain.nonce += 1;
+        }
+        relayer.l1.hold = false;
+        relayer.poll().await.unwrap();
+        assert_eq!(relayer.store.stage(&[1; 32]), Some(Stage::Posted));
+        assert_eq!(posted(&l1), vec![[1; 32]]);
+        assert_eq!(sent(&l1), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unsent_post_settled_by_events() {
+        let l1 = FakeL1 {
+            head: MAX_RANGE * 3,
+            ..Default::default()
+        };
+        {
+            // The relayer stopped after its post with nonce 0 was sent, but
+            // before recording the transaction
+            let mut chain = l1.chain.lock().unwrap();
+            chain.nonce = 1;
+            chain.available.push((MAX_RANGE * 2, [1; 32]));
+        }
+        let mut relayer = relayer(FakeNear::default(), l1.clone());
+        for (id, nonce) in [([1; 32], Some(0)), ([2; 32], None)] {
+            relayer
+                .store
+                .record(Record::Posting {
+                    id,
+                    block: 1,
+                    nonce,
+                })
+                .await
+                .unwrap();
+        }
+        relayer
+            .store
+            .record(Record::Scanned {
+                next_block: MAX_RANGE * 3 + 1,
+            })
+            .await
+            .unwrap();
+
+        relayer.poll().await.unwrap();
+        assert_eq!(relayer.store.stage(&[1; 32]), Some(Stage::Posted));
+        // Without a nonce or an event it is queued again
+        assert_eq!(relayer.store.queued(), vec![[2; 32]]);
+        assert_eq!(sent(&l1), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run() {
+        let near = FakeNear(HashMap::from([([1; 32], Status::Final)]));
+        let l1 = FakeL1 {
+            head: 1,
+            submitted: vec![(1, [1; 32])],
+            ..Default::default()
+        };
+        let mut relayer = relayer(near, l1.clone());
+        relayer.dry_run = true;
+        relayer.poll().await.unwrap();
+        relayer.poll().await.unwrap();
+        assert!(posted(&l1).is_empty());
+        assert_eq!(relayer.store.stage(&[1; 32]), Some(Stage::Posted));
+    }
+}
@@ -0,0 +1,348 @@
+//! What the relayer has seen and posted, so a restart carries on where it
+//! left off and never posts a batch twice.
+//!
+//! Progress is appended to a file, one JSON record per line, and replayed on
+//! start. A batch is marked as posting, with the L1 nonce it is sent with,
+//! before `notifyAvailable` is sent, and its transaction is recorded as soon
+//! as it is sent. A crash mid-send is settled on restart from the receipt or
+//! the nonce rather than resent blindly.
+
+use anyhow::Context;
+use ethers::types::TxHash;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+use tokio::io::AsyncWriteExt;
+
+use crate::Result;
+
+/// A NEAR transaction id, as notified to the contract.
+pub type Id = [u8; 32];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Record {
+    /// L1 was read up to, but not including, this block.
+    Scanned { next_block: u64 },
+    /// A `Submitted` event for the transaction was seen.
+    Queued {
+        #[serde(with = "hex::serde")]
+        id: Id,
+    },
+    /// `notifyAvailable` is about to be sent with `nonce`, at or after L1
+    /// block `block`. Older state files have no nonce.
+    Posting {
+        #[serde(with = "hex::serde")]
+        id: Id,
+        block: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        nonce: Option<u64>,
+    },
+    /// `notifyAvailable` was sent in `tx`, which isn't known to be included.
+    Sent {
+        #[serde(with = "hex::serde")]
+        id: Id,
+        tx: TxHash,
+    },
+    /// The contract holds the batch. `tx` is unknown when the post was found
+    /// on L1 after a restart, or when it was a dry run.
+    Posted {
+        #[serde(with = "hex::serde")]
+        id: Id,
+        tx: Option<String>,
+    },
+    /// The NEAR transaction failed, so it is never posted.
+    Failed {
+        #[serde(with = "hex::serde")]
+        id: Id,
+        error: String,
+    },
+}
+
+/// A `notifyAvailable` that was started but isn't known to have landed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Post {
+    /// The L1 block the post started from.
+    pub block: u64,
+    pub nonce: Option<u64>,
+    /// Set once the transaction was sent.
+    pub tx: Option<TxHash>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Queued,
+    Posting(Post),
+    Posted,
+    Failed,
+}
+
+#[derive(Default)]
+pub struct Store {
+    next_block: Option<u64>,
+    stages: HashMap<Id, Stage>,
+    /// Ids in the order they were queued, including finished ones until the
+    /// next [`Store::compact_order`].
+    order: Vec<Id>,
+    /// Records are appended to this file. Without it, nothing outlives the
+    /// process.
+    file: Option<PathBuf>,
+}
+
+impl Store {
+    /// Open the store, replaying the records already in `path`.
+    pub async fn open(path: &Path) -> Result<Self> {
+        let mut store = Self::default();
+        match tokio::fs::read_to_string(path).await {
+            Ok(contents) => {
+                for (i, line) in contents.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let record = serde_json::from_str(line).with_context(|| {
+                        format!("{}:{} is not a valid record", path.display(), i + 1)
+                    })?;
+                    store.apply(record);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+        }
+        store.compact_order();
+        store.file = Some(path.to_path_buf());
+        Ok(store)
+    }
+
+    /// Stop writing to the file, e.g. for a dry run that should not affect
+    /// the next real one.
+    pub fn detach(mut self) -> Self {
+        self.file = None;
+        self
+    }
+
+    /// The first L1 block not read yet, if any were.
+    pub fn next_block(&self) -> Option<u64> {
+        self.next_block
+    }
+
+    pub fn stage(&self, id: &Id) -> Option<Stage> {
+        self.stages.get(id).copied()
+    }
+
+    /// Ids waiting for finality or verification, oldest first.
+    pub fn queued(&self) -> Vec<Id> {
+        self.in_stage(|stage| stage == Stage::Queued)
+    }
+
+    /// Ids that were being posted when the relayer stopped or the send
+    /// failed.
+    pub fn posting(&self) -> Vec<(Id, Post)> {
+        self.order
+            .iter()
+            .filter_map(|id| match self.stages[id] {
+                Stage::Posting(post) => Some((*id, post)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn in_stage(&self, f: impl Fn(Stage) -> bool) -> Vec<Id> {
+        self.order
+            .iter()
+            .filter(|id| f(self.stages[*id]))
+            .copied()
+            .collect()
+    }
+
+    pub async fn record(&mut self, record: Record) -> Result<()> {
+        if let Some(path) = &self.file {
+            let mut line = serde_json::to_vec(&record)?;
+            line.push(b'\n');
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await
+                .with_context(|| format!("failed to open {}", path.display()))?;
+            file.write_all(&line).await?;
+            file.sync_data().await?;
+        }
+        self.apply(record);
+        Ok(())
+    }
+
+    fn apply(&mut self, record: Record) {
+        let (id, stage) = match record {
+            Record::Scanned { next_block } => {
+                self.next_block = Some(next_block);
+                return;
+            }
+            Record::Queued { id } => (id, Stage::Queued),
+            Record::Posting { id, block, nonce } => (
+                id,
+                Stage::Posting(Post {
+                    block,
+                    nonce,
+                    tx: None,
+                }),
+            ),
+            Record::Sent { id, tx } => {
+                if let Some(Stage::Posting(post)) = self.stages.get_mut(&id) {
+                    post.tx = Some(tx);
+                }
+                return;
+            }
+            Record::Posted { id, .. } => (id, Stage::Posted),
+            Record::Failed { id, .. } => (id, Stage::Failed),
+        };
+        if self.stages.insert(id, stage).is_none() {
+            self.order.push(id);
+        }
+    }
+
+    /// Drop finished ids from the queue order, they stay in `stages` so they
+    /// are still recognised.
+    pub fn compact_order(&mut self) {
+        let stages = &self.stages;
+        self.order
+            .retain(|id| matches!(stages[id], Stage::Queued | Stage::Posting(_)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_memory() {
+        let mut store = Store::default();
+        assert_eq!(store.next_block(), None);
+
+        for id in [[1; 32], [2; 32], [3; 32]] {
+            store.record(Record::Queued { id }).await.unwrap();
+        }
+        store
+            .record(Record::Posting {
+                id: [2; 32],
+                block: 7,
+                nonce: Some(4),
+            })
+            .await
+            .unwrap();
+        store
+            .record(Record::Sent {
+                id: [2; 32],
+                tx: TxHash::repeat_byte(1),
+            })
+            .await
+            .unwrap();
+        store
+            .record(Record::Failed {
+                id: [3; 32],
+                error: "reverted".into(),
+            })
+            .await
+            .unwrap();
+        store
+            .record(Record::Scanned { next_block: 8 })
+            .await
+            .unwrap();
+
+        assert_eq!(store.next_block(), Some(8));
+        assert_eq!(store.queued(), vec![[1; 32]]);
+        assert_eq!(
+            store.posting(),
+            vec![(
+                [2; 32],
+                Post {
+                    block: 7,
+                    nonce: Some(4),
+                    tx: Some(TxHash::repeat_byte(1)),
+                }
+            )]
+        );
+        assert_eq!(store.stage(&[3; 32]), Some(Stage::Failed));
+        assert_eq!(store.stage(&[4; 32]), None);
+    }
+
+    #[tokio::test]
+    async fn test_persisted() {
+        let path = std::env::temp_dir().join("near-da-relayer-store.jsonl");
+        let _ = std::fs::remove_file(&path);
+
+        let mut store = Store::open(&path).await.unwrap();
+        store.record(Record::Queued { id: [1; 32] }).await.unwrap();
+        store.record(Record::Queued { id: [2; 32] }).await.unwrap();
+        store
+            .record(Record::Posting {
+                id: [1; 32],
+                block: 3,
+                nonce: Some(0),
+            })
+            .await
+            .unwrap();
+        store
+            .record(Record::Posted {
+                id: [1; 32],
+                tx: Some("0x01".into()),
+            })
+            .await
+            .unwrap();
+        store
+            .record(Record::Scanned { next_block: 4 })
+            .await
+            .unwrap();
+
+        let reopened = Store::open(&path).await.unwrap();
+        assert_eq!(reopened.next_block(), Some(4));
+        assert_eq!(reopened.stage(&[1; 32]), Some(Stage::Posted));
+        assert_eq!(reopened.queued(), vec![[2; 32]]);
+
+        // A detached store keeps the file as it was
+        let mut detached = reopened.detach();
+        detached
+            .record(Record::Posted {
+                id: [2; 32],
+                tx: None,
+            })
+            .await
+            .unwrap();
+        let reopened = Store::open(&path).await.unwrap();
+        assert_eq!(reopened.queued(), vec![[2; 32]]);
+    }
+
+    #[test]
+    fn test_record_format() {
+        let record = Record::Posting {
+            id: [0xab; 32],
+            block: 1,
+            nonce: None,
+        };
+        let line = serde_json::to_string(&record).unwrap();
+        assert_eq!(
+            line,
+            format!(
+                r#"{{"kind":"posting","id":"{}","block":1}}"#,
+                "ab".repeat(32)
+            )
+        );
+        assert_eq!(serde_json::from_str::<Record>(&line).unwrap(), record);
+
+        let record = Record::Sent {
+            id: [0xab; 32],
+            tx: TxHash::repeat_byte(0xcd),
+        };
+        let line = serde_json::to_string(&record).unwrap();
+        assert_eq!(
+            line,
+            format!(
+                r#"{{"kind":"sent","id":"{}","tx":"0x{}"}}"#,
+                "ab".repeat(32),
+                "cd".repeat(32)
+            )
+        );
+        assert_eq!(serde_json::from_str::<Record>(&line).unwrap(), record);
+    }
+}
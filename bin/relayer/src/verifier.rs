@@ -0,0 +1,135 @@
+//! Whether a final NEAR submission is proven, and by which L1 transaction.
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{store::Id, Result};
+
+#[async_trait]
+pub trait Verifier: Send + Sync {
+    /// The hash of the transaction that verified `id`, or `None` if it is not
+    /// verified yet.
+    async fn verify(&self, id: Id) -> Result<Option<[u8; 32]>>;
+}
+
+#[async_trait]
+impl<V: Verifier + ?Sized> Verifier for Box<V> {
+    async fn verify(&self, id: Id) -> Result<Option<[u8; 32]>> {
+        (**self).verify(id).await
+    }
+}
+
+/// Trusts NEAR finality alone. `verifyTxHash` is left empty.
+pub struct Finality;
+
+#[async_trait]
+impl Verifier for Finality {
+    async fn verify(&self, _id: Id) -> Result<Option<[u8; 32]>> {
+        Ok(Some([0; 32]))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct VerifyRequest<'a> {
+    #[serde(with = "hex::serde")]
+    transaction_id: Id,
+    sender: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyResponse {
+    verified: bool,
+    #[serde(default, with = "optional_hex")]
+    verify_tx_hash: Option<[u8; 32]>,
+}
+
+/// Asks a light client endpoint, which POSTs take a transaction id and its
+/// sender and answer with whether the inclusion proof checked out.
+pub struct LightClient {
+    http: reqwest::Client,
+    url: String,
+    sender: String,
+}
+
+impl LightClient {
+    pub fn new(url: String, sender: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url,
+            sender,
+        }
+    }
+}
+
+#[async_trait]
+impl Verifier for LightClient {
+    async fn verify(&self, id: Id) -> Result<Option<[u8; 32]>> {
+        let response: VerifyResponse = self
+            .http
+            .post(&self.url)
+            .json(&VerifyRequest {
+                transaction_id: id,
+                sender: &self.sender,
+            })
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .with_context(|| format!("light client {} failed", self.url))?
+            .json()
+            .await
+            .context("invalid light client response")?;
+        Ok(response
+            .verified
+            .then(|| response.verify_tx_hash.unwrap_or_default()))
+    }
+}
+
+mod optional_hex {
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<[u8; 32]>, D::Error> {
+        Option::<String>::deserialize(d)?
+            .map(|s| {
+                let mut hash = [0; 32];
+                hex::decode_to_slice(s.trim_start_matches("0x"), &mut hash)
+                    .map_err(serde::de::Error::custom)?;
+                Ok(hash)
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_request() {
+        let request = serde_json::to_value(VerifyRequest {
+            transaction_id: [1; 32],
+            sender: "da.testnet",
+        })
+        .unwrap();
+        assert_eq!(
+            request,
+            serde_json::json!({ "transaction_id": "01".repeat(32), "sender": "da.testnet" })
+        );
+    }
+
+    #[test]
+    fn test_verify_response() {
+        let response: VerifyResponse = serde_json::from_value(serde_json::json!({
+            "verified": true,
+            "verify_tx_hash": format!("0x{}", "02".repeat(32)),
+        }))
+        .unwrap();
+        assert!(response.verified);
+        assert_eq!(response.verify_tx_hash, Some([2; 32]));
+
+        let response: VerifyResponse =
+            serde_json::from_value(serde_json::json!({ "verified": false })).unwrap();
+        assert!(!response.verified);
+        assert_eq!(response.verify_tx_hash, None);
+    }
+}
@@ -0,0 +1,200 @@
+//! Polygon CDK data availability backend, for `NearDataAvailability.sol`.
+//!
+//! The sequence sender posts its batches, one NEAR transaction each, and puts
+//! the returned message in its L1 sequence transaction. The synchronizer
+//! reads the batches back with the batch hashes and that message. Like any
+//! other submission, the batches are passed to the L1 notifier if one is set.
+
+use axum::{extract::State, response::Json};
+use futures_util::TryStreamExt;
+use near_da_eth::{data_availability_message, parse_data_availability_message};
+use near_da_http_api_data::{
+    GetSequenceRequest, GetSequenceResponse, PostSequenceRequest, PostSequenceResponse,
+};
+use near_da_rpc::{BlobRef, CryptoHash, DataAvailability};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::{
+    commitments::keccak256,
+    plasma::{fetch_refs, invalid, CHUNK_SIZE},
+    AppError, AppState, NotConfigured, Result,
+};
+
+pub(crate) async fn post_sequence(
+    State(state): State<Arc<RwLock<AppState>>>,
+    Json(request): Json<PostSequenceRequest>,
+) -> std::result::Result<Json<PostSequenceResponse>, AppError> {
+    if request.batches.is_empty() {
+        return Err(invalid("sequence has no batches").into());
+    }
+    if let Some((i, batch)) = request
+        .batches
+        .iter()
+        .enumerate()
+        .find(|(_, batch)| batch.len() > CHUNK_SIZE)
+    {
+        return Err(invalid(format!(
+            "batch {i} is {} bytes, more than the {CHUNK_SIZE} that fit in a transaction",
+            batch.len()
+        ))
+        .into());
+    }
+
+    let drain = state.read().await.drain.clone();
+    let size = request.batches.iter().map(Vec::len).sum();
+    // One guard for the whole sequence, so shutdown never cuts it in half
+    let in_flight = drain.begin(None, size)?;
+
+    // Sequentially, as the transactions share the signer's nonce
+    let mut refs = Vec::with_capacity(request.batches.len());
+    for batch in request.batches {
+        let blob_hash = CryptoHash::hash_bytes(&batch);
+        refs.push(crate::submit_blob(&state, blob_hash, batch, &in_flight).await?);
+    }
+
+    Ok(Json(PostSequenceResponse {
+        data_availability_message: data_availability_message(&refs),
+    }))
+}
+
+pub(crate) async fn get_sequence(
+    State(state): State<Arc<RwLock<AppState>>>,
+    Json(request): Json<GetSequenceRequest>,
+) -> std::result::Result<Json<GetSequenceResponse>, AppError> {
+    let client = state.read().await.client.clone().ok_or(NotConfigured)?;
+    let batches = read_sequence(client, request).await?;
+    Ok(Json(GetSequenceResponse { batches }))
+}
+
+/// Fetch the batches in the message, checking each against its hash.
+async fn read_sequence<D>(da: Arc<D>, request: GetSequenceRequest) -> Result<Vec<Vec<u8>>>
+where
+    D: DataAvailability + Send + Sync + 'static,
+{
+    let refs: Vec<BlobRef> = parse_data_availability_message(&request.data_availability_message)
+        .map_err(|e| invalid(e.to_string()))?;
+    if refs.len() != request.batch_hashes.len() {
+        return Err(invalid(format!(
+            "message holds {} batches but {} batch hashes were given",
+            refs.len(),
+            request.batch_hashes.len()
+        ))
+        .into());
+    }
+
+    let batches = fetch_refs(da, refs).try_collect::<Vec<_>>().await?;
+    batches
+        .into_iter()
+        .zip(request.batch_hashes)
+        .enumerate()
+        .map(|(i, (batch, hash))| {
+            if keccak256(&batch) != hash {
+                anyhow::bail!("batch {i} does not match its hash 0x{}", hex::encode(hash));
+            }
+            Ok(batch.to_vec())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        plasma::CommitmentError,
+        tests::{state, MemoryDa},
+    };
+    use axum::{http::StatusCode, response::IntoResponse};
+    use near_da_rpc::Blob;
+
+    async fn da_with(batches: &[&[u8]]) -> (Arc<MemoryDa>, Vec<u8>) {
+        let da = Arc::new(MemoryDa::default());
+        let mut refs = vec![];
+        for batch in batches {
+            refs.push(da.submit(Blob::new(batch.to_vec())).await.unwrap().0);
+        }
+        (da, data_availability_message(&refs))
+    }
+
+    #[tokio::test]
+    async fn test_read_sequence() {
+        let (da, message) = da_with(&[b"one", b"two", b"one"]).await;
+        let batches = read_sequence(
+            da,
+            GetSequenceRequest {
+                batch_hashes: vec![keccak256(b"one"), keccak256(b"two"), keccak256(b"one")],
+                data_availability_message: message,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            batches,
+            vec![b"one".to_vec(), b"two".to_vec(), b"one".to_vec()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_sequence_hash_mismatch() {
+        let (da, message) = da_with(&[b"one", b"two"]).await;
+        let err = read_sequence(
+            da,
+            GetSequenceRequest {
+                batch_hashes: vec![keccak256(b"one"), keccak256(b"other")],
+                data_availability_message: message,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            err.to_string().starts_with("batch 1 does not match"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_sequence_invalid() {
+        let (da, message) = da_with(&[b"one"]).await;
+        for request in [
+            GetSequenceRequest {
+                batch_hashes: vec![keccak256(b"one"), keccak256(b"two")],
+                data_availability_message: message,
+            },
+            GetSequenceRequest {
+                batch_hashes: vec![keccak256(b"one")],
+                data_availability_message: vec![1; 31],
+            },
+        ] {
+            let err = read_sequence(da.clone(), request).await.unwrap_err();
+            assert!(matches!(
+                err.downcast_ref(),
+                Some(CommitmentError::Invalid(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_post_sequence_invalid() {
+        for batches in [vec![], vec![vec![1], vec![0; CHUNK_SIZE + 1]]] {
+            let response = post_sequence(State(state()), Json(PostSequenceRequest { batches }))
+                .await
+                .unwrap_err()
+                .into_response();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unconfigured() {
+        let response = post_sequence(
+            State(state()),
+            Json(PostSequenceRequest {
+                batches: vec![vec![1]],
+            }),
+        )
+        .await
+        .unwrap_err()
+        .into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
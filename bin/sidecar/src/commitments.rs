@@ -0,0 +1,145 @@
+//! Maps OP Alt-DA keccak256 commitments to the NEAR transactions holding the
+//! data. Unlike generic commitments, a keccak commitment says nothing about
+//! where the data is, so the sidecar has to remember it.
+
+use anyhow::Context;
+use near_da_rpc::BlobRef;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+use tokio::{io::AsyncWriteExt, sync::Mutex};
+
+use crate::Result;
+
+pub type Commitment = [u8; 32];
+
+pub fn keccak256(data: &[u8]) -> Commitment {
+    Keccak256::digest(data).into()
+}
+
+/// A line in the store file.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    commitment: String,
+    refs: Vec<BlobRef>,
+}
+
+#[derive(Default)]
+pub struct CommitmentStore {
+    refs: RwLock<HashMap<Commitment, Vec<BlobRef>>>,
+    /// Records are appended to this file, one JSON object per line.
+    file: Option<PathBuf>,
+    write: Mutex<()>,
+}
+
+impl CommitmentStore {
+    /// Open the store, loading the commitments already recorded in `path`.
+    pub async fn open(path: &Path) -> Result<Self> {
+        let mut refs = HashMap::new();
+        match tokio::fs::read_to_string(path).await {
+            Ok(contents) => {
+                for (i, line) in contents.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let record: Record = serde_json::from_str(line).with_context(|| {
+                        format!("{}:{} is not a valid record", path.display(), i + 1)
+                    })?;
+                    let commitment = hex::decode(&record.commitment)?.try_into().map_err(|_| {
+                        anyhow::anyhow!("{}:{} has an invalid commitment", path.display(), i + 1)
+                    })?;
+                    refs.insert(commitment, record.refs);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+        }
+        Ok(Self {
+            refs: RwLock::new(refs),
+            file: Some(path.to_path_buf()),
+            write: Mutex::new(()),
+        })
+    }
+
+    pub fn get(&self, commitment: &Commitment) -> Option<Vec<BlobRef>> {
+        self.refs.read().unwrap().get(commitment).cloned()
+    }
+
+    pub async fn insert(&self, commitment: Commitment, refs: Vec<BlobRef>) -> Result<()> {
+        if let Some(path) = &self.file {
+            let mut line = serde_json::to_vec(&Record {
+                commitment: hex::encode(commitment),
+                refs: refs.clone(),
+            })?;
+            line.push(b'\n');
+
+            let _write = self.write.lock().await;
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await
+                .with_context(|| format!("failed to open {}", path.display()))?;
+            file.write_all(&line).await?;
+            file.flush().await?;
+        }
+        self.refs.write().unwrap().insert(commitment, refs);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keccak256() {
+        assert_eq!(
+            hex::encode(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_in_memory() {
+        let store = CommitmentStore::default();
+        let commitment = keccak256(b"data");
+        assert!(store.get(&commitment).is_none());
+
+        store
+            .insert(commitment, vec![BlobRef::new([1; 32])])
+            .await
+            .unwrap();
+        assert_eq!(store.get(&commitment).unwrap()[0].transaction_id, [1; 32]);
+    }
+
+    #[tokio::test]
+    async fn test_persisted() {
+        let path = std::env::temp_dir().join("near-da-sidecar-commitments.jsonl");
+        let _ = std::fs::remove_file(&path);
+        let commitment = keccak256(b"data");
+
+        let store = CommitmentStore::open(&path).await.unwrap();
+        store
+            .insert(
+                commitment,
+                vec![BlobRef::new([1; 32]), BlobRef::new([2; 32])],
+            )
+            .await
+            .unwrap();
+        store
+            .insert(keccak256(b"other"), vec![BlobRef::new([3; 32])])
+            .await
+            .unwrap();
+
+        let reopened = CommitmentStore::open(&path).await.unwrap();
+        let refs = reopened.get(&commitment).unwrap();
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[1].transaction_id, [2; 32]);
+        assert!(reopened.get(&keccak256(b"other")).is_some());
+    }
+}
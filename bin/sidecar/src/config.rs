@@ -0,0 +1,372 @@
+use anyhow::{anyhow, bail, Context};
+use near_da_http_api_data::ConfigureClientRequest;
+use near_da_rpc::near::{config::Config, signer, Client};
+use serde_json::{Map, Value};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::RwLock,
+    time::Interval,
+};
+use tracing::{error, info, warn};
+
+use crate::{AppState, Result};
+
+/// Prefix of the environment variables that can provide configuration fields,
+/// e.g. `NEAR_DA_SECRET_KEY` for `secret_key`.
+pub const ENV_PREFIX: &str = "NEAR_DA_";
+
+/// Fields that must be present, either in the file or the environment.
+const REQUIRED_FIELDS: [&str; 3] = ["account_id", "contract_id", "network"];
+/// At least one of these must be present to sign transactions.
+const KEY_FIELDS: [&str; 2] = ["secret_key", "key"];
+
+#[derive(Clone, Copy)]
+enum FieldKind {
+    /// The variable is taken verbatim as a string.
+    String,
+    /// The variable is parsed as JSON, e.g. `true` or `{"version":1,"id":1}`.
+    Json,
+}
+
+const ENV_FIELDS: [(&str, FieldKind); 9] = [
+    ("account_id", FieldKind::String),
+    ("secret_key", FieldKind::String),
+    ("key", FieldKind::Json),
+    ("contract_id", FieldKind::String),
+    ("network", FieldKind::String),
+    ("namespace", FieldKind::Json),
+    ("mode", FieldKind::String),
+    ("should_cache", FieldKind::Json),
+    ("registry_id", FieldKind::String),
+];
+
+pub fn env_var(field: &str) -> String {
+    format!("{ENV_PREFIX}{}", field.to_uppercase())
+}
+
+/// Write the environment variables into the configuration object. When
+/// `overwrite` is false, only fields missing from the object are filled in.
+fn overlay_env<F>(object: &mut Map<String, Value>, lookup: F, overwrite: bool) -> Result<()>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    for (field, kind) in ENV_FIELDS {
+        if !overwrite && object.get(field).is_some_and(|v| !v.is_null()) {
+            continue;
+        }
+        let var = env_var(field);
+        if let Some(raw) = lookup(&var) {
+            let value = match kind {
+                FieldKind::String => Value::String(raw),
+                FieldKind::Json => serde_json::from_str(&raw)
+                    .with_context(|| format!("{var} is not valid JSON"))?,
+            };
+            object.insert(field.to_string(), value);
+        }
+    }
+    Ok(())
+}
+
+fn parse_request(object: Map<String, Value>) -> Result<ConfigureClientRequest> {
+    let is_missing = |field: &str| object.get(field).unwrap_or(&Value::Null).is_null();
+    let describe = |field: &str| format!("`{field}` (or {})", env_var(field));
+
+    let mut missing = REQUIRED_FIELDS
+        .into_iter()
+        .filter(|field| is_missing(field))
+        .map(describe)
+        .collect::<Vec<_>>();
+    if KEY_FIELDS.into_iter().all(is_missing) {
+        missing.push(KEY_FIELDS.map(describe).join(" or "));
+    }
+    if !missing.is_empty() {
+        bail!("missing configuration: {}", missing.join(", "));
+    }
+    serde_json::from_value(Value::Object(object)).context("invalid configuration")
+}
+
+/// Load the configuration from an optional file, with environment variables
+/// taking precedence over the file. Returns `None` if neither provides
+/// anything, so the client can still be configured via PUT /configure.
+pub async fn load(path: Option<&Path>) -> Result<Option<ConfigureClientRequest>> {
+    load_with(path, |var| std::env::var(var).ok()).await
+}
+
+async fn load_with<F>(path: Option<&Path>, lookup: F) -> Result<Option<ConfigureClientRequest>>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut object = match path {
+        Some(path) => {
+            let contents = tokio::fs::read_to_string(path)
+                .await
+                .with_context(|| format!("failed to read config file {}", path.display()))?;
+            match serde_json::from_str(&contents)
+                .with_context(|| format!("failed to parse config file {}", path.display()))?
+            {
+                Value::Object(object) => object,
+                _ => bail!("config file {} must contain a JSON object", path.display()),
+            }
+        }
+        None => Map::new(),
+    };
+    overlay_env(&mut object, lookup, true)?;
+
+    if object.is_empty() {
+        return Ok(None);
+    }
+    parse_request(object).map(Some)
+}
+
+/// Parse a PUT /configure body. Fields missing from the request, such as the
+/// secret key, are taken from the environment.
+pub fn from_request(request: Value) -> Result<ConfigureClientRequest> {
+    from_request_with(request, |var| std::env::var(var).ok())
+}
+
+fn from_request_with<F>(request: Value, lookup: F) -> Result<ConfigureClientRequest>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let Value::Object(mut object) = request else {
+        bail!("configuration must be a JSON object");
+    };
+    overlay_env(&mut object, lookup, false)?;
+    parse_request(object)
+}
+
+/// Build a client, checking the network and key up front so a bad
+/// configuration is reported here rather than on the first submission.
+pub fn build_client(request: ConfigureClientRequest) -> Result<Client> {
+    let config = Config::try_from(request).map_err(|e| anyhow!("{e}"))?;
+    if config.contract.is_empty() {
+        bail!("`contract_id` must not be empty");
+    }
+    let signer =
+        signer::from_config(&config).map_err(|e| anyhow!("invalid signer configuration: {e}"))?;
+    Ok(Client::with_signer(&config, signer))
+}
+
+/// Swap the client in the application state. Requests already in flight keep
+/// using the client they started with.
+pub async fn apply(state: &RwLock<AppState>, request: ConfigureClientRequest) -> Result<()> {
+    let should_cache = request.should_cache;
+    let client = Arc::new(build_client(request)?);
+
+    let mut state = state.write().await;
+    state.should_cache = should_cache;
+    if !should_cache {
+        state.cache.invalidate_all();
+    }
+    state.client = Some(client);
+    Ok(())
+}
+
+/// Reload the configuration on SIGHUP and, if `poll` is set, whenever the
+/// config file's modification time changes. A failed reload is logged and the
+/// current client is kept.
+pub async fn watch(state: Arc<RwLock<AppState>>, path: Option<PathBuf>, poll: Option<Duration>) {
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            error!("failed to install SIGHUP handler, config reload disabled: {e}");
+            return;
+        }
+    };
+    let mut interval = poll.filter(|_| path.is_some()).map(tokio::time::interval);
+    let mut modified = modified_at(path.as_deref()).await;
+
+    loop {
+        let reason = tokio::select! {
+            _ = hangup.recv() => "received SIGHUP",
+            _ = tick(&mut interval) => {
+                let current = modified_at(path.as_deref()).await;
+                if current == modified {
+                    continue;
+                }
+                modified = current;
+                "config file changed"
+            }
+        };
+        info!("reloading client configuration: {reason}");
+
+        match load(path.as_deref()).await {
+            Ok(Some(request)) => match apply(&state, request).await {
+                Ok(()) => info!("client configuration reloaded"),
+                Err(e) => error!("failed to reload client, keeping the current one: {e:#}"),
+            },
+            Ok(None) => warn!("no configuration found, keeping the current client"),
+            Err(e) => error!("failed to load configuration, keeping the current client: {e:#}"),
+        }
+    }
+}
+
+async fn tick(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+async fn modified_at(path: Option<&Path>) -> Option<SystemTime> {
+    tokio::fs::metadata(path?).await.ok()?.modified().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_da_http_api_data::KeySource;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |var| vars.get(var).cloned()
+    }
+
+    fn write_config(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("near-da-sidecar-{name}.json"));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_load_env_overrides_file() {
+        let path = write_config(
+            "env-overrides",
+            r#"{"account_id":"file.testnet","contract_id":"file.testnet","network":"testnet"}"#,
+        );
+
+        let request = load_with(
+            Some(&path),
+            lookup(&[
+                ("NEAR_DA_SECRET_KEY", "ed25519:secret"),
+                ("NEAR_DA_ACCOUNT_ID", "env.testnet"),
+                ("NEAR_DA_SHOULD_CACHE", "true"),
+                ("NEAR_DA_NAMESPACE", r#"{"version":1,"id":2}"#),
+            ]),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(request.account_id, "env.testnet");
+        assert_eq!(
+            request.secret_key.as_ref().map(|key| key.expose().as_str()),
+            Some("ed25519:secret")
+        );
+        assert_eq!(request.contract_id, "file.testnet");
+        assert!(request.should_cache);
+        assert_eq!(request.namespace.map(|ns| ns.id), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_load_key_from_env() {
+        let request = load_with(
+            None,
+            lookup(&[
+                ("NEAR_DA_ACCOUNT_ID", "env.testnet"),
+                ("NEAR_DA_CONTRACT_ID", "env.testnet"),
+                ("NEAR_DA_NETWORK", "testnet"),
+                (
+                    "NEAR_DA_KEY",
+                    r#"{"type":"file","path":"/keys/env.testnet.json"}"#,
+                ),
+            ]),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(request.secret_key, None);
+        assert_eq!(
+            request.key,
+            Some(KeySource::File {
+                path: "/keys/env.testnet.json".to_string()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_nothing() {
+        assert!(load_with(None, lookup(&[])).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_load_missing_fields() {
+        let err = load_with(None, lookup(&[("NEAR_DA_NETWORK", "testnet")]))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("`secret_key` (or NEAR_DA_SECRET_KEY)"),
+            "{err}"
+        );
+        assert!(!err.contains("`network`"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_load_missing_file() {
+        let err = load_with(Some(Path::new("/does/not/exist.json")), lookup(&[]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("/does/not/exist.json"));
+    }
+
+    #[tokio::test]
+    async fn test_load_invalid_json() {
+        let path = write_config("invalid-json", "{ not json");
+        assert!(load_with(Some(&path), lookup(&[])).await.is_err());
+
+        let path = write_config("not-object", "[]");
+        assert!(load_with(Some(&path), lookup(&[])).await.is_err());
+    }
+
+    #[test]
+    fn test_from_request_fills_missing_secret() {
+        let request = from_request_with(
+            json!({
+                "account_id": "request.testnet",
+                "contract_id": "request.testnet",
+                "network": "testnet",
+            }),
+            lookup(&[
+                ("NEAR_DA_SECRET_KEY", "ed25519:secret"),
+                ("NEAR_DA_ACCOUNT_ID", "env.testnet"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            request.secret_key.as_ref().map(|key| key.expose().as_str()),
+            Some("ed25519:secret")
+        );
+        assert_eq!(request.account_id, "request.testnet");
+    }
+
+    #[test]
+    fn test_build_client_invalid_key() {
+        let request = ConfigureClientRequest {
+            account_id: "account.testnet".to_string(),
+            secret_key: Some("secret_key".to_string().into()),
+            key: None,
+            contract_id: "account.testnet".to_string(),
+            network: "testnet".to_string(),
+            namespace: None,
+            mode: None,
+            registry_id: None,
+            should_cache: false,
+        };
+        assert!(build_client(request).is_err());
+    }
+}
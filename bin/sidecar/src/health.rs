@@ -0,0 +1,131 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use near_da_rpc::near::health::Check;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::AppState;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct Readiness {
+    pub ready: bool,
+    /// Why the checks weren't run, e.g. while draining.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub checks: Vec<Check>,
+}
+
+impl Readiness {
+    fn not_ready(reason: &str) -> Self {
+        Self {
+            ready: false,
+            reason: Some(reason.to_string()),
+            checks: vec![],
+        }
+    }
+}
+
+impl IntoResponse for Readiness {
+    fn into_response(self) -> Response {
+        let status = if self.ready {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+/// Reports whether the sidecar is draining, kept for existing deployments.
+pub(crate) async fn health(State(state): State<Arc<RwLock<AppState>>>) -> Response {
+    if state.read().await.drain.is_draining() {
+        (StatusCode::SERVICE_UNAVAILABLE, "draining").into_response()
+    } else {
+        "".into_response()
+    }
+}
+
+/// The process is up and serving requests.
+pub(crate) async fn live() -> &'static str {
+    ""
+}
+
+/// The sidecar can accept submissions: a client is configured, NEAR RPC and
+/// the archive respond, the signer's access key is usable and the contract is
+/// deployed.
+pub(crate) async fn ready(State(state): State<Arc<RwLock<AppState>>>) -> Readiness {
+    let client = {
+        let state = state.read().await;
+        if state.drain.is_draining() {
+            return Readiness::not_ready("draining");
+        }
+        state.client.clone()
+    };
+    let Some(client) = client else {
+        return Readiness::not_ready("client is not configured");
+    };
+
+    let checks = client.readiness().await;
+    Readiness {
+        ready: checks.iter().all(|check| check.ok),
+        reason: None,
+        checks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::state;
+
+    #[tokio::test]
+    async fn test_live() {
+        assert_eq!(live().await.into_response().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_ready_unconfigured() {
+        let readiness = ready(State(state())).await;
+        assert_eq!(readiness, Readiness::not_ready("client is not configured"));
+        assert_eq!(
+            readiness.into_response().status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ready_draining() {
+        let state = state();
+        state.read().await.drain.start_draining();
+        assert_eq!(ready(State(state)).await, Readiness::not_ready("draining"));
+    }
+
+    #[test]
+    fn test_readiness_status() {
+        let check = |ok| Check {
+            name: "rpc".to_string(),
+            ok,
+            error: None,
+        };
+        let readiness = |checks: Vec<Check>| Readiness {
+            ready: checks.iter().all(|check| check.ok),
+            reason: None,
+            checks,
+        };
+        assert_eq!(
+            readiness(vec![check(true)]).into_response().status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            readiness(vec![check(true), check(false)])
+                .into_response()
+                .status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+}
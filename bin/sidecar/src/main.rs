@@ -8,12 +8,9 @@ use axum::{
 use clap::Parser;
 use futures_util::stream::{self, StreamExt};
 use moka::future::Cache;
-use near_da_http_api_data::ConfigureClientRequest;
-use near_da_rpc::{
-    near::{config::Config, Client},
-    Blob, BlobRef, CryptoHash, DataAvailability,
-};
-use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use near_da_http_api_data::Secret;
+use near_da_rpc::{near::Client, Blob, BlobRef, CryptoHash, DataAvailability, NotFound};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 use tokio::sync::RwLock;
 use tower::Layer;
 use tower_http::{
@@ -24,7 +21,13 @@ use tower_http::{
 use tracing::{debug, Level};
 use tracing_subscriber::EnvFilter;
 
+mod cdk;
+mod commitments;
+mod config;
+mod health;
+mod nitro;
 mod plasma;
+mod shutdown;
 
 pub type Result<T> = anyhow::Result<T>;
 
@@ -36,54 +39,77 @@ struct CliArgs {
     port: u16,
 
     /// Path to the client configuration. If not specified, the client can be
-    /// configured via PUT /config after starting the server.
-    #[arg(short, long)]
+    /// configured via PUT /config after starting the server. Any field can also
+    /// be set through the environment, e.g. NEAR_DA_SECRET_KEY, which takes
+    /// precedence over the file.
+    #[arg(short, long, env = "NEAR_DA_CONFIG")]
     config: Option<PathBuf>,
+
+    /// Poll the config file every SECONDS and reload the client when it
+    /// changes. The configuration is always reloaded on SIGHUP.
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    watch: Option<u64>,
+
+    /// On SIGTERM, how long to wait for in-flight submissions before exiting.
+    #[arg(long, value_name = "SECONDS", default_value_t = 30)]
+    shutdown_timeout: u64,
+
+    /// Where to record submissions that were still in flight at shutdown.
+    #[arg(long, default_value = "pending-submissions.json")]
+    pending_file: PathBuf,
+
+    /// Where to record which NEAR transactions hold the data for each keccak256
+    /// commitment. Without it the mapping is kept in memory and lost on restart.
+    #[arg(long)]
+    commitment_store: Option<PathBuf>,
+
+    /// Ethereum RPC endpoint. With the contract and key set, every blob
+    /// submitted to NEAR is recorded on `NearDataAvailability.sol` with
+    /// `notifySubmitted`.
+    #[arg(long, env = "NEAR_DA_L1_RPC", requires_all = ["l1_contract", "l1_key"])]
+    l1_rpc: Option<String>,
+
+    /// Address of the `NearDataAvailability` contract.
+    #[arg(long, env = "NEAR_DA_L1_CONTRACT", requires = "l1_rpc")]
+    l1_contract: Option<String>,
+
+    /// Hex encoded key of an L1 account with the notifier role.
+    #[arg(long, env = "NEAR_DA_L1_KEY", hide_env_values = true, requires = "l1_rpc", value_parser = parse_secret)]
+    l1_key: Option<Secret<String>>,
+}
+
+fn parse_secret(value: &str) -> std::result::Result<Secret<String>, std::convert::Infallible> {
+    Ok(value.to_string().into())
 }
 
 /// Represents the application's state.
 struct AppState {
-    /// An optional HTTP client for making network requests.
-    client: Option<Client>,
+    /// An optional HTTP client for making network requests. Handlers clone the
+    /// reference so the client can be swapped without interrupting them.
+    client: Option<Arc<Client>>,
     /// A cache for storing and retrieving data using cryptographic hashes as keys.
     /// TODO: choose a faster cache key implementation.
     cache: Cache<CryptoHash, BlobRef>,
     should_cache: bool,
-}
-
-fn config_request_to_client_config(request: ConfigureClientRequest) -> Result<Config> {
-    Ok(Config {
-        key: near_da_rpc::near::config::KeyType::SecretKey(request.account_id, request.secret_key),
-        contract: request.contract_id,
-        network: request
-            .network
-            .as_str()
-            .try_into()
-            .map_err(|e: String| anyhow::anyhow!(e))?,
-        namespace: request
-            .namespace
-            .map(|ns| near_da_primitives::Namespace::new(ns.version, ns.id)),
-        mode: request.mode.unwrap_or_default(),
-    })
+    /// In-flight submissions, so shutdown can wait for them to land.
+    drain: Arc<shutdown::Drain>,
+    /// NEAR transactions for each keccak256 commitment.
+    commitments: Arc<commitments::CommitmentStore>,
+    /// Notifies L1 of each blob submitted to NEAR.
+    l1: Option<near_da_eth::Notifier>,
 }
 
 async fn configure_client(
     State(state): State<Arc<RwLock<AppState>>>,
-    Json(request): Json<ConfigureClientRequest>,
+    Json(request): Json<serde_json::Value>,
 ) -> anyhow::Result<(), AppError> {
+    let request = config::from_request(request)?;
+    // Secrets are redacted by their Debug implementation
     debug!("client configuration request: {:?}", request);
+    let (account_id, contract_id) = (request.account_id.clone(), request.contract_id.clone());
 
-    tracing::info!("client configuration set: {:?}", request);
-
-    let mut state = state.write().await;
-
-    state.should_cache = request.should_cache;
-    if !request.should_cache {
-        state.cache.invalidate_all();
-    }
-
-    let client = Client::new(&config_request_to_client_config(request)?);
-    state.client = Some(client);
+    config::apply(&state, request).await?;
+    tracing::info!("client configured for {account_id} on {contract_id}");
 
     Ok(())
 }
@@ -93,34 +119,64 @@ async fn get(
     Query(request): Query<BlobRef>,
 ) -> anyhow::Result<Json<near_da_http_api_data::Blob>, AppError> {
     debug!("getting blob: {:?}", request);
-    let app_state = state.read().await;
-    let client = app_state
-        .client
-        .as_ref()
-        .ok_or(anyhow::anyhow!("client is not configured"))?;
-
-    let blob = client
-        .get(CryptoHash(request.transaction_id))
-        .await
-        .map_err(|e| anyhow::anyhow!("failed to get blob: {}", e))?
-        .0;
+    let client = state.read().await.client.clone().ok_or(NotConfigured)?;
+
+    let blob = fetch_blob(&*client, &request).await?;
 
     let blob = near_da_http_api_data::Blob { data: blob.data };
 
     Ok(Json(blob))
 }
 
+/// Fetch a blob, keeping [`NotFound`] so it can be reported as a 404.
+pub(crate) async fn fetch_blob<D: DataAvailability + ?Sized>(
+    da: &D,
+    blob_ref: &BlobRef,
+) -> Result<Blob> {
+    da.get(CryptoHash(blob_ref.transaction_id))
+        .await
+        .map(|read| read.0)
+        .map_err(|e| match e.downcast::<NotFound>() {
+            Ok(not_found) => anyhow::Error::new(not_found),
+            Err(e) => anyhow::anyhow!("failed to get blob: {}", e),
+        })
+}
+
 async fn submit(
     State(state): State<Arc<RwLock<AppState>>>,
     Json(request): Json<Blob>,
 ) -> anyhow::Result<Json<BlobRef>, AppError> {
     debug!("submitting blob: {:?}", request);
-    let app_state = state.read().await;
+    let drain = state.read().await.drain.clone();
 
     let blob_hash = CryptoHash::hash_bytes(request.data.as_slice());
+    // Held until the submission reaches its mode, so shutdown can wait for it
+    let in_flight = drain.begin(Some(&blob_hash), request.data.len())?;
+    Ok(submit_blob(&state, blob_hash, request.data, &in_flight)
+        .await?
+        .into())
+}
 
-    let blob_ref = if app_state.should_cache {
-        app_state.cache.get(&blob_hash).await.map(|blob_ref| {
+/// Submit `data` in a single transaction, as part of the request `in_flight`
+/// tracks.
+pub(crate) async fn submit_blob(
+    state: &RwLock<AppState>,
+    blob_hash: CryptoHash,
+    data: Vec<u8>,
+    in_flight: &shutdown::InFlight,
+) -> anyhow::Result<BlobRef, AppError> {
+    let (client, cache, should_cache, l1) = {
+        let app_state = state.read().await;
+        (
+            app_state.client.clone(),
+            app_state.cache.clone(),
+            app_state.should_cache,
+            app_state.l1.clone(),
+        )
+    };
+
+    let blob_ref = if should_cache {
+        cache.get(&blob_hash).await.map(|blob_ref| {
             debug!("blob is cached, returning: {:?}", blob_ref);
             blob_ref
         })
@@ -130,13 +186,12 @@ async fn submit(
     let blob_ref = if let Some(blob_ref) = blob_ref {
         blob_ref
     } else {
-        let client = app_state
-            .client
-            .as_ref()
-            .ok_or(anyhow::anyhow!("client is not configured"))?;
+        let client = client.ok_or(NotConfigured)?;
 
         let blob_ref = client
-            .submit(near_da_primitives::Blob::new(request.data))
+            .submit_signed(near_da_primitives::Blob::new(data), &|id| {
+                in_flight.signed(id)
+            })
             .await
             .map_err(|e| anyhow::anyhow!("failed to submit blobs: {}", e))?
             .0;
@@ -146,13 +201,16 @@ async fn submit(
             hex::encode(blob_ref.transaction_id)
         );
 
-        if app_state.should_cache {
+        if should_cache {
             debug!("caching {blob_hash}");
-            app_state.cache.insert(blob_hash, blob_ref.clone()).await;
+            cache.insert(blob_hash, blob_ref.clone()).await;
+        }
+        if let Some(l1) = l1 {
+            l1.submitted(blob_ref.clone());
         }
         blob_ref
     };
-    Ok(blob_ref.into())
+    Ok(blob_ref)
 }
 
 pub(crate) fn stream_response<T: Into<axum::body::Bytes> + Send + Sync + 'static>(
@@ -165,11 +223,46 @@ pub(crate) fn stream_response<T: Into<axum::body::Bytes> + Send + Sync + 'static
         .unwrap()
 }
 
+#[derive(Debug)]
+struct NotConfigured;
+
+impl std::fmt::Display for NotConfigured {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("client is not configured")
+    }
+}
+
+impl std::error::Error for NotConfigured {}
+
 // https://github.com/tokio-rs/axum/blob/d7258bf009194cf2f242694e673759d1dbf8cfc0/examples/anyhow-error-response/src/main.rs#L34-L57
+#[derive(Debug)]
 struct AppError(pub anyhow::Error);
 
+impl AppError {
+    /// Alt-DA clients rely on 404 for missing data and 5xx for failures they
+    /// should retry.
+    fn status(&self) -> StatusCode {
+        if self.0.is::<shutdown::ShuttingDown>() || self.0.is::<NotConfigured>() {
+            StatusCode::SERVICE_UNAVAILABLE
+        } else if self.0.is::<NotFound>() {
+            StatusCode::NOT_FOUND
+        } else {
+            match self.0.downcast_ref::<plasma::CommitmentError>() {
+                Some(plasma::CommitmentError::Invalid(_)) => StatusCode::BAD_REQUEST,
+                Some(plasma::CommitmentError::Unknown(_)) => StatusCode::NOT_FOUND,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        }
+    }
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let status = self.status();
+        if status != StatusCode::INTERNAL_SERVER_ERROR {
+            tracing::warn!("{}", self.0);
+            return (status, self.0.to_string()).into_response();
+        }
         tracing::error!("{}", self.0);
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -188,8 +281,49 @@ where
     }
 }
 
+fn router(state: Arc<RwLock<AppState>>) -> Router {
+    Router::new()
+        .route("/health", routing::get(health::health))
+        .route("/health/live", routing::get(health::live))
+        .route("/health/ready", routing::get(health::ready))
+        .route("/configure", routing::put(configure_client))
+        .route("/blob", routing::get(get))
+        .route("/blob", routing::post(submit))
+        .route("/plasma/get/:transaction_id", routing::get(plasma::get))
+        .route("/plasma/put", routing::post(plasma::submit))
+        .route(
+            "/plasma/put/:commitment",
+            routing::post(plasma::submit_keccak),
+        )
+        // OP Alt-DA server routes
+        .route("/get/:commitment", routing::get(plasma::get))
+        .route("/put", routing::post(plasma::submit))
+        .route("/put/:commitment", routing::post(plasma::submit_keccak))
+        // Polygon CDK routes
+        .route("/cdk/post-sequence", routing::post(cdk::post_sequence))
+        .route("/cdk/get-sequence", routing::post(cdk::get_sequence))
+        // Arbitrum Nitro DAS routes
+        .route("/nitro", routing::post(nitro::rpc))
+        .route("/nitro/get-by-hash/:hash", routing::get(nitro::get_by_hash))
+        .route("/nitro/health", routing::get(nitro::health))
+        .route(
+            "/nitro/expiration-policy",
+            routing::get(nitro::expiration_policy),
+        )
+        .with_state(state)
+        .layer(
+            TraceLayer::new_for_http()
+                .on_failure(trace::DefaultOnFailure::new().level(Level::WARN))
+                .on_failure(|_error: ServerErrorsFailureClass, _latency, _request: &_| {
+                    tracing::warn!("request failed {:?}", _error);
+                })
+                .make_span_with(trace::DefaultMakeSpan::new().level(Level::INFO))
+                .on_response(trace::DefaultOnResponse::new().level(Level::INFO)),
+        )
+}
+
 #[tokio::main]
-async fn main() {
+async fn main() -> Result<()> {
     let args = CliArgs::parse();
 
     tracing_subscriber::fmt()
@@ -198,57 +332,117 @@ async fn main() {
         .compact()
         .init();
 
-    let mut state = AppState {
+    let commitments = match args.commitment_store {
+        Some(ref path) => commitments::CommitmentStore::open(path).await?,
+        None => {
+            tracing::warn!("no --commitment-store, keccak commitments are lost on restart");
+            Default::default()
+        }
+    };
+    let l1 = match (args.l1_rpc, args.l1_contract, args.l1_key) {
+        (Some(rpc), Some(contract), Some(key)) => {
+            let config = near_da_eth::Config {
+                rpc,
+                contract: contract
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("invalid L1 contract address {contract}"))?,
+                key,
+            };
+            let l1 = near_da_eth::Client::new(&config)
+                .await
+                .map_err(|e| anyhow::anyhow!("failed to connect to L1: {e:#}"))?;
+            tracing::info!("notifying {:?} of submissions", l1.address());
+            Some(near_da_eth::Notifier::spawn(Arc::new(l1)))
+        }
+        _ => None,
+    };
+    let state = Arc::new(RwLock::new(AppState {
         client: None,
         cache: Cache::new(2048), // (32 * 2) * 2048 = 128kb
         should_cache: true,
-    };
-
-    if let Some(path) = args.config {
-        let file_contents = tokio::fs::read_to_string(path).await.unwrap();
-        let config_parse = serde_json::from_str::<ConfigureClientRequest>(&file_contents)
-            .unwrap_or_else(|e| panic!("failed to parse config: {}", e));
-        state.client = Some(Client::new(
-            &config_request_to_client_config(config_parse).unwrap(),
-        ));
+        drain: Default::default(),
+        commitments: Arc::new(commitments),
+        l1,
+    }));
+    let drain = state.read().await.drain.clone();
+    shutdown::warn_pending(&args.pending_file).await;
+
+    if let Some(request) = config::load(args.config.as_deref()).await? {
+        config::apply(&state, request).await?;
     }
 
-    let state = Arc::new(RwLock::new(state));
+    tokio::spawn(config::watch(
+        state.clone(),
+        args.config,
+        args.watch.map(Duration::from_secs),
+    ));
 
-    let router = Router::new()
-        .route("/health", routing::get(|| async { "" }))
-        .route("/configure", routing::put(configure_client))
-        .route("/blob", routing::get(get))
-        .route("/blob", routing::post(submit))
-        .route("/plasma/get/:transaction_id", routing::get(plasma::get))
-        .route("/plasma/put", routing::post(plasma::submit))
-        .with_state(state)
-        .layer(
-            TraceLayer::new_for_http()
-                .on_failure(trace::DefaultOnFailure::new().level(Level::WARN))
-                .on_failure(|_error: ServerErrorsFailureClass, _latency, _request: &_| {
-                    tracing::warn!("request failed {:?}", _error);
-                })
-                .make_span_with(trace::DefaultMakeSpan::new().level(Level::INFO))
-                .on_response(trace::DefaultOnResponse::new().level(Level::INFO)),
-        );
-    let router_normalized = NormalizePathLayer::trim_trailing_slash().layer(router);
+    let router_normalized = NormalizePathLayer::trim_trailing_slash().layer(router(state));
 
     let addr = SocketAddr::from(([0; 4], args.port));
     tracing::info!("listening on {}", addr);
 
-    axum::Server::bind(&addr)
-        .serve(router_normalized.into_make_service())
-        .await
-        .unwrap();
+    // The server keeps running while draining, so reads and /health are still
+    // served until the in-flight submissions finish.
+    let server =
+        tokio::spawn(axum::Server::bind(&addr).serve(router_normalized.into_make_service()));
+    tokio::select! {
+        result = server => result??,
+        result = shutdown::signal_received() => {
+            result?;
+            shutdown::drain(
+                &drain,
+                Duration::from_secs(args.shutdown_timeout),
+                &args.pending_file,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
+    use near_da_http_api_data::ConfigureClientRequest;
     use near_da_primitives::Mode;
+    use near_da_rpc::near::config::{Config, KeyType};
+    use near_da_rpc::{Read, SubmitResult};
+    use std::{collections::HashMap, sync::Mutex};
 
     use super::*;
 
+    /// Keeps blobs in memory, the transaction id is the keccak256 of the data.
+    #[derive(Default)]
+    pub(crate) struct MemoryDa(Mutex<HashMap<[u8; 32], Vec<u8>>>);
+
+    #[async_trait::async_trait]
+    impl DataAvailability for MemoryDa {
+        async fn submit(&self, blob: Blob) -> eyre::Result<SubmitResult> {
+            let id = crate::commitments::keccak256(&blob.data);
+            self.0.lock().unwrap().insert(id, blob.data);
+            Ok(SubmitResult(BlobRef::new(id)))
+        }
+
+        async fn get(&self, transaction_id: CryptoHash) -> eyre::Result<Read> {
+            let data = self.0.lock().unwrap().get(&transaction_id.0).cloned();
+            data.map(|data| Read(Blob::new(data)))
+                .ok_or_else(|| near_da_rpc::NotFound(transaction_id).into())
+        }
+    }
+
+    /// An unconfigured sidecar for handler tests.
+    pub(crate) fn state() -> Arc<RwLock<AppState>> {
+        Arc::new(RwLock::new(AppState {
+            client: None,
+            cache: Cache::new(16),
+            should_cache: false,
+            drain: Default::default(),
+            commitments: Default::default(),
+            l1: None,
+        }))
+    }
+
     // #[test]
     // fn test_config_request_to_config() {
     //     let request = ConfigureClientRequest {
@@ -319,19 +513,28 @@ mod tests {
     //     assert_eq!(config.mode, Mode::default());
     // }
 
+    #[test]
+    fn test_watch_rejects_zero() {
+        assert!(CliArgs::try_parse_from(["near-da-sidecar", "--watch", "0"]).is_err());
+        let args = CliArgs::try_parse_from(["near-da-sidecar", "--watch", "5"]).unwrap();
+        assert_eq!(args.watch, Some(5));
+    }
+
     #[test]
     fn test_config_request_to_config_default_mode() {
         let request = ConfigureClientRequest {
             account_id: "account_id".to_string(),
-            secret_key: "secret_key".to_string(),
+            secret_key: Some("secret_key".to_string().into()),
+            key: None,
             contract_id: "contract_id".to_string(),
             network: "mainnet".to_string(),
             namespace: None,
             mode: None,
+            registry_id: None,
             should_cache: false,
         };
 
-        let config = config_request_to_client_config(request).unwrap();
+        let config = Config::try_from(request).unwrap();
 
         assert_eq!(config.mode, Mode::default());
     }
@@ -340,16 +543,156 @@ mod tests {
     fn test_config_request_to_config_invalid_network() {
         let request = ConfigureClientRequest {
             account_id: "account_id".to_string(),
-            secret_key: "secret_key".to_string(),
+            secret_key: Some("secret_key".to_string().into()),
+            key: None,
             contract_id: "contract_id".to_string(),
             network: "invalid_network".to_string(),
             namespace: None,
             mode: None,
+            registry_id: None,
             should_cache: false,
         };
 
-        let result = config_request_to_client_config(request);
+        let result = Config::try_from(request);
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_config_request_to_config_no_key() {
+        let request = ConfigureClientRequest {
+            account_id: "account_id".to_string(),
+            secret_key: None,
+            key: None,
+            contract_id: "contract_id".to_string(),
+            network: "mainnet".to_string(),
+            namespace: None,
+            mode: None,
+            registry_id: None,
+            should_cache: false,
+        };
+
+        assert!(Config::try_from(request).is_err());
+    }
+
+    #[test]
+    fn test_config_request_to_config_key_source() {
+        let request: ConfigureClientRequest = serde_json::from_str(
+            r#"{
+                "account_id": "account_id",
+                "secret_key": "ignored",
+                "key": { "type": "external", "socket": "/tmp/signer.sock", "public_key": "ed25519:pk" },
+                "contract_id": "contract_id",
+                "network": "mainnet"
+            }"#,
+        )
+        .unwrap();
+
+        let config = Config::try_from(request).unwrap();
+
+        match config.key {
+            KeyType::External(account, public_key, socket) => {
+                assert_eq!(account, "account_id");
+                assert_eq!(public_key, "ed25519:pk");
+                assert_eq!(socket, PathBuf::from("/tmp/signer.sock"));
+            }
+            key => panic!("expected an external key, got {key:?}"),
+        }
+    }
+
+    /// Collects everything written by the tracing subscriber.
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_configure_client_does_not_log_secrets() {
+        let secret = "ed25519:2T3R1CBAsKQN1Xa9fN9aL1epRwnxgbvk5RAy3sNAdh1n4nfkD9gyGKDLECBMVkwg1zPeewPG9eoX8XVRC6tr6nDt";
+        let seed = "correct horse battery staple";
+
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(Level::TRACE)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let state = state();
+        for key in [
+            serde_json::json!({ "secret_key": secret }),
+            serde_json::json!({ "key": { "type": "secret_key", "secret_key": secret } }),
+            serde_json::json!({ "key": { "type": "seed", "seed": seed } }),
+        ] {
+            let mut request = serde_json::json!({
+                "account_id": "throwawaykey.testnet",
+                "contract_id": "throwawaykey.testnet",
+                "network": "testnet",
+            });
+            request
+                .as_object_mut()
+                .unwrap()
+                .extend(key.as_object().unwrap().clone());
+            assert!(configure_client(State(state.clone()), Json(request))
+                .await
+                .is_ok());
+        }
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains("throwawaykey.testnet"), "{logs}");
+        assert!(!logs.contains(secret), "{logs}");
+        assert!(!logs.contains(seed), "{logs}");
+    }
+
+    #[test]
+    fn test_configure_request_redacted() {
+        let request: ConfigureClientRequest = serde_json::from_value(serde_json::json!({
+            "account_id": "account_id",
+            "secret_key": "ed25519:secret",
+            "contract_id": "contract_id",
+            "network": "mainnet",
+        }))
+        .unwrap();
+
+        assert!(!format!("{request:?}").contains("ed25519:secret"));
+        assert!(!serde_json::to_string(&request)
+            .unwrap()
+            .contains("ed25519:secret"));
+        assert_eq!(
+            request.secret_key.as_ref().map(|key| key.expose().as_str()),
+            Some("ed25519:secret")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_draining() {
+        let state = state();
+        assert_eq!(
+            health::health(State(state.clone())).await.status(),
+            StatusCode::OK
+        );
+
+        state.read().await.drain.start_draining();
+        assert_eq!(
+            health::health(State(state.clone())).await.status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        let response = submit(State(state), Json(Blob::new(vec![1, 2, 3])))
+            .await
+            .err()
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
 }
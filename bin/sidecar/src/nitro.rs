@@ -0,0 +1,442 @@
+//! An Arbitrum Nitro Data Availability Server backed by NEAR, so AnyTrust and
+//! Orbit chains can post batches through the sidecar.
+//!
+//! Batches are stored with the `das_store` JSON-RPC method and read back with
+//! `das_getByHash` or the REST `/get-by-hash` route Nitro's readers use. The
+//! data hash is the flat keccak256 of the batch, which Nitro accepts alongside
+//! its tree hash. The NEAR transactions holding each batch are recorded in the
+//! [`CommitmentStore`] under that hash.
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use futures_util::StreamExt;
+use near_da_eth::Notifier;
+use near_da_rpc::{near::Client, Blob, BlobRef, CryptoHash, DataAvailability};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_with::{base64::Base64, serde_as};
+use std::{fmt, sync::Arc};
+use tokio::sync::RwLock;
+
+use crate::{
+    commitments::{keccak256, Commitment, CommitmentStore},
+    plasma::{decode_hex, fetch_refs, invalid, CommitmentError, CHUNK_SIZE},
+    shutdown::Drain,
+    AppError, AppState, NotConfigured, Result,
+};
+
+/// The batch is hashed with plain keccak256 rather than Nitro's tree hash.
+pub const CERTIFICATE_VERSION: u64 = 0;
+/// NEAR archival nodes keep transactions forever.
+pub const EXPIRATION_POLICY: &str = "KeepForever";
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const SERVER_ERROR: i64 = -32000;
+
+/// Nitro's `StoreResult`, plus the NEAR transactions holding the batch which
+/// Nitro ignores. There is no committee, so the keyset, signers and signature
+/// are empty.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Certificate {
+    pub data_hash: String,
+    pub timeout: String,
+    pub signers_mask: String,
+    pub keyset_hash: String,
+    pub sig: String,
+    pub version: String,
+    pub near_refs: Vec<String>,
+}
+
+impl Certificate {
+    fn new(data_hash: Commitment, timeout: u64, refs: &[BlobRef]) -> Self {
+        Self {
+            data_hash: format!("0x{}", hex::encode(data_hash)),
+            timeout: format!("{timeout:#x}"),
+            signers_mask: "0x0".to_string(),
+            keyset_hash: format!("0x{}", hex::encode([0u8; 32])),
+            sig: "0x".to_string(),
+            version: format!("{CERTIFICATE_VERSION:#x}"),
+            near_refs: refs
+                .iter()
+                .map(|blob_ref| format!("0x{}", hex::encode(blob_ref.transaction_id)))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RpcRequest {
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Vec<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl fmt::Display) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(SERVER_ERROR, e)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Result(Value),
+    Error(RpcError),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+impl RpcResponse {
+    fn new(id: Value, result: std::result::Result<Value, RpcError>) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            outcome: match result {
+                Ok(result) => Outcome::Result(result),
+                Err(error) => Outcome::Error(error),
+            },
+        }
+    }
+}
+
+/// The body of a `/get-by-hash` response.
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetByHashResponse {
+    #[serde_as(as = "Base64")]
+    pub data: Vec<u8>,
+}
+
+fn parse_hash(hash: &str) -> Result<Commitment> {
+    decode_hex(hash)?
+        .try_into()
+        .map_err(|_| invalid("data hash must be 32 bytes").into())
+}
+
+fn parse_u64(value: &str) -> Result<u64> {
+    let digits = value
+        .strip_prefix("0x")
+        .ok_or_else(|| anyhow::anyhow!("{value} is missing the 0x prefix"))?;
+    Ok(u64::from_str_radix(digits, 16)?)
+}
+
+/// The positional parameter `i` as a string, failing with invalid params.
+fn param<'a>(params: &'a [Value], i: usize, name: &str) -> std::result::Result<&'a str, RpcError> {
+    params
+        .get(i)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("missing {name}")))
+}
+
+fn invalid_param(name: &str) -> impl FnOnce(anyhow::Error) -> RpcError + '_ {
+    move |e| RpcError::new(INVALID_PARAMS, format!("invalid {name}: {e}"))
+}
+
+/// Serves the DAS methods on top of a [`DataAvailability`] implementation.
+pub struct Das<D> {
+    da: Option<Arc<D>>,
+    commitments: Arc<CommitmentStore>,
+    drain: Arc<Drain>,
+    l1: Option<Notifier>,
+}
+
+impl Das<Client> {
+    async fn from_state(state: &RwLock<AppState>) -> Self {
+        let state = state.read().await;
+        Self::new(
+            state.client.clone(),
+            state.commitments.clone(),
+            state.drain.clone(),
+            state.l1.clone(),
+        )
+    }
+}
+
+impl<D: DataAvailability + Send + Sync + 'static> Das<D> {
+    pub fn new(
+        da: Option<Arc<D>>,
+        commitments: Arc<CommitmentStore>,
+        drain: Arc<Drain>,
+        l1: Option<Notifier>,
+    ) -> Self {
+        Self {
+            da,
+            commitments,
+            drain,
+            l1,
+        }
+    }
+
+    fn da(&self) -> Result<Arc<D>> {
+        self.da.clone().ok_or_else(|| NotConfigured.into())
+    }
+
+    /// Submit `message` in transactions of at most [`CHUNK_SIZE`]. A batch
+    /// that was already stored isn't submitted again.
+    pub async fn store(&self, message: Vec<u8>, timeout: u64) -> Result<Certificate> {
+        let data_hash = keccak256(&message);
+        if let Some(refs) = self.commitments.get(&data_hash) {
+            return Ok(Certificate::new(data_hash, timeout, &refs));
+        }
+
+        let da = self.da()?;
+        let in_flight = self
+            .drain
+            .begin(Some(&CryptoHash(data_hash)), message.len())?;
+        let mut refs = vec![];
+        // An empty batch is still stored, as a single empty transaction
+        for chunk in message
+            .chunks(CHUNK_SIZE)
+            .chain(message.is_empty().then_some(&[][..]))
+        {
+            let blob_ref = da
+                .submit_signed(Blob::new(chunk.to_vec()), &|id| in_flight.signed(id))
+                .await
+                .map_err(|e| anyhow::anyhow!("failed to submit batch: {}", e))?
+                .0;
+            if let Some(l1) = &self.l1 {
+                l1.submitted(blob_ref.clone());
+            }
+            refs.push(blob_ref);
+        }
+        self.commitments.insert(data_hash, refs.clone()).await?;
+
+        Ok(Certificate::new(data_hash, timeout, &refs))
+    }
+
+    pub async fn get_by_hash(&self, data_hash: Commitment) -> Result<Vec<u8>> {
+        let refs = self
+            .commitments
+            .get(&data_hash)
+            .ok_or(CommitmentError::Unknown(data_hash))?;
+
+        let mut chunks = Box::pin(fetch_refs(self.da()?, refs));
+        let mut data = vec![];
+        while let Some(chunk) = chunks.next().await {
+            data.extend_from_slice(&chunk?);
+        }
+        if keccak256(&data) != data_hash {
+            anyhow::bail!(
+                "data for 0x{} does not match its hash",
+                hex::encode(data_hash)
+            );
+        }
+        Ok(data)
+    }
+
+    /// Handle a JSON-RPC request body.
+    pub async fn handle(&self, body: &[u8]) -> RpcResponse {
+        let request = match serde_json::from_slice::<Value>(body) {
+            Ok(request) => request,
+            Err(e) => return RpcResponse::new(Value::Null, Err(RpcError::new(PARSE_ERROR, e))),
+        };
+        let id = request.get("id").cloned().unwrap_or_default();
+        match serde_json::from_value::<RpcRequest>(request) {
+            Ok(request) => {
+                let result = self.call(&request).await;
+                RpcResponse::new(request.id, result)
+            }
+            Err(e) => RpcResponse::new(id, Err(RpcError::new(INVALID_REQUEST, e))),
+        }
+    }
+
+    async fn call(&self, request: &RpcRequest) -> std::result::Result<Value, RpcError> {
+        let params = &request.params;
+        match request.method.as_str() {
+            // The signature is only checked by Nitro's own servers when
+            // configured, anyone able to reach the sidecar may store
+            "das_store" => {
+                let message =
+                    decode_hex(param(params, 0, "message")?).map_err(invalid_param("message"))?;
+                let timeout = match params.get(1) {
+                    Some(_) => {
+                        parse_u64(param(params, 1, "timeout")?).map_err(invalid_param("timeout"))?
+                    }
+                    None => 0,
+                };
+                let certificate = self.store(message, timeout).await?;
+                Ok(serde_json::to_value(certificate).map_err(anyhow::Error::from)?)
+            }
+            "das_getByHash" => {
+                let data_hash =
+                    parse_hash(param(params, 0, "hash")?).map_err(invalid_param("hash"))?;
+                let data = self.get_by_hash(data_hash).await?;
+                Ok(Value::String(format!("0x{}", hex::encode(data))))
+            }
+            "das_healthCheck" => {
+                self.da()?;
+                Ok(Value::Null)
+            }
+            method => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("the method {method} does not exist"),
+            )),
+        }
+    }
+}
+
+pub(crate) async fn rpc(State(state): State<Arc<RwLock<AppState>>>, body: Bytes) -> Response {
+    let das = Das::from_state(&state).await;
+    Json(das.handle(&body).await).into_response()
+}
+
+pub(crate) async fn get_by_hash(
+    State(state): State<Arc<RwLock<AppState>>>,
+    Path(hash): Path<String>,
+) -> std::result::Result<Json<GetByHashResponse>, AppError> {
+    let data_hash = parse_hash(&hash)?;
+    let das = Das::from_state(&state).await;
+    Ok(Json(GetByHashResponse {
+        data: das.get_by_hash(data_hash).await?,
+    }))
+}
+
+pub(crate) async fn health(
+    State(state): State<Arc<RwLock<AppState>>>,
+) -> std::result::Result<(), AppError> {
+    Das::from_state(&state).await.da()?;
+    Ok(())
+}
+
+pub(crate) async fn expiration_policy() -> Response {
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "expiration-policy": EXPIRATION_POLICY })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{state, MemoryDa};
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    fn das(da: Option<MemoryDa>) -> Das<MemoryDa> {
+        Das::new(
+            da.map(Arc::new),
+            Default::default(),
+            Default::default(),
+            None,
+        )
+    }
+
+    #[derive(Deserialize)]
+    struct Case {
+        name: String,
+        /// A JSON-RPC request, or a string sent as the raw body.
+        request: Value,
+        /// Errors are compared by code only.
+        response: RpcResponse,
+    }
+
+    /// Replays Nitro DAS client requests against a sidecar backed by memory,
+    /// in order, as later cases read what earlier ones stored.
+    #[tokio::test]
+    async fn test_conformance() {
+        let cases: Vec<Case> =
+            serde_json::from_str(include_str!("testdata/nitro-das-requests.json")).unwrap();
+        let das = das(Some(MemoryDa::default()));
+
+        for case in cases {
+            let body = match case.request {
+                Value::String(body) => body.into_bytes(),
+                request => serde_json::to_vec(&request).unwrap(),
+            };
+            let response = das.handle(&body).await;
+            assert_eq!(response.jsonrpc, "2.0", "{}", case.name);
+            assert_eq!(response.id, case.response.id, "{}", case.name);
+            match (response.outcome, case.response.outcome) {
+                (Outcome::Error(error), Outcome::Error(expected)) => {
+                    assert_eq!(
+                        error.code, expected.code,
+                        "{}: {}",
+                        case.name, error.message
+                    )
+                }
+                (outcome, expected) => assert_eq!(outcome, expected, "{}", case.name),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_chunks_and_deduplicates() {
+        let das = das(Some(MemoryDa::default()));
+        let message = (0..CHUNK_SIZE * 2 + 1).map(|i| i as u8).collect::<Vec<_>>();
+
+        let certificate = das.store(message.clone(), 10).await.unwrap();
+        assert_eq!(certificate.near_refs.len(), 3);
+        assert_eq!(certificate, das.store(message.clone(), 10).await.unwrap());
+        assert_eq!(das.get_by_hash(keccak256(&message)).await.unwrap(), message);
+
+        let certificate = das.store(vec![], 0).await.unwrap();
+        assert_eq!(certificate.near_refs.len(), 1);
+        assert!(das.get_by_hash(keccak256(&[])).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unconfigured() {
+        let response = das(None)
+            .handle(
+                br#"{"jsonrpc":"2.0","id":1,"method":"das_store","params":["0x01","0x0","0x"]}"#,
+            )
+            .await;
+        assert_eq!(
+            response.outcome,
+            Outcome::Error(RpcError::new(SERVER_ERROR, "client is not configured"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_routes() {
+        let router = crate::router(state());
+        let get = |uri: &str| {
+            let router = router.clone();
+            let request = Request::get(uri).body(Body::empty()).unwrap();
+            async move { router.oneshot(request).await.unwrap().status() }
+        };
+
+        assert_eq!(get("/nitro/expiration-policy").await, StatusCode::OK);
+        assert_eq!(get("/nitro/health").await, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            get("/nitro/get-by-hash/0x1234").await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            get(&format!("/nitro/get-by-hash/0x{}", hex::encode([7; 32]))).await,
+            StatusCode::NOT_FOUND
+        );
+    }
+}
@@ -1,31 +1,71 @@
-use anyhow::anyhow;
+use anyhow::Context;
 use axum::{
-    extract::{BodyStream, Path, Query, State},
+    body::{boxed, Bytes, StreamBody},
+    extract::{BodyStream, Path, State},
     response::Response,
 };
-use futures_util::stream::StreamExt;
+use futures_util::{
+    stream::{self, StreamExt},
+    Stream,
+};
 use itertools::Itertools;
-use near_da_rpc::{Blob, BlobRef};
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use near_da_rpc::{BlobRef, CryptoHash, DataAvailability};
+use sha3::{Digest, Keccak256};
+use std::{collections::HashMap, fmt, future::Future, sync::Arc};
+use tokio::{sync::RwLock, task::JoinHandle};
 
-use crate::{stream_response, AppError, AppState};
+use crate::{
+    commitments::Commitment, fetch_blob, shutdown::InFlight, stream_response, AppError, AppState,
+    NotConfigured,
+};
 
 // https://github.com/ethereum-optimism/specs/discussions/135
 pub const DA_SELECTOR: u8 = 0x6e;
 // https://github.com/ethereum-optimism/optimism/blob/457f33f4fdda9373dcf2839619ebf67182ee5057/op-plasma/commitment.go#L37
 pub const OP_PLASMA_GENERIC_COMMITMENT: u8 = 1;
+/// The commitment is keccak256 of the data, the sidecar maps it to the NEAR
+/// transactions.
+pub const OP_PLASMA_KECCAK_COMMITMENT: u8 = 0;
+
+/// Payloads are split into transactions of at most this size, well under
+/// NEAR's transaction size limit.
+pub const CHUNK_SIZE: usize = 1024 * 1024;
+/// How many transactions are fetched at once when reading a payload.
+pub const FETCH_CONCURRENCY: usize = 8;
+
+/// Errors the batcher or op-node should see as a client error or a miss,
+/// rather than the sidecar failing.
+#[derive(Debug)]
+pub enum CommitmentError {
+    Invalid(String),
+    /// A keccak commitment the sidecar holds no data for.
+    Unknown(Commitment),
+}
+
+impl fmt::Display for CommitmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(reason) => write!(f, "invalid commitment: {reason}"),
+            Self::Unknown(commitment) => {
+                write!(f, "unknown commitment 0x{}", hex::encode(commitment))
+            }
+        }
+    }
+}
+
+impl std::error::Error for CommitmentError {}
+
+pub(crate) fn invalid(reason: impl Into<String>) -> CommitmentError {
+    CommitmentError::Invalid(reason.into())
+}
 
 pub fn strip_plasma_bytes(bytes: Vec<u8>) -> super::Result<Vec<u8>> {
-    bytes
+    let stripped = bytes
         .strip_prefix(&[OP_PLASMA_GENERIC_COMMITMENT])
-        .ok_or_else(|| anyhow!("invalid plasma commitment"))
-        .and_then(|stripped| {
-            stripped
-                .strip_prefix(&[DA_SELECTOR])
-                .ok_or_else(|| anyhow!("invalid DA selector, should be {DA_SELECTOR}"))
-        })
-        .map(Into::into)
+        .ok_or_else(|| invalid("unsupported commitment type"))?
+        .strip_prefix(&[DA_SELECTOR])
+        .ok_or_else(|| invalid(format!("invalid DA selector, should be {DA_SELECTOR}")))?;
+    Ok(stripped.into())
 }
 
 pub fn append_plasma_bytes(mut bytes: Vec<u8>) -> Vec<u8> {
@@ -34,16 +74,248 @@ pub fn append_plasma_bytes(mut bytes: Vec<u8>) -> Vec<u8> {
     bytes
 }
 
+pub fn parse_keccak_commitment(bytes: &[u8]) -> super::Result<Commitment> {
+    bytes
+        .strip_prefix(&[OP_PLASMA_KECCAK_COMMITMENT])
+        .ok_or_else(|| invalid("not a keccak commitment"))?
+        .try_into()
+        .map_err(|_| invalid("keccak commitment must be 32 bytes").into())
+}
+
+pub fn keccak_commitment_bytes(commitment: Commitment) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(commitment.len() + 1);
+    bytes.push(OP_PLASMA_KECCAK_COMMITMENT);
+    bytes.extend_from_slice(&commitment);
+    bytes
+}
+
+pub(crate) fn decode_hex(request: &str) -> super::Result<Vec<u8>> {
+    Ok(hex::decode(request.strip_prefix("0x").unwrap_or(request))
+        .map_err(|e| invalid(format!("{request} is not hex: {e}")))?)
+}
+
+fn mismatch(commitment: &Commitment) -> CommitmentError {
+    invalid(format!(
+        "0x{} is not the keccak256 of the data",
+        hex::encode(commitment)
+    ))
+}
+
+/// Submit the body in chunks of [`CHUNK_SIZE`] as it arrives. Transactions
+/// share the signer's nonce so they are sent one at a time, but the next chunk
+/// is read while the previous one is submitted, holding at most two chunks in
+/// memory. If `commitment` is set the body is checked against it before the
+/// last chunk is submitted, so a mismatched body that fits in one chunk is
+/// never submitted.
+async fn submit_chunks<S, E, F, Fut>(
+    mut body: S,
+    commitment: Option<Commitment>,
+    submit: F,
+) -> Result<Vec<BlobRef>, AppError>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Into<anyhow::Error>,
+    F: Fn(Vec<u8>) -> Fut,
+    Fut: Future<Output = Result<BlobRef, AppError>> + Send + 'static,
+{
+    let mut hasher = commitment.map(|_| Keccak256::new());
+    let mut refs = vec![];
+    let mut pending: Option<JoinHandle<Result<BlobRef, AppError>>> = None;
+    let mut buffer = Vec::with_capacity(CHUNK_SIZE);
+
+    loop {
+        let done = match body.next().await {
+            Some(chunk) => {
+                let chunk = chunk.map_err(Into::into)?;
+                if let Some(hasher) = hasher.as_mut() {
+                    hasher.update(&chunk);
+                }
+                buffer.extend_from_slice(&chunk);
+                false
+            }
+            None => true,
+        };
+        if done {
+            if let (Some(hasher), Some(commitment)) = (hasher.take(), commitment) {
+                if <[u8; 32]>::from(hasher.finalize()) != commitment {
+                    return Err(mismatch(&commitment).into());
+                }
+            }
+        }
+
+        // A full chunk is only submitted once more data follows, so the last
+        // chunk waits for the check above. An empty body is a single empty chunk.
+        while buffer.len() > CHUNK_SIZE
+            || (done && (!buffer.is_empty() || (refs.is_empty() && pending.is_none())))
+        {
+            let rest = buffer.split_off(buffer.len().min(CHUNK_SIZE));
+            let data = std::mem::replace(&mut buffer, rest);
+            if let Some(previous) = pending.take() {
+                refs.push(previous.await??);
+            }
+            pending = Some(tokio::spawn(submit(data)));
+        }
+        if done {
+            break;
+        }
+    }
+
+    if let Some(previous) = pending {
+        refs.push(previous.await??);
+    }
+    Ok(refs)
+}
+
+async fn submit_chunk(
+    state: Arc<RwLock<AppState>>,
+    in_flight: Arc<InFlight>,
+    data: Vec<u8>,
+) -> Result<BlobRef, AppError> {
+    in_flight.grow(data.len());
+    let blob_hash = CryptoHash::hash_bytes(&data);
+    crate::submit_blob(&state, blob_hash, data, &in_flight).await
+}
+
+/// Check the streamed data hashes to `commitment`, failing the stream at the
+/// end if it doesn't.
+fn verify_keccak(
+    chunks: impl Stream<Item = anyhow::Result<Bytes>> + Send + 'static,
+    commitment: Commitment,
+) -> impl Stream<Item = anyhow::Result<Bytes>> + Send + 'static {
+    stream::unfold(
+        (Box::pin(chunks), Some(Keccak256::new())),
+        move |(mut chunks, hasher)| async move {
+            let mut hasher = hasher?;
+            match chunks.next().await {
+                Some(Ok(chunk)) => {
+                    hasher.update(&chunk);
+                    Some((Ok(chunk), (chunks, Some(hasher))))
+                }
+                Some(Err(e)) => Some((Err(e), (chunks, None))),
+                None if <[u8; 32]>::from(hasher.finalize()) == commitment => None,
+                None => Some((
+                    Err(anyhow::anyhow!(
+                        "data for commitment 0x{} does not match its hash",
+                        hex::encode(commitment)
+                    )),
+                    (chunks, None),
+                )),
+            }
+        },
+    )
+}
+
+/// Fetch the data for `refs` in order, up to [`FETCH_CONCURRENCY`] at once.
+/// A ref that appears more than once is fetched once, its data is kept until
+/// the last time it is used. The stream ends after the first error.
+fn fetch_in_order<F, Fut>(
+    refs: Vec<BlobRef>,
+    fetch: F,
+) -> impl Stream<Item = anyhow::Result<Bytes>> + Send + 'static
+where
+    F: Fn(BlobRef) -> Fut + Send + 'static,
+    Fut: Future<Output = anyhow::Result<Bytes>> + Send + 'static,
+{
+    let mut uses: HashMap<[u8; 32], usize> = HashMap::new();
+    for blob_ref in &refs {
+        *uses.entry(blob_ref.transaction_id).or_default() += 1;
+    }
+    // Distinct refs in order of first use, so they come back in the order
+    // they are first needed
+    let distinct = refs
+        .iter()
+        .unique_by(|blob_ref| blob_ref.transaction_id)
+        .cloned()
+        .collect_vec();
+    let fetched = Box::pin(
+        stream::iter(distinct)
+            .map(fetch)
+            .buffered(FETCH_CONCURRENCY),
+    );
+
+    stream::unfold(
+        (refs.into_iter(), fetched, uses, HashMap::new()),
+        |(mut refs, mut fetched, mut uses, mut kept)| async move {
+            let id = refs.next()?.transaction_id;
+            let remaining = uses.get_mut(&id).map(|uses| {
+                *uses -= 1;
+                *uses
+            })?;
+
+            let chunk = match kept.get(&id).cloned() {
+                Some(chunk) => Ok(chunk),
+                None => fetched.next().await?,
+            };
+            match &chunk {
+                Ok(data) if remaining > 0 => {
+                    kept.insert(id, data.clone());
+                }
+                Ok(_) => {
+                    kept.remove(&id);
+                }
+                // Nothing after a failed ref is sent
+                Err(_) => refs = Vec::new().into_iter(),
+            }
+            Some((chunk, (refs, fetched, uses, kept)))
+        },
+    )
+}
+
+/// Fetch the data for `refs` from `da`, see [`fetch_in_order`].
+pub(crate) fn fetch_refs<D>(
+    da: Arc<D>,
+    refs: Vec<BlobRef>,
+) -> impl Stream<Item = anyhow::Result<Bytes>> + Send + 'static
+where
+    D: DataAvailability + Send + Sync + 'static,
+{
+    fetch_in_order(refs, move |blob_ref| {
+        let da = da.clone();
+        async move {
+            fetch_blob(&*da, &blob_ref)
+                .await
+                .map(|blob| Bytes::from(blob.data))
+                .with_context(|| format!("failed to fetch ref 0x{}", hex::encode(*blob_ref)))
+        }
+    })
+}
+
+/// Stream the data for `refs` to the response. The first ref is fetched
+/// before responding so a missing blob is still a 404, a failure after that
+/// aborts the response.
+async fn stream_refs(
+    state: Arc<RwLock<AppState>>,
+    refs: Vec<BlobRef>,
+    commitment: Option<Commitment>,
+) -> Result<Response, AppError> {
+    let client = state.read().await.client.clone().ok_or(NotConfigured)?;
+    let mut chunks = Box::pin(fetch_refs(client, refs));
+    let first = chunks.next().await.transpose()?;
+    let chunks = stream::iter(first.map(Ok)).chain(chunks);
+
+    let body = match commitment {
+        Some(commitment) => boxed(StreamBody::new(verify_keccak(chunks, commitment))),
+        None => boxed(StreamBody::new(chunks)),
+    };
+    Ok(Response::builder()
+        .header("Content-Type", "application/octet-stream")
+        .body(body)
+        .unwrap())
+}
+
 pub(crate) async fn get(
     State(state): State<Arc<RwLock<AppState>>>,
     Path(request): Path<String>,
 ) -> Result<Response, AppError> {
-    let commitments = hex::decode(request.strip_prefix("0x").unwrap_or(&request))?;
+    let commitments = decode_hex(&request)?;
+    if commitments.first() == Some(&OP_PLASMA_KECCAK_COMMITMENT) {
+        return get_keccak(state, parse_keccak_commitment(&commitments)?).await;
+    }
     let commitments = strip_plasma_bytes(commitments)?;
 
     // Commitment can be chunks of 32 byte hashes for larger blobs
-    if commitments.len() % 32 != 0 {
-        return Err(anyhow::anyhow!("invalid commitment").into());
+    if commitments.is_empty() || commitments.len() % 32 != 0 {
+        return Err(invalid("expected one or more 32 byte transaction hashes").into());
     }
 
     let refs = commitments
@@ -52,38 +324,309 @@ pub(crate) async fn get(
         .map(|tx| BlobRef::from(tx.unwrap()))
         .collect_vec();
 
-    let mut data = vec![];
-    for blob_ref in refs {
-        data.extend_from_slice(
-            &super::get(State(state.clone()), Query(blob_ref))
-                .await?
-                .data,
-        );
-    }
+    stream_refs(state, refs, None).await
+}
+
+async fn get_keccak(
+    state: Arc<RwLock<AppState>>,
+    commitment: Commitment,
+) -> Result<Response, AppError> {
+    let commitments = state.read().await.commitments.clone();
+    let refs = commitments
+        .get(&commitment)
+        .ok_or(CommitmentError::Unknown(commitment))?;
 
-    Ok(stream_response(data))
+    stream_refs(state, refs, Some(commitment)).await
 }
 
 pub(crate) async fn submit(
     State(state): State<Arc<RwLock<AppState>>>,
-    mut stream: BodyStream,
+    stream: BodyStream,
 ) -> Result<Response, AppError> {
-    let mut chunks = vec![];
-    while let Some(chunk) = stream.next().await {
-        chunks.extend_from_slice(&chunk?[..])
-    }
+    let drain = state.read().await.drain.clone();
+    // One guard for the whole body, so shutdown never cuts a payload in half
+    let in_flight = Arc::new(drain.begin(None, 0)?);
+    let refs = submit_chunks(stream, None, |data| {
+        submit_chunk(state.clone(), in_flight.clone(), data)
+    })
+    .await?;
 
-    let commitments = super::submit(State(state), Blob::new(chunks).into())
-        .await
-        .map(|r| r.transaction_id.to_vec())?;
+    let commitments = refs.iter().flat_map(|r| r.transaction_id).collect_vec();
     let commitments = append_plasma_bytes(commitments);
 
     Ok(stream_response(commitments))
 }
 
+/// Submit data under a keccak256 commitment computed by the batcher.
+pub(crate) async fn submit_keccak(
+    State(state): State<Arc<RwLock<AppState>>>,
+    Path(request): Path<String>,
+    stream: BodyStream,
+) -> Result<Response, AppError> {
+    let commitment = parse_keccak_commitment(&decode_hex(&request)?)?;
+
+    let (commitments, drain) = {
+        let state = state.read().await;
+        (state.commitments.clone(), state.drain.clone())
+    };
+    if commitments.get(&commitment).is_none() {
+        let in_flight = Arc::new(drain.begin(Some(&CryptoHash(commitment)), 0)?);
+        let refs = submit_chunks(stream, Some(commitment), |data| {
+            submit_chunk(state.clone(), in_flight.clone(), data)
+        })
+        .await?;
+        commitments.insert(commitment, refs).await?;
+    }
+
+    Ok(stream_response(keccak_commitment_bytes(commitment)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::commitments::keccak256;
+    use axum::{
+        body::{Body, HttpBody},
+        http::Request,
+    };
+    use serde::Deserialize;
+    use std::{collections::HashMap, convert::Infallible, sync::Mutex};
+    use tower::ServiceExt;
+
+    /// Submit `parts` as a body, returning the sizes of the submitted chunks.
+    async fn chunk_sizes(
+        parts: Vec<usize>,
+        commitment: Option<Commitment>,
+    ) -> Result<Vec<usize>, AppError> {
+        let body = stream::iter(
+            parts
+                .into_iter()
+                .map(|len| Ok::<_, Infallible>(Bytes::from(vec![1u8; len]))),
+        );
+        let submitted = Arc::new(Mutex::new(vec![]));
+        let refs = submit_chunks(body, commitment, |data| {
+            let submitted = submitted.clone();
+            async move {
+                let mut submitted = submitted.lock().unwrap();
+                submitted.push(data.len());
+                Ok(BlobRef::new([submitted.len() as u8; 32]))
+            }
+        })
+        .await?;
+
+        let submitted = submitted.lock().unwrap().clone();
+        // Refs come back in submission order
+        for (i, blob_ref) in refs.iter().enumerate() {
+            assert_eq!(blob_ref.transaction_id, [i as u8 + 1; 32]);
+        }
+        assert_eq!(refs.len(), submitted.len());
+        Ok(submitted)
+    }
+
+    #[tokio::test]
+    async fn test_submit_chunks() {
+        let half = CHUNK_SIZE / 2;
+        assert_eq!(chunk_sizes(vec![10, 20], None).await.unwrap(), vec![30]);
+        assert_eq!(
+            chunk_sizes(vec![half; 5], None).await.unwrap(),
+            vec![CHUNK_SIZE, CHUNK_SIZE, half]
+        );
+        assert_eq!(
+            chunk_sizes(vec![CHUNK_SIZE * 2], None).await.unwrap(),
+            vec![CHUNK_SIZE, CHUNK_SIZE]
+        );
+        assert_eq!(chunk_sizes(vec![], None).await.unwrap(), vec![0]);
+    }
+
+    #[tokio::test]
+    async fn test_submit_chunks_keccak() {
+        let data = vec![1u8; 30];
+        assert_eq!(
+            chunk_sizes(vec![10, 20], Some(keccak256(&data)))
+                .await
+                .unwrap(),
+            vec![30]
+        );
+
+        // Nothing is submitted for a mismatched body that fits in one chunk
+        let err = chunk_sizes(vec![10, 20], Some(keccak256(b"other")))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.0.downcast_ref(),
+            Some(CommitmentError::Invalid(_))
+        ));
+    }
+
+    /// Fetch `refs` with a fake fetcher that returns the first byte of the
+    /// ref, recording the fetches and the most fetches in flight at once.
+    async fn fetch_all(
+        refs: &[u8],
+        fail: Option<u8>,
+    ) -> (Vec<anyhow::Result<Bytes>>, Vec<u8>, usize) {
+        #[derive(Default)]
+        struct Calls {
+            fetched: Vec<u8>,
+            in_flight: usize,
+            max_in_flight: usize,
+        }
+        let calls = Arc::new(Mutex::new(Calls::default()));
+
+        let refs = refs.iter().map(|id| BlobRef::new([*id; 32])).collect_vec();
+        let fetch = {
+            let calls = calls.clone();
+            move |blob_ref: BlobRef| {
+                let calls = calls.clone();
+                async move {
+                    let id = blob_ref.transaction_id[0];
+                    {
+                        let mut calls = calls.lock().unwrap();
+                        calls.fetched.push(id);
+                        calls.in_flight += 1;
+                        calls.max_in_flight = calls.max_in_flight.max(calls.in_flight);
+                    }
+                    tokio::task::yield_now().await;
+                    calls.lock().unwrap().in_flight -= 1;
+                    if Some(id) == fail {
+                        anyhow::bail!("failed to fetch ref {id}");
+                    }
+                    Ok(Bytes::from(vec![id]))
+                }
+            }
+        };
+        let chunks = fetch_in_order(refs, fetch).collect::<Vec<_>>().await;
+
+        let calls = calls.lock().unwrap();
+        (chunks, calls.fetched.clone(), calls.max_in_flight)
+    }
+
+    fn data(chunks: Vec<anyhow::Result<Bytes>>) -> Vec<u8> {
+        chunks
+            .into_iter()
+            .flat_map(|c| c.unwrap().to_vec())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_fetch_in_order() {
+        let refs = (1..=20).collect_vec();
+        let (chunks, fetched, max_in_flight) = fetch_all(&refs, None).await;
+        assert_eq!(data(chunks), refs);
+        assert_eq!(fetched.len(), refs.len());
+        assert!(max_in_flight > 1, "fetched sequentially");
+        assert!(max_in_flight <= FETCH_CONCURRENCY);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_in_order_deduplicates() {
+        let (chunks, fetched, _) = fetch_all(&[1, 2, 1, 3, 2, 1], None).await;
+        assert_eq!(data(chunks), vec![1, 2, 1, 3, 2, 1]);
+        assert_eq!(fetched, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_in_order_stops_at_failed_ref() {
+        let (chunks, _, _) = fetch_all(&[1, 2, 3, 4], Some(3)).await;
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[..2].iter().all(Result::is_ok));
+        assert_eq!(
+            chunks[2].as_ref().unwrap_err().to_string(),
+            "failed to fetch ref 3"
+        );
+    }
+
+    async fn collect_verified(
+        chunks: Vec<&'static [u8]>,
+        commitment: Commitment,
+    ) -> anyhow::Result<Vec<u8>> {
+        let chunks = stream::iter(chunks.into_iter().map(|c| Ok(Bytes::from(c))));
+        let mut verified = Box::pin(verify_keccak(chunks, commitment));
+        let mut data = vec![];
+        while let Some(chunk) = verified.next().await {
+            data.extend_from_slice(&chunk?);
+        }
+        Ok(data)
+    }
+
+    #[tokio::test]
+    async fn test_verify_keccak() {
+        let commitment = keccak256(b"hello world");
+        assert_eq!(
+            collect_verified(vec![b"hello", b" world"], commitment)
+                .await
+                .unwrap(),
+            b"hello world"
+        );
+        assert!(collect_verified(vec![b"hello", b" there"], commitment)
+            .await
+            .is_err());
+    }
+
+    /// A request in the form op-node's Alt-DA client sends it, with the
+    /// response the sidecar should give.
+    #[derive(Deserialize)]
+    struct Recorded {
+        name: String,
+        method: String,
+        path: String,
+        headers: HashMap<String, String>,
+        /// Hex encoded request body.
+        body: String,
+        status: u16,
+        content_type: Option<String>,
+        /// Hex encoded response body.
+        response: Option<String>,
+    }
+
+    #[tokio::test]
+    async fn test_replay_op_node_requests() {
+        let recorded: Vec<Recorded> =
+            serde_json::from_str(include_str!("testdata/op-node-requests.json")).unwrap();
+
+        let state = crate::tests::state();
+        state
+            .read()
+            .await
+            .commitments
+            .insert(keccak256(b"hello"), vec![BlobRef::new([0x11; 32])])
+            .await
+            .unwrap();
+        let router = crate::router(state);
+
+        for request in recorded {
+            let mut builder = Request::builder()
+                .method(request.method.as_str())
+                .uri(&request.path);
+            for (name, value) in &request.headers {
+                builder = builder.header(name, value);
+            }
+            let body = Body::from(hex::decode(&request.body).unwrap());
+            let response = router
+                .clone()
+                .oneshot(builder.body(body).unwrap())
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), request.status, "{}", request.name);
+            if let Some(content_type) = request.content_type {
+                assert_eq!(
+                    response.headers()["content-type"],
+                    content_type.as_str(),
+                    "{}",
+                    request.name
+                );
+            }
+            if let Some(expected) = request.response {
+                let mut body = response.into_body();
+                let mut bytes = vec![];
+                while let Some(chunk) = body.data().await {
+                    bytes.extend_from_slice(&chunk.unwrap());
+                }
+                assert_eq!(hex::encode(bytes), expected, "{}", request.name);
+            }
+        }
+    }
 
     #[test]
     fn test_strip_plasma_bytes() {
@@ -104,6 +647,38 @@ mod tests {
         assert!(strip_plasma_bytes(bytes).is_err());
     }
 
+    #[test]
+    fn test_keccak_commitment() {
+        let commitment = keccak256(b"data");
+        let bytes = keccak_commitment_bytes(commitment);
+        assert_eq!(bytes[0], OP_PLASMA_KECCAK_COMMITMENT);
+        assert_eq!(parse_keccak_commitment(&bytes).unwrap(), commitment);
+    }
+
+    #[test]
+    fn test_parse_keccak_commitment_invalid() {
+        let commitment = keccak256(b"data");
+        assert!(parse_keccak_commitment(&commitment).is_err());
+        assert!(parse_keccak_commitment(&[OP_PLASMA_KECCAK_COMMITMENT, 1, 2, 3]).is_err());
+
+        let mut generic = keccak_commitment_bytes(commitment);
+        generic[0] = OP_PLASMA_GENERIC_COMMITMENT;
+        assert!(parse_keccak_commitment(&generic).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_unknown_keccak_commitment() {
+        let commitment = hex::encode(keccak_commitment_bytes(keccak256(b"data")));
+        let err = get(
+            State(crate::tests::state()),
+            Path(format!("0x{commitment}")),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert!(err.0.to_string().contains("unknown commitment"));
+    }
+
     #[test]
     fn test_append_plasma_bytes() {
         let bytes = vec![1, 2, 3];
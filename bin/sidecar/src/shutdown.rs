@@ -0,0 +1,296 @@
+use near_da_rpc::CryptoHash;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::Notify,
+};
+use tracing::{info, warn};
+
+use crate::Result;
+
+/// Returned for submissions that arrive after shutdown has started.
+#[derive(Debug)]
+pub struct ShuttingDown;
+
+impl fmt::Display for ShuttingDown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("sidecar is shutting down, not accepting new submissions")
+    }
+}
+
+impl std::error::Error for ShuttingDown {}
+
+/// A submission that was sent to NEAR but hadn't reached its mode yet.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PendingSubmission {
+    /// Hex encoded hash of the data, if it was known before submitting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob_hash: Option<String>,
+    /// Bytes of data submitted so far.
+    pub size: usize,
+    /// Unix timestamp in seconds.
+    pub started_at: u64,
+    /// The NEAR transactions signed for it so far, look them up to see if the
+    /// data landed.
+    #[serde(default)]
+    pub transactions: Vec<CryptoHash>,
+}
+
+#[derive(Default)]
+struct Submissions {
+    draining: bool,
+    next_id: u64,
+    in_flight: HashMap<u64, PendingSubmission>,
+}
+
+/// Tracks in-flight submissions so shutdown can wait for them.
+#[derive(Default)]
+pub struct Drain {
+    submissions: Mutex<Submissions>,
+    idle: Notify,
+}
+
+impl Drain {
+    pub fn is_draining(&self) -> bool {
+        self.submissions.lock().unwrap().draining
+    }
+
+    /// Register a submission, it is tracked until the guard is dropped. Fails
+    /// with [`ShuttingDown`] once draining has started.
+    ///
+    /// A request that submits several transactions holds a single guard, so
+    /// it is either refused up front or drained as a whole.
+    pub fn begin(
+        self: &Arc<Self>,
+        blob_hash: Option<&CryptoHash>,
+        size: usize,
+    ) -> Result<InFlight> {
+        let mut submissions = self.submissions.lock().unwrap();
+        if submissions.draining {
+            return Err(ShuttingDown.into());
+        }
+        let id = submissions.next_id;
+        submissions.next_id += 1;
+        submissions.in_flight.insert(
+            id,
+            PendingSubmission {
+                blob_hash: blob_hash.map(|hash| hex::encode(hash.0)),
+                size,
+                started_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                transactions: vec![],
+            },
+        );
+        Ok(InFlight {
+            drain: self.clone(),
+            id,
+        })
+    }
+
+    pub fn start_draining(&self) {
+        self.submissions.lock().unwrap().draining = true;
+    }
+
+    /// The submissions still in flight, oldest first.
+    pub fn pending(&self) -> Vec<PendingSubmission> {
+        let submissions = self.submissions.lock().unwrap();
+        let mut pending = submissions.in_flight.iter().collect::<Vec<_>>();
+        pending.sort_by_key(|(id, _)| **id);
+        pending.into_iter().map(|(_, p)| p.clone()).collect()
+    }
+
+    /// Wait until no submissions are in flight.
+    pub async fn drained(&self) {
+        loop {
+            // Created before checking so a wakeup in between isn't missed
+            let idle = self.idle.notified();
+            if self.submissions.lock().unwrap().in_flight.is_empty() {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    fn update(&self, id: u64, f: impl FnOnce(&mut PendingSubmission)) {
+        if let Some(pending) = self.submissions.lock().unwrap().in_flight.get_mut(&id) {
+            f(pending);
+        }
+    }
+
+    fn finish(&self, id: u64) {
+        let mut submissions = self.submissions.lock().unwrap();
+        submissions.in_flight.remove(&id);
+        if submissions.in_flight.is_empty() {
+            self.idle.notify_waiters();
+        }
+    }
+}
+
+/// Marks a submission as in flight until dropped.
+pub struct InFlight {
+    drain: Arc<Drain>,
+    id: u64,
+}
+
+impl InFlight {
+    /// Record a transaction signed for the submission, pass it to
+    /// [`DataAvailability::submit_signed`].
+    ///
+    /// [`DataAvailability::submit_signed`]: near_da_rpc::DataAvailability::submit_signed
+    pub fn signed(&self, transaction_id: CryptoHash) {
+        self.drain
+            .update(self.id, |pending| pending.transactions.push(transaction_id));
+    }
+
+    /// Count `size` more bytes, for data that streams in while submitting.
+    pub fn grow(&self, size: usize) {
+        self.drain.update(self.id, |pending| pending.size += size);
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.drain.finish(self.id);
+    }
+}
+
+/// Resolves on SIGTERM or SIGINT.
+pub async fn signal_received() -> Result<()> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        _ = terminate.recv() => info!("received SIGTERM"),
+        result = tokio::signal::ctrl_c() => {
+            result?;
+            info!("received SIGINT");
+        }
+    }
+    Ok(())
+}
+
+/// Stop accepting submissions and wait up to `timeout` for the in-flight ones.
+/// Submissions that don't finish in time are written to `pending_file`, which
+/// is removed after a clean drain so it only ever lists the last shutdown's.
+pub async fn drain(drain: &Drain, timeout: Duration, pending_file: &Path) -> Result<()> {
+    drain.start_draining();
+    info!(
+        "draining {} in-flight submissions, waiting up to {timeout:?}",
+        drain.pending().len()
+    );
+
+    if tokio::time::timeout(timeout, drain.drained()).await.is_ok() {
+        info!("all submissions finished");
+        return match tokio::fs::remove_file(pending_file).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    }
+
+    let pending = drain.pending();
+    warn!(
+        "{} submissions did not finish in time, writing them to {}",
+        pending.len(),
+        pending_file.display()
+    );
+    persist(&pending, pending_file).await
+}
+
+pub async fn persist(pending: &[PendingSubmission], path: &Path) -> Result<()> {
+    tokio::fs::write(path, serde_json::to_vec_pretty(pending)?).await?;
+    Ok(())
+}
+
+/// Warn about submissions left pending by a previous shutdown, their data may
+/// or may not have landed on NEAR.
+pub async fn warn_pending(path: &Path) {
+    let Ok(contents) = tokio::fs::read(path).await else {
+        return;
+    };
+    match serde_json::from_slice::<Vec<PendingSubmission>>(&contents) {
+        Ok(pending) if !pending.is_empty() => warn!(
+            "{} submissions were still pending at the last shutdown, see {}",
+            pending.len(),
+            path.display()
+        ),
+        Ok(_) => {}
+        Err(e) => warn!("failed to read pending submissions {}: {e}", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drained_waits_for_in_flight() {
+        let drain = Arc::new(Drain::default());
+        let in_flight = drain.begin(Some(&CryptoHash([1; 32])), 10).unwrap();
+        assert_eq!(drain.pending().len(), 1);
+
+        let waiter = {
+            let drain = drain.clone();
+            tokio::spawn(async move { drain.drained().await })
+        };
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        drop(in_flight);
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(drain.pending().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_draining_rejects_submissions() {
+        let drain = Arc::new(Drain::default());
+        drain.start_draining();
+        assert!(drain.is_draining());
+
+        let err = drain.begin(Some(&CryptoHash([1; 32])), 10).err().unwrap();
+        assert!(err.is::<ShuttingDown>());
+    }
+
+    #[tokio::test]
+    async fn test_drain_timeout_persists_pending() {
+        let drain = Arc::new(Drain::default());
+        let in_flight = drain.begin(Some(&CryptoHash([2; 32])), 40).unwrap();
+        in_flight.grow(2);
+        in_flight.signed(CryptoHash([3; 32]));
+        let path = std::env::temp_dir().join("near-da-sidecar-pending.json");
+        let _ = std::fs::remove_file(&path);
+
+        super::drain(&drain, Duration::from_millis(10), &path)
+            .await
+            .unwrap();
+
+        let pending: Vec<PendingSubmission> =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].blob_hash, Some(hex::encode([2; 32])));
+        assert_eq!(pending[0].size, 42);
+        assert_eq!(pending[0].transactions, vec![CryptoHash([3; 32])]);
+    }
+
+    #[tokio::test]
+    async fn test_drain_without_in_flight() {
+        let drain = Drain::default();
+        let path = std::env::temp_dir().join("near-da-sidecar-pending-empty.json");
+        // Left by an earlier shutdown that timed out
+        persist(&[], &path).await.unwrap();
+
+        super::drain(&drain, Duration::from_secs(1), &path)
+            .await
+            .unwrap();
+        assert!(!path.exists());
+    }
+}
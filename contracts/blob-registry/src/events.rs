@@ -1,12 +1,15 @@
-use crate::{Maintainer, Namespace};
+use crate::{Maintainer, Namespace, Priority, TransactionId};
 use near_sdk::{
     env::log_str,
     serde::{Deserialize, Serialize},
     serde_json::to_string,
+    BlockHeight,
 };
 
 const CONTRACT_STANDARD_NAME: &str = "nepXXX";
 const CONTRACT_STANDARD_VERSION: &str = "1.0.0";
+/// Prefix that marks a log as an event, see NEP-297.
+const EVENT_JSON_PREFIX: &str = "EVENT_JSON:";
 
 /// Interface to capture data about an event.
 ///
@@ -30,27 +33,36 @@ pub(crate) struct EventLog {
 #[serde(crate = "near_sdk::serde")]
 #[non_exhaustive]
 pub(crate) enum EventLogVariant {
-    AddMaintainer(AddMaintainerLog),
+    AddMaintainer(MaintainerLog),
+    RemoveMaintainer(MaintainerLog),
+    NamespaceTransfer(MaintainerLog),
     NamespaceRegistration(NamespaceRegistrationLog),
+    NamespaceUnregistration(NamespaceRegistrationLog),
+    BlobSubmitted(BlobSubmittedLog),
+    PriorityUpdate(PriorityUpdateLog),
+    ExtraUpdate(ExtraUpdateLog),
 }
 
-/// An event log to capture a maintainer inclusion.
+/// An event log to capture a maintainer inclusion, removal, or a namespace
+/// handed over to a single maintainer.
 ///
 /// Arguments
-/// * `owner_id`: "account.near"
+/// * `namespace`: u32 the maintainer belongs to
+/// * `maintainer`: "account.near" as bytes
 /// * `memo`: optional message
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(crate = "near_sdk::serde")]
-pub(crate) struct AddMaintainerLog {
+pub(crate) struct MaintainerLog {
+    pub namespace: Namespace,
     pub maintainer: Maintainer,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub memo: Option<String>,
 }
 
-/// An event log to capture a new namespace registration.
+/// An event log to capture a namespace registration, or its removal.
 ///
 /// Arguments
-/// * `namespace`: u32 that has been registered
+/// * `namespace`: u32 that has been registered or removed
 /// * `memo`: optional message
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(crate = "near_sdk::serde")]
@@ -60,6 +72,53 @@ pub(crate) struct NamespaceRegistrationLog {
     pub memo: Option<String>,
 }
 
+/// An event log to capture submitted transaction ids.
+///
+/// Arguments
+/// * `namespace`: u32 the blobs were submitted for
+/// * `transaction_ids`: hex encoded ids of the transactions carrying the blobs
+/// * `block_height`: height they were recorded at
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct BlobSubmittedLog {
+    pub namespace: Namespace,
+    pub transaction_ids: Vec<TransactionId>,
+    pub block_height: BlockHeight,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture a new priority for a namespace.
+///
+/// Arguments
+/// * `namespace`: u32 that was updated
+/// * `priority`: the new priority level
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct PriorityUpdateLog {
+    pub namespace: Namespace,
+    pub priority: Priority,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture new extra information for a namespace.
+///
+/// Arguments
+/// * `namespace`: u32 that was updated
+/// * `extra`: the new information, `None` when it was cleared
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ExtraUpdateLog {
+    pub namespace: Namespace,
+    pub extra: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
 impl EventLog {
     fn new(event: EventLogVariant) -> Self {
         Self {
@@ -69,21 +128,86 @@ impl EventLog {
         }
     }
 
-    pub(crate) fn maintainer(maintainer: Maintainer) {
-        let log = EventLog::new(EventLogVariant::AddMaintainer(AddMaintainerLog {
+    fn emit(self) {
+        log_str(&format!("{EVENT_JSON_PREFIX}{}", to_string(&self).unwrap()));
+    }
+
+    pub(crate) fn maintainer(namespace: Namespace, maintainer: Maintainer) {
+        EventLog::new(EventLogVariant::AddMaintainer(MaintainerLog {
+            namespace,
+            maintainer,
+            memo: None,
+        }))
+        .emit();
+    }
+
+    pub(crate) fn remove_maintainer(namespace: Namespace, maintainer: Maintainer) {
+        EventLog::new(EventLogVariant::RemoveMaintainer(MaintainerLog {
+            namespace,
             maintainer,
             memo: None,
-        }));
-        log_str(&to_string(&log).unwrap());
+        }))
+        .emit();
+    }
+
+    pub(crate) fn transfer_namespace(namespace: Namespace, maintainer: Maintainer) {
+        EventLog::new(EventLogVariant::NamespaceTransfer(MaintainerLog {
+            namespace,
+            maintainer,
+            memo: None,
+        }))
+        .emit();
+    }
+
+    pub(crate) fn priority(namespace: Namespace, priority: Priority) {
+        EventLog::new(EventLogVariant::PriorityUpdate(PriorityUpdateLog {
+            namespace,
+            priority,
+            memo: None,
+        }))
+        .emit();
+    }
+
+    pub(crate) fn extra(namespace: Namespace, extra: Option<String>) {
+        EventLog::new(EventLogVariant::ExtraUpdate(ExtraUpdateLog {
+            namespace,
+            extra,
+            memo: None,
+        }))
+        .emit();
     }
 
     pub(crate) fn namespace(namespace: Namespace) {
-        let log = EventLog::new(EventLogVariant::NamespaceRegistration(
+        EventLog::new(EventLogVariant::NamespaceRegistration(
+            NamespaceRegistrationLog {
+                namespace,
+                memo: None,
+            },
+        ))
+        .emit();
+    }
+
+    pub(crate) fn blob_submitted(
+        namespace: Namespace,
+        transaction_ids: Vec<TransactionId>,
+        block_height: BlockHeight,
+    ) {
+        EventLog::new(EventLogVariant::BlobSubmitted(BlobSubmittedLog {
+            namespace,
+            transaction_ids,
+            block_height,
+            memo: None,
+        }))
+        .emit();
+    }
+
+    pub(crate) fn unregister(namespace: Namespace) {
+        EventLog::new(EventLogVariant::NamespaceUnregistration(
             NamespaceRegistrationLog {
                 namespace,
                 memo: None,
             },
-        ));
-        log_str(&to_string(&log).unwrap());
+        ))
+        .emit();
     }
 }
@@ -1,9 +1,12 @@
 use events::EventLog;
 use near_sdk::{
-    assert_one_yocto, env, near,
+    assert_one_yocto,
+    borsh::BorshDeserialize,
+    env, near,
     serde::de::{self, Visitor},
     serde::{Deserialize, Deserializer, Serialize, Serializer},
-    AccountId, NearToken, PanicOnDefault,
+    store::{IterableMap, LookupMap, Vector},
+    AccountId, BlockHeight, BorshStorageKey, NearToken, PanicOnDefault, Promise, StorageUsage,
 };
 
 use near_sdk_contract_tools::{
@@ -21,15 +24,54 @@ const ERR_INVALID_INPUT: &str = "Invalid input";
 const ERR_CONTRACT_INITIALIZED: &str = "Contract already initialized";
 const ERR_NAMESPACE_EXISTS: &str = "Namespace exists and cannot be registered again";
 const ERR_NOT_ENOUGHT_FUNDS: &str = "Not enough funds to register a namespace";
-const MINIMUM_DEPOSIT: u8 = 100; // 0.1 NEAR == 100 miliNEAR
+const ERR_NOT_ENOUGH_STORAGE_DEPOSIT: &str = "Attached deposit does not cover the storage used";
+const ERR_BYTE_COST_TOO_LOW: &str = "Byte cost is below the storage staking cost";
+const ERR_UNKNOWN_STATE: &str = "State is not a version that can be migrated";
+const ERR_ALREADY_MIGRATED: &str = "State is already at the current version";
+const ERR_TOO_MANY_SUBMISSIONS: &str =
+    "Namespace has too many submissions to unregister, clear them first";
+const MAX_LIMIT: u32 = 100; // most submissions returned by a view or cleared by a call
+
+/// Version of the state layout, bumped with every change that needs a `migrate`.
+const STATE_VERSION: u8 = 3;
 
 /// The contract itself.
 #[derive(PanicOnDefault, Owner)]
-#[near(contract_state, serializers=[borsh, json])]
+#[near(contract_state)]
 pub struct Contract {
+    version: u8,
+    /// Metadata of each namespace, in registration order.
+    info: IterableMap<Namespace, Metadata>,
+    /// Submitted transaction ids of each namespace, oldest first.
+    transactions: LookupMap<Namespace, Vector<Submission>>,
+    fees: FeeSchedule,
+    /// Storage staked by each namespace.
+    deposits: LookupMap<Namespace, Deposit>,
+}
+
+/// The first deployed state, which kept every namespace in one map.
+#[near(serializers=[borsh])]
+struct ContractV1 {
     info: HashMap<Namespace, Metadata>,
 }
 
+/// The state before storage was staked.
+#[near(serializers=[borsh])]
+struct ContractV2 {
+    version: u8,
+    info: IterableMap<Namespace, Metadata>,
+    transactions: LookupMap<Namespace, Vector<Submission>>,
+}
+
+#[near(serializers=[borsh])]
+#[derive(BorshStorageKey)]
+enum StorageKey {
+    Transactions,
+    NamespaceTransactions(Namespace),
+    Info,
+    Deposits,
+}
+
 /// Repository information, understood as a set of namespaces and their metadata.
 #[derive(Default, Clone)]
 #[near(serializers=[borsh, json])]
@@ -44,6 +86,55 @@ type Priority = u32;
 type Maintainer = Vec<u8>;
 type TransactionId = Hash;
 
+/// A transaction id and the block height it was submitted at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub struct Submission {
+    pub transaction_id: TransactionId,
+    pub block_height: BlockHeight,
+}
+
+/// What a namespace costs, set by the owner.
+#[derive(Debug, Clone, PartialEq)]
+#[near(serializers=[borsh, json])]
+pub struct FeeSchedule {
+    /// Kept by the contract for each registration.
+    pub registration_fee: NearToken,
+    /// Staked for each byte a namespace stores, at least what NEAR locks for it.
+    pub byte_cost: NearToken,
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self {
+            registration_fee: NearToken::from_yoctonear(0),
+            byte_cost: env::storage_byte_cost(),
+        }
+    }
+}
+
+/// The storage a namespace uses and what was staked for it.
+#[derive(Debug, Clone, PartialEq)]
+#[near(serializers=[borsh, json])]
+pub struct Deposit {
+    /// The account refunded the stake as storage is released and when the
+    /// namespace is unregistered: whoever registered it, or was last
+    /// transferred it.
+    pub depositor: AccountId,
+    pub bytes: StorageUsage,
+    pub staked: NearToken,
+}
+
+impl Deposit {
+    fn new(depositor: AccountId) -> Self {
+        Self {
+            depositor,
+            bytes: 0,
+            staked: NearToken::from_yoctonear(0),
+        }
+    }
+}
+
 #[near]
 impl Contract {
     #[init]
@@ -51,12 +142,64 @@ impl Contract {
     pub fn new(owner_id: AccountId) -> Self {
         assert!(!env::state_exists(), "{ERR_CONTRACT_INITIALIZED}");
         let mut contract = Self {
-            info: Default::default(),
+            version: STATE_VERSION,
+            info: IterableMap::new(StorageKey::Info),
+            transactions: LookupMap::new(StorageKey::Transactions),
+            fees: FeeSchedule::default(),
+            deposits: LookupMap::new(StorageKey::Deposits),
         };
         Self::init(&mut contract, &owner_id);
         contract
     }
 
+    #[init(ignore_state)]
+    /// Upgrade the state left by an earlier version of the contract, only the owner can.
+    pub fn migrate() -> Self {
+        Self::require_owner();
+        let state =
+            env::storage_read(b"STATE").unwrap_or_else(|| env::panic_str(ERR_UNKNOWN_STATE));
+        // a migrated state has a version, so it can't be migrated again
+        if Self::try_from_slice(&state).is_ok() {
+            env::panic_str(ERR_ALREADY_MIGRATED);
+        }
+        let (info, transactions) = if let Ok(old) = ContractV1::try_from_slice(&state) {
+            let mut info = IterableMap::new(StorageKey::Info);
+            let mut namespaces: Vec<_> = old.info.into_iter().collect();
+            namespaces.sort_by_key(|(namespace, _)| *namespace);
+            info.extend(namespaces);
+            (info, LookupMap::new(StorageKey::Transactions))
+        } else if let Ok(old) = ContractV2::try_from_slice(&state) {
+            (old.info, old.transactions)
+        } else {
+            env::panic_str(ERR_UNKNOWN_STATE);
+        };
+
+        // only the owner gets here
+        let owner = env::predecessor_account_id();
+        let mut deposits = LookupMap::new(StorageKey::Deposits);
+        for (namespace, metadata) in info.iter() {
+            // Their flat deposits were kept, so nothing is staked for them yet
+            let depositor = metadata
+                .maintainers
+                .iter()
+                .find_map(maintainer_account)
+                .unwrap_or_else(|| owner.clone());
+            deposits.insert(*namespace, Deposit::new(depositor));
+        }
+        Self {
+            version: STATE_VERSION,
+            info,
+            transactions,
+            fees: FeeSchedule::default(),
+            deposits,
+        }
+    }
+
+    /// Get the version of the state layout.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
     /// Get the priority level.
     pub fn priority(&self, namespace: Namespace) -> Option<Priority> {
         self.info.get(&namespace).map(|metadata| metadata.priority)
@@ -76,34 +219,147 @@ impl Contract {
             .and_then(|metadata| metadata.extra.clone())
     }
 
-    /// Add a new maintainer.
+    /// Get up to `limit` submissions of the namespace, starting at index `from`.
+    pub fn transactions(&self, namespace: Namespace, from: u32, limit: u32) -> Vec<Submission> {
+        self.transactions
+            .get(&namespace)
+            .map(|submissions| {
+                let to = from
+                    .saturating_add(limit.min(MAX_LIMIT))
+                    .min(submissions.len());
+                (from..to)
+                    .filter_map(|index| submissions.get(index).cloned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the last submission of the namespace.
+    pub fn latest(&self, namespace: Namespace) -> Option<Submission> {
+        self.transactions.get(&namespace).and_then(|submissions| {
+            let last = submissions.len().checked_sub(1)?;
+            submissions.get(last).cloned()
+        })
+    }
+
+    /// Get the fee schedule.
+    pub fn fees(&self) -> FeeSchedule {
+        self.fees.clone()
+    }
+
+    /// Get the storage staked by the namespace.
+    pub fn deposit(&self, namespace: Namespace) -> Option<Deposit> {
+        self.deposits.get(&namespace).cloned()
+    }
+
+    /// Set the fee schedule, only the owner can. Stakes that were already paid are
+    /// kept as they are.
+    pub fn set_fees(&mut self, fees: FeeSchedule) {
+        Self::require_owner();
+        if fees.byte_cost < env::storage_byte_cost() {
+            env::panic_str(ERR_BYTE_COST_TOO_LOW);
+        }
+        self.fees = fees;
+    }
+
+    /// Get up to `limit` namespaces and their metadata, starting at index `from`.
+    pub fn namespaces(&self, from: u32, limit: u32) -> Vec<(Namespace, Metadata)> {
+        self.info
+            .iter()
+            .skip(from as usize)
+            .take(limit.min(MAX_LIMIT) as usize)
+            .map(|(namespace, metadata)| (*namespace, metadata.clone()))
+            .collect()
+    }
+
+    /// Add a new maintainer. The deposit pays for the storage.
+    #[payable]
     pub fn add_maintainer(&mut self, namespace: Namespace, maintainer: Maintainer) {
-        match self.check_authorized(namespace) {
-            Some(mut metadata) => {
-                // add it to the set and log the inclusion
-                if metadata.maintainers.insert(maintainer.clone()) {
-                    EventLog::maintainer(maintainer);
-                };
-            }
-            None => {
-                env::panic_str(ERR_UNAUTHORIZED_CALLER);
+        let initial_storage = env::storage_usage();
+        // add it to the set and log the inclusion
+        if self
+            .authorized_metadata(namespace)
+            .maintainers
+            .insert(maintainer.clone())
+        {
+            EventLog::maintainer(namespace, maintainer);
+        }
+        self.settle_storage(namespace, initial_storage, env::attached_deposit());
+    }
+
+    /// Remove a maintainer.
+    pub fn remove_maintainer(&mut self, namespace: Namespace, maintainer: Maintainer) {
+        let initial_storage = env::storage_usage();
+        if self
+            .authorized_metadata(namespace)
+            .maintainers
+            .remove(&maintainer)
+        {
+            EventLog::remove_maintainer(namespace, maintainer);
+        }
+        self.settle_storage(namespace, initial_storage, env::attached_deposit());
+    }
+
+    /// Replace all the maintainers with a new one, who is then refunded the
+    /// stake when the namespace is unregistered. The deposit pays for the storage.
+    #[payable]
+    pub fn transfer_namespace(&mut self, namespace: Namespace, maintainer: Maintainer) {
+        let initial_storage = env::storage_usage();
+        self.authorized_metadata(namespace).maintainers = HashSet::from([maintainer.clone()]);
+        if let Some(account) = maintainer_account(&maintainer) {
+            if let Some(deposit) = self.deposits.get_mut(&namespace) {
+                deposit.depositor = account;
             }
         }
+        EventLog::transfer_namespace(namespace, maintainer);
+        self.settle_storage(namespace, initial_storage, env::attached_deposit());
+    }
+
+    /// Set the extra information in the metadata. The deposit pays for the storage.
+    #[payable]
+    pub fn set_extra(&mut self, namespace: Namespace, extra: Option<String>) {
+        let initial_storage = env::storage_usage();
+        self.authorized_metadata(namespace).extra = extra.clone();
+        EventLog::extra(namespace, extra);
+        self.settle_storage(namespace, initial_storage, env::attached_deposit());
     }
 
-    /// Submit the blob and the namespace.
-    pub fn submit(&self, namespace: Namespace, _transaction_ids: Vec<TransactionId>) {
+    /// Set the priority level, only the owner can.
+    pub fn set_priority(&mut self, namespace: Namespace, priority: Priority) {
+        Self::require_owner();
+        match self.info.get_mut(&namespace) {
+            Some(metadata) => metadata.priority = priority,
+            None => env::panic_str(ERR_NAMESPACE_MISSING),
+        }
+        EventLog::priority(namespace, priority);
+    }
+
+    /// Record the transaction ids of blobs submitted for the namespace. The
+    /// deposit pays for the storage.
+    #[payable]
+    pub fn submit(&mut self, namespace: Namespace, transaction_ids: Vec<TransactionId>) {
         // check the namespace exists and the caller is in the maintainers list
-        match self.check_authorized(namespace) {
-            Some(_) => {
-                env::input()
-                    .is_none()
-                    .then(|| env::panic_str(ERR_INVALID_INPUT));
-            }
-            None => {
-                env::panic_str(ERR_UNAUTHORIZED_CALLER);
-            }
+        if self.check_authorized(namespace).is_none() {
+            env::panic_str(ERR_UNAUTHORIZED_CALLER);
+        }
+        if transaction_ids.is_empty() {
+            env::panic_str(ERR_INVALID_INPUT);
+        }
+
+        let initial_storage = env::storage_usage();
+        let block_height = env::block_height();
+        let submissions = self
+            .transactions
+            .entry(namespace)
+            .or_insert_with(|| Vector::new(StorageKey::NamespaceTransactions(namespace)));
+        for transaction_id in &transaction_ids {
+            submissions.push(Submission {
+                transaction_id: *transaction_id,
+                block_height,
+            });
         }
+        EventLog::blob_submitted(namespace, transaction_ids, block_height);
+        self.settle_storage(namespace, initial_storage, env::attached_deposit());
     }
 
     /// Transfer the ownership of the contract. An event is emited by `Self::update_owner`.
@@ -113,39 +369,85 @@ impl Contract {
         Self::update_owner(self, Some(new_owner_id.clone()));
     }
 
-    /// Register a DA consumer.
+    /// Register a DA consumer. The deposit pays the registration fee and
+    /// stakes the storage, the rest is refunded.
     #[payable]
     pub fn register_consumer(&mut self, namespace: Namespace) {
         if self.info.get(&namespace).is_some() {
             // when the namespace does not exist,
             env::panic_str(ERR_NAMESPACE_EXISTS);
         } else {
-            // when the deposit is enough
-            if env::attached_deposit() >= NearToken::from_millinear(MINIMUM_DEPOSIT.into()) {
-                // and the namespace does not exist, then it can be registered
-                let metadata = Metadata {
-                    maintainers: HashSet::from([env::predecessor_account_id().as_bytes().to_vec()]),
-                    ..Default::default()
-                };
-                self.info.insert(namespace, metadata);
-                // and an event can be emitted
-                EventLog::namespace(namespace);
-            } else {
+            // when the deposit covers the fee
+            let Some(deposit) = env::attached_deposit().checked_sub(self.fees.registration_fee)
+            else {
                 env::panic_str(ERR_NOT_ENOUGHT_FUNDS);
+            };
+            // and the namespace does not exist, then it can be registered
+            let initial_storage = env::storage_usage();
+            let predecessor = env::predecessor_account_id();
+            let metadata = Metadata {
+                maintainers: HashSet::from([predecessor.as_bytes().to_vec()]),
+                ..Default::default()
+            };
+            self.info.insert(namespace, metadata);
+            self.deposits.insert(namespace, Deposit::new(predecessor));
+            self.settle_storage(namespace, initial_storage, deposit);
+            // and an event can be emitted
+            EventLog::namespace(namespace);
+        }
+    }
+
+    /// Remove up to `limit` of the namespace's latest submissions. The stake of
+    /// the storage they free is refunded to the depositor.
+    pub fn clear_submissions(&mut self, namespace: Namespace, limit: u32) {
+        if self.check_authorized(namespace).is_none() {
+            env::panic_str(ERR_UNAUTHORIZED_CALLER);
+        }
+        let initial_storage = env::storage_usage();
+        if let Some(submissions) = self.transactions.get_mut(&namespace) {
+            for _ in 0..limit.min(MAX_LIMIT) {
+                if submissions.pop().is_none() {
+                    break;
+                }
             }
         }
+        self.settle_storage(namespace, initial_storage, env::attached_deposit());
+    }
+
+    /// Remove the namespace and its submissions. What is still staked is
+    /// refunded to the depositor. A namespace with more than 100 submissions
+    /// has to be cleared with `clear_submissions` first.
+    pub fn unregister(&mut self, namespace: Namespace) {
+        if self.check_authorized(namespace).is_none() {
+            env::panic_str(ERR_UNAUTHORIZED_CALLER);
+        }
+        if let Some(submissions) = self.transactions.get(&namespace) {
+            if submissions.len() > MAX_LIMIT {
+                env::panic_str(ERR_TOO_MANY_SUBMISSIONS);
+            }
+        }
+        self.info.remove(&namespace);
+        if let Some(mut submissions) = self.transactions.remove(&namespace) {
+            submissions.clear();
+        }
+        if let Some(deposit) = self.deposits.remove(&namespace) {
+            if !deposit.staked.is_zero() {
+                Promise::new(deposit.depositor).transfer(deposit.staked);
+            }
+        }
+        EventLog::unregister(namespace);
     }
 }
 
 impl Contract {
     /// Helper function to check that the caller is authorized to call the method.
-    fn check_authorized(&self, namespace: Namespace) -> Option<Metadata> {
+    fn check_authorized(&self, namespace: Namespace) -> Option<&Metadata> {
         let predecessor = env::predecessor_account_id();
         if let Some(metadata) = self.info.get(&namespace) {
             if self.own_get_owner().unwrap() == predecessor
                 || metadata.maintainers.contains(predecessor.as_bytes())
             {
-                Some(metadata.clone())
+                Some(metadata)
             } else {
                 None
             }
@@ -153,10 +455,75 @@ impl Contract {
             env::panic_str(ERR_NAMESPACE_MISSING);
         }
     }
+
+    /// Helper function to stake the storage the call added to the namespace from
+    /// `attached`, or release the stake of the storage it freed to the depositor.
+    /// What is left of `attached` goes back to the caller.
+    fn settle_storage(
+        &mut self,
+        namespace: Namespace,
+        initial_storage: StorageUsage,
+        attached: NearToken,
+    ) {
+        // the collections cache their writes, so they are counted once flushed
+        self.info.flush();
+        self.deposits.flush();
+        if let Some(submissions) = self.transactions.get_mut(&namespace) {
+            submissions.flush();
+        }
+        self.transactions.flush();
+        let storage = env::storage_usage();
+
+        let byte_cost = self.fees.byte_cost;
+        let deposit = self
+            .deposits
+            .get_mut(&namespace)
+            .unwrap_or_else(|| env::panic_str(ERR_NAMESPACE_MISSING));
+        let mut refund = attached;
+        if storage > initial_storage {
+            let added = storage - initial_storage;
+            let cost = byte_cost.saturating_mul(added.into());
+            refund = attached
+                .checked_sub(cost)
+                .unwrap_or_else(|| env::panic_str(ERR_NOT_ENOUGH_STORAGE_DEPOSIT));
+            deposit.bytes += added;
+            deposit.staked = deposit.staked.saturating_add(cost);
+        } else if deposit.bytes > 0 {
+            // release the stake in proportion, as bytes may have cost less before
+            let freed = (initial_storage - storage).min(deposit.bytes);
+            let released = NearToken::from_yoctonear(
+                deposit.staked.as_yoctonear() / u128::from(deposit.bytes) * u128::from(freed),
+            );
+            deposit.bytes -= freed;
+            deposit.staked = deposit.staked.saturating_sub(released);
+            if !released.is_zero() {
+                Promise::new(deposit.depositor.clone()).transfer(released);
+            }
+        }
+        if !refund.is_zero() {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+    }
+
+    /// Helper function to get the metadata for a change, if the caller is authorized.
+    fn authorized_metadata(&mut self, namespace: Namespace) -> &mut Metadata {
+        if self.check_authorized(namespace).is_none() {
+            env::panic_str(ERR_UNAUTHORIZED_CALLER);
+        }
+        self.info
+            .get_mut(&namespace)
+            .unwrap_or_else(|| env::panic_str(ERR_NAMESPACE_MISSING))
+    }
+}
+
+/// The account a maintainer is, if it is one.
+fn maintainer_account(maintainer: &Maintainer) -> Option<AccountId> {
+    std::str::from_utf8(maintainer).ok()?.parse().ok()
 }
 
 /// Hash type for represennting the transaction id.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[near(serializers=[borsh])]
 pub struct Hash([u8; 32]);
 
 impl Serialize for Hash {
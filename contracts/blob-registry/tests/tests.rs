@@ -1,7 +1,7 @@
 #![cfg(test)]
 
 use near_sdk::NearToken;
-use serde_json::json;
+use serde_json::{json, Value};
 
 #[tokio::test]
 async fn new() -> anyhow::Result<()> {
@@ -52,7 +52,7 @@ async fn register_consumer_not_enough_funds_errs() -> anyhow::Result<()> {
     let registration = alice
         .call(contract.id(), "register_consumer")
         .args_json(json!({ "namespace": 0 }))
-        .deposit(NearToken::from_millinear(99).as_yoctonear())
+        .deposit(NearToken::from_yoctonear(1).as_yoctonear())
         .transact()
         .await?
         .into_result();
@@ -91,20 +91,106 @@ async fn submit() -> anyhow::Result<()> {
         .into_result()?;
 
     let mut tx_ids = vec![];
-    for _ in 0..100 {
-        tx_ids.push(hex::encode([1u8; 32]));
+    for i in 0..100 {
+        tx_ids.push(hex::encode([i as u8; 32]));
     }
 
     eprintln!("Submitting {} TX IDs...", tx_ids.len());
     let result = alice
         .call(contract.id(), "submit")
-        .args_json(json!({ "namespace": 0, "_transaction_ids": tx_ids }))
+        .args_json(json!({ "namespace": 0, "transaction_ids": tx_ids }))
+        .deposit(NearToken::from_near(1).as_yoctonear())
         .transact()
         .await?
         .into_result()?;
 
     println!("Gas burned: {}", result.total_gas_burnt);
 
+    // The ids are emitted as a NEP-297 event
+    let logs = result.logs();
+    assert_eq!(logs.len(), 1);
+    let event: Value = serde_json::from_str(logs[0].strip_prefix("EVENT_JSON:").unwrap())?;
+    assert_eq!(event["event"], "blob_submitted");
+    assert_eq!(event["data"]["namespace"], 0);
+    assert_eq!(event["data"]["transaction_ids"], json!(tx_ids));
+    let block_height = event["data"]["block_height"].as_u64().unwrap();
+
+    // and stored with the height they were submitted at
+    let page = contract
+        .view("transactions")
+        .args_json(json!({ "namespace": 0, "from": 10, "limit": 5 }))
+        .await?
+        .json::<Vec<Value>>()?;
+    assert_eq!(page.len(), 5);
+    for (i, submission) in page.iter().enumerate() {
+        assert_eq!(submission["transaction_id"], tx_ids[10 + i]);
+        assert_eq!(submission["block_height"], block_height);
+    }
+
+    // Pages stop at the last submission
+    let page = contract
+        .view("transactions")
+        .args_json(json!({ "namespace": 0, "from": 98, "limit": 5 }))
+        .await?
+        .json::<Vec<Value>>()?;
+    assert_eq!(page.len(), 2);
+
+    let latest = contract
+        .view("latest")
+        .args_json(json!({ "namespace": 0 }))
+        .await?
+        .json::<Value>()?;
+    assert_eq!(latest["transaction_id"], tx_ids[99]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn submit_unauthorized_errs() -> anyhow::Result<()> {
+    // Create a new sandbox for testing.
+    let worker = near_workspaces::sandbox().await?;
+    // Compile the contract.
+    let wasm = near_workspaces::compile_project(".").await?;
+    // Deploy the (wasm) contract into the sandbox.
+    let contract = worker.dev_deploy(&wasm).await?;
+    // Create dev accounts for testing.
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+
+    // Calling contract's `new()`
+    contract
+        .call("new")
+        .args_json(json!({ "owner_id": alice.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // Register a consumer
+    alice
+        .call(contract.id(), "register_consumer")
+        .args_json(json!({ "namespace": 0 }))
+        .deposit(NearToken::from_millinear(100).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+
+    // Bob is not a maintainer of the namespace
+    let submission = bob
+        .call(contract.id(), "submit")
+        .args_json(json!({ "namespace": 0, "transaction_ids": [hex::encode([1u8; 32])] }))
+        .transact()
+        .await?
+        .into_result();
+    assert!(submission.is_err());
+
+    // Nothing is recorded
+    let latest = contract
+        .view("latest")
+        .args_json(json!({ "namespace": 0 }))
+        .await?
+        .json::<Option<Value>>()?;
+    assert!(latest.is_none());
+
     Ok(())
 }
 
@@ -158,3 +244,609 @@ async fn owner_change() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn manage_namespace() -> anyhow::Result<()> {
+    // Create a new sandbox for testing.
+    let worker = near_workspaces::sandbox().await?;
+    // Compile the contract.
+    let wasm = near_workspaces::compile_project(".").await?;
+    // Deploy the (wasm) contract into the sandbox.
+    let contract = worker.dev_deploy(&wasm).await?;
+    // Create dev accounts for testing.
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let carol = worker.dev_create_account().await?;
+
+    // Calling contract's `new()`
+    contract
+        .call("new")
+        .args_json(json!({ "owner_id": alice.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // Bob registers a consumer, so he is its only maintainer
+    bob.call(contract.id(), "register_consumer")
+        .args_json(json!({ "namespace": 7 }))
+        .deposit(NearToken::from_millinear(100).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+
+    let maintainers = |contract: near_workspaces::Contract| async move {
+        contract
+            .view("maintainers")
+            .args_json(json!({ "namespace": 7 }))
+            .await?
+            .json::<Vec<Vec<u8>>>()
+    };
+
+    // Bob adds Carol
+    let result = bob
+        .call(contract.id(), "add_maintainer")
+        .args_json(json!({ "namespace": 7, "maintainer": carol.id().as_bytes() }))
+        .deposit(NearToken::from_millinear(10).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+    assert!(result.logs()[0].contains("\"event\":\"add_maintainer\""));
+    assert_eq!(maintainers(contract.clone()).await?.len(), 2);
+
+    // and removes himself
+    let result = bob
+        .call(contract.id(), "remove_maintainer")
+        .args_json(json!({ "namespace": 7, "maintainer": bob.id().as_bytes() }))
+        .transact()
+        .await?
+        .into_result()?;
+    assert!(result.logs()[0].contains("\"event\":\"remove_maintainer\""));
+    assert_eq!(
+        maintainers(contract.clone()).await?,
+        vec![carol.id().as_bytes().to_vec()]
+    );
+
+    // so he can no longer change the metadata
+    let extra = bob
+        .call(contract.id(), "set_extra")
+        .args_json(json!({ "namespace": 7, "extra": "bob's rollup" }))
+        .transact()
+        .await?
+        .into_result();
+    assert!(extra.is_err());
+
+    // but Carol can
+    let result = carol
+        .call(contract.id(), "set_extra")
+        .args_json(json!({ "namespace": 7, "extra": "carol's rollup" }))
+        .deposit(NearToken::from_millinear(10).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+    assert!(result.logs()[0].contains("\"event\":\"extra_update\""));
+    let extra = contract
+        .view("extra")
+        .args_json(json!({ "namespace": 7 }))
+        .await?
+        .json::<Option<String>>()?;
+    assert_eq!(extra.as_deref(), Some("carol's rollup"));
+
+    // Only the owner can set the priority
+    let priority = carol
+        .call(contract.id(), "set_priority")
+        .args_json(json!({ "namespace": 7, "priority": 3 }))
+        .transact()
+        .await?
+        .into_result();
+    assert!(priority.is_err());
+    let result = alice
+        .call(contract.id(), "set_priority")
+        .args_json(json!({ "namespace": 7, "priority": 3 }))
+        .transact()
+        .await?
+        .into_result()?;
+    assert!(result.logs()[0].contains("\"event\":\"priority_update\""));
+    let priority = contract
+        .view("priority")
+        .args_json(json!({ "namespace": 7 }))
+        .await?
+        .json::<Option<u32>>()?;
+    assert_eq!(priority, Some(3));
+
+    // Carol hands the namespace over to Bob
+    let result = carol
+        .call(contract.id(), "transfer_namespace")
+        .args_json(json!({ "namespace": 7, "maintainer": bob.id().as_bytes() }))
+        .deposit(NearToken::from_millinear(10).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+    assert!(result.logs()[0].contains("\"event\":\"namespace_transfer\""));
+    assert_eq!(
+        maintainers(contract.clone()).await?,
+        vec![bob.id().as_bytes().to_vec()]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn namespaces() -> anyhow::Result<()> {
+    // Create a new sandbox for testing.
+    let worker = near_workspaces::sandbox().await?;
+    // Compile the contract.
+    let wasm = near_workspaces::compile_project(".").await?;
+    // Deploy the (wasm) contract into the sandbox.
+    let contract = worker.dev_deploy(&wasm).await?;
+    // Create a dev account for testing.
+    let alice = worker.dev_create_account().await?;
+
+    // Calling contract's `new()`
+    contract
+        .call("new")
+        .args_json(json!({ "owner_id": alice.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    for namespace in [5, 1, 3] {
+        alice
+            .call(contract.id(), "register_consumer")
+            .args_json(json!({ "namespace": namespace }))
+            .deposit(NearToken::from_millinear(100).as_yoctonear())
+            .transact()
+            .await?
+            .into_result()?;
+    }
+
+    // Pages are in registration order
+    let page = |from: u32, limit: u32| {
+        let contract = contract.clone();
+        async move {
+            let page = contract
+                .view("namespaces")
+                .args_json(json!({ "from": from, "limit": limit }))
+                .await?
+                .json::<Vec<(u32, Value)>>()?;
+            anyhow::Ok(
+                page.into_iter()
+                    .map(|(namespace, _)| namespace)
+                    .collect::<Vec<_>>(),
+            )
+        }
+    };
+    assert_eq!(page(0, 2).await?, vec![5, 1]);
+    assert_eq!(page(2, 2).await?, vec![3]);
+    assert!(page(3, 2).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn migrate() -> anyhow::Result<()> {
+    // Create a new sandbox for testing.
+    let worker = near_workspaces::sandbox().await?;
+    // Compile the first version of the contract and the current one.
+    let v1 = near_workspaces::compile_project("./tests/v1").await?;
+    let wasm = near_workspaces::compile_project(".").await?;
+    // Deploy the first version into the sandbox.
+    let contract = worker.dev_deploy(&v1).await?;
+    // Create dev accounts for testing.
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+
+    // Calling contract's `new()` stores the owner
+    contract
+        .call("new")
+        .args_json(json!({ "owner_id": alice.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // and the first version registers a namespace for each of them
+    for (account, namespace) in [(&bob, 2), (&alice, 1)] {
+        account
+            .call(contract.id(), "register_consumer")
+            .args_json(json!({ "namespace": namespace }))
+            .deposit(NearToken::from_millinear(100).as_yoctonear())
+            .transact()
+            .await?
+            .into_result()?;
+    }
+
+    // then the current code is deployed over it
+    contract.as_account().deploy(&wasm).await?.into_result()?;
+
+    // Only the owner can migrate
+    let migration = bob
+        .call(contract.id(), "migrate")
+        .transact()
+        .await?
+        .into_result();
+    assert!(migration.is_err());
+
+    alice
+        .call(contract.id(), "migrate")
+        .transact()
+        .await?
+        .into_result()?;
+
+    let version = contract.view("version").await?.json::<u8>()?;
+    assert_eq!(version, 3);
+    let namespaces = contract
+        .view("namespaces")
+        .args_json(json!({ "from": 0, "limit": 10 }))
+        .await?
+        .json::<Vec<(u32, Value)>>()?;
+    assert_eq!(namespaces.len(), 2);
+    assert_eq!(namespaces[0].0, 1);
+    assert_eq!(
+        namespaces[0].1["maintainers"],
+        json!([alice.id().as_bytes()])
+    );
+    assert_eq!(namespaces[1].0, 2);
+    assert_eq!(namespaces[1].1["maintainers"], json!([bob.id().as_bytes()]));
+    let deposit = contract
+        .view("deposit")
+        .args_json(json!({ "namespace": 2 }))
+        .await?
+        .json::<Value>()?;
+    assert_eq!(deposit["depositor"], bob.id().as_str());
+
+    // and the namespaces keep working
+    bob.call(contract.id(), "submit")
+        .args_json(json!({ "namespace": 2, "transaction_ids": [hex::encode([1u8; 32])] }))
+        .deposit(NearToken::from_millinear(10).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+
+    // The state can't be migrated twice
+    let migration = alice
+        .call(contract.id(), "migrate")
+        .transact()
+        .await?
+        .into_result();
+    assert!(format!("{:?}", migration.unwrap_err()).contains("already at the current version"));
+    assert_eq!(contract.view("version").await?.json::<u8>()?, 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn storage_deposits() -> anyhow::Result<()> {
+    // Create a new sandbox for testing.
+    let worker = near_workspaces::sandbox().await?;
+    // Compile the contract.
+    let wasm = near_workspaces::compile_project(".").await?;
+    // Deploy the (wasm) contract into the sandbox.
+    let contract = worker.dev_deploy(&wasm).await?;
+    // Create dev accounts for testing.
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+
+    // Calling contract's `new()`
+    contract
+        .call("new")
+        .args_json(json!({ "owner_id": alice.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let fees = contract.view("fees").await?.json::<Value>()?;
+    let byte_cost: u128 = fees["byte_cost"].as_str().unwrap().parse()?;
+    let deposit = |namespace: u32| {
+        let contract = contract.clone();
+        async move {
+            let deposit = contract
+                .view("deposit")
+                .args_json(json!({ "namespace": namespace }))
+                .await?
+                .json::<Option<Value>>()?;
+            anyhow::Ok(deposit.map(|deposit| {
+                let bytes = deposit["bytes"].as_u64().unwrap();
+                let staked: u128 = deposit["staked"].as_str().unwrap().parse().unwrap();
+                (bytes, staked)
+            }))
+        }
+    };
+
+    // Bob registers with far more than the storage costs, and the rest is refunded
+    let before = bob.view_account().await?.balance;
+    bob.call(contract.id(), "register_consumer")
+        .args_json(json!({ "namespace": 1 }))
+        .deposit(NearToken::from_near(5).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+    let (bytes, staked) = deposit(1).await?.unwrap();
+    assert!(bytes > 0);
+    assert_eq!(staked, byte_cost * bytes as u128);
+    let spent = before - bob.view_account().await?.balance;
+    assert!(
+        spent < NearToken::from_millinear(100).as_yoctonear(),
+        "{spent}"
+    );
+
+    // Submissions stake their storage too
+    let tx_ids: Vec<String> = (0..50u8).map(|i| hex::encode([i; 32])).collect();
+    let not_enough = bob
+        .call(contract.id(), "submit")
+        .args_json(json!({ "namespace": 1, "transaction_ids": tx_ids }))
+        .deposit(byte_cost)
+        .transact()
+        .await?
+        .into_result();
+    assert!(not_enough.is_err());
+    bob.call(contract.id(), "submit")
+        .args_json(json!({ "namespace": 1, "transaction_ids": tx_ids }))
+        .deposit(NearToken::from_near(1).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+    let (submitted_bytes, submitted_stake) = deposit(1).await?.unwrap();
+    assert!(submitted_bytes > bytes);
+    assert_eq!(submitted_stake, byte_cost * submitted_bytes as u128);
+
+    // Freeing storage releases its stake to the depositor, even when the
+    // owner frees it
+    let large = vec![7u8; 2000];
+    bob.call(contract.id(), "add_maintainer")
+        .args_json(json!({ "namespace": 1, "maintainer": large }))
+        .deposit(NearToken::from_millinear(100).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+    let (_, added_stake) = deposit(1).await?.unwrap();
+    let bob_before = bob.view_account().await?.balance;
+    let alice_before = alice.view_account().await?.balance;
+    alice
+        .call(contract.id(), "remove_maintainer")
+        .args_json(json!({ "namespace": 1, "maintainer": large }))
+        .transact()
+        .await?
+        .into_result()?;
+    assert_eq!(deposit(1).await?, Some((submitted_bytes, submitted_stake)));
+    assert_eq!(
+        bob.view_account().await?.balance - bob_before,
+        added_stake - submitted_stake
+    );
+    assert!(alice.view_account().await?.balance < alice_before);
+
+    // A namespace with more submissions than a page can't be unregistered
+    // before they're cleared
+    let tx_ids: Vec<String> = (50..101u8).map(|i| hex::encode([i; 32])).collect();
+    bob.call(contract.id(), "submit")
+        .args_json(json!({ "namespace": 1, "transaction_ids": tx_ids }))
+        .deposit(NearToken::from_near(1).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+    let too_many = bob
+        .call(contract.id(), "unregister")
+        .args_json(json!({ "namespace": 1 }))
+        .transact()
+        .await?
+        .into_result();
+    assert!(too_many.is_err());
+    let (full_bytes, _) = deposit(1).await?.unwrap();
+    bob.call(contract.id(), "clear_submissions")
+        .args_json(json!({ "namespace": 1, "limit": 10 }))
+        .transact()
+        .await?
+        .into_result()?;
+    let latest = contract
+        .view("latest")
+        .args_json(json!({ "namespace": 1 }))
+        .await?
+        .json::<Value>()?;
+    assert_eq!(latest["transaction_id"], hex::encode([90u8; 32]));
+    let (cleared_bytes, cleared_stake) = deposit(1).await?.unwrap();
+    assert!(cleared_bytes < full_bytes);
+    assert_eq!(cleared_stake, byte_cost * cleared_bytes as u128);
+
+    // Handing the namespace over moves who gets the stake back
+    bob.call(contract.id(), "transfer_namespace")
+        .args_json(json!({ "namespace": 1, "maintainer": alice.id().as_bytes() }))
+        .deposit(NearToken::from_millinear(10).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+    let depositor = contract
+        .view("deposit")
+        .args_json(json!({ "namespace": 1 }))
+        .await?
+        .json::<Value>()?["depositor"]
+        .clone();
+    assert_eq!(depositor, alice.id().as_str());
+
+    // Unregistering refunds the rest
+    let before = alice.view_account().await?.balance;
+    let result = alice
+        .call(contract.id(), "unregister")
+        .args_json(json!({ "namespace": 1 }))
+        .transact()
+        .await?
+        .into_result()?;
+    assert!(result.logs()[0].contains("\"event\":\"namespace_unregistration\""));
+    assert!(alice.view_account().await?.balance > before);
+    assert_eq!(deposit(1).await?, None);
+    let submissions = contract
+        .view("transactions")
+        .args_json(json!({ "namespace": 1, "from": 0, "limit": 10 }))
+        .await?
+        .json::<Vec<Value>>()?;
+    assert!(submissions.is_empty());
+
+    // and the namespace can be registered again
+    alice
+        .call(contract.id(), "register_consumer")
+        .args_json(json!({ "namespace": 1 }))
+        .deposit(NearToken::from_millinear(10).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn clear_submissions() -> anyhow::Result<()> {
+    // Create a new sandbox for testing.
+    let worker = near_workspaces::sandbox().await?;
+    // Compile the contract.
+    let wasm = near_workspaces::compile_project(".").await?;
+    // Deploy the (wasm) contract into the sandbox.
+    let contract = worker.dev_deploy(&wasm).await?;
+    // Create dev accounts for testing.
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let carol = worker.dev_create_account().await?;
+
+    // Calling contract's `new()`
+    contract
+        .call("new")
+        .args_json(json!({ "owner_id": alice.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let staked = || {
+        let contract = contract.clone();
+        async move {
+            let deposit = contract
+                .view("deposit")
+                .args_json(json!({ "namespace": 1 }))
+                .await?
+                .json::<Value>()?;
+            anyhow::Ok(deposit["staked"].as_str().unwrap().parse::<u128>()?)
+        }
+    };
+
+    // Bob registers a namespace, pays for its submissions and lets Carol
+    // maintain it too
+    bob.call(contract.id(), "register_consumer")
+        .args_json(json!({ "namespace": 1 }))
+        .deposit(NearToken::from_millinear(100).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+    let tx_ids: Vec<String> = (0..100u8).map(|i| hex::encode([i; 32])).collect();
+    bob.call(contract.id(), "submit")
+        .args_json(json!({ "namespace": 1, "transaction_ids": tx_ids }))
+        .deposit(NearToken::from_near(1).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+    bob.call(contract.id(), "add_maintainer")
+        .args_json(json!({ "namespace": 1, "maintainer": carol.id().as_bytes() }))
+        .deposit(NearToken::from_millinear(10).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+
+    // Carol clears them, and the stake Bob paid goes back to Bob, not Carol
+    let before = staked().await?;
+    let bob_before = bob.view_account().await?.balance;
+    let carol_before = carol.view_account().await?.balance;
+    carol
+        .call(contract.id(), "clear_submissions")
+        .args_json(json!({ "namespace": 1, "limit": 100 }))
+        .transact()
+        .await?
+        .into_result()?;
+    let released = before - staked().await?;
+    assert!(released > 0);
+    assert_eq!(bob.view_account().await?.balance - bob_before, released);
+    assert!(carol.view_account().await?.balance < carol_before);
+    let latest = contract
+        .view("latest")
+        .args_json(json!({ "namespace": 1 }))
+        .await?
+        .json::<Option<Value>>()?;
+    assert_eq!(latest, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fee_schedule() -> anyhow::Result<()> {
+    // Create a new sandbox for testing.
+    let worker = near_workspaces::sandbox().await?;
+    // Compile the contract.
+    let wasm = near_workspaces::compile_project(".").await?;
+    // Deploy the (wasm) contract into the sandbox.
+    let contract = worker.dev_deploy(&wasm).await?;
+    // Create dev accounts for testing.
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+
+    // Calling contract's `new()`
+    contract
+        .call("new")
+        .args_json(json!({ "owner_id": alice.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let fees = contract.view("fees").await?.json::<Value>()?;
+    assert_eq!(fees["registration_fee"], "0");
+    let byte_cost: u128 = fees["byte_cost"].as_str().unwrap().parse()?;
+
+    let schedule = json!({
+        "registration_fee": NearToken::from_near(1).as_yoctonear().to_string(),
+        "byte_cost": (byte_cost * 2).to_string(),
+    });
+
+    // Only the owner can set the fees
+    let set_fees = bob
+        .call(contract.id(), "set_fees")
+        .args_json(json!({ "fees": schedule }))
+        .transact()
+        .await?
+        .into_result();
+    assert!(set_fees.is_err());
+
+    // and not below what NEAR charges for storage
+    let set_fees = alice
+        .call(contract.id(), "set_fees")
+        .args_json(json!({ "fees": { "registration_fee": "0", "byte_cost": (byte_cost - 1).to_string() } }))
+        .transact()
+        .await?
+        .into_result();
+    assert!(set_fees.is_err());
+
+    alice
+        .call(contract.id(), "set_fees")
+        .args_json(json!({ "fees": schedule }))
+        .transact()
+        .await?
+        .into_result()?;
+    assert_eq!(contract.view("fees").await?.json::<Value>()?, schedule);
+
+    // The fee alone doesn't cover the storage
+    let registration = bob
+        .call(contract.id(), "register_consumer")
+        .args_json(json!({ "namespace": 1 }))
+        .deposit(NearToken::from_near(1).as_yoctonear())
+        .transact()
+        .await?
+        .into_result();
+    assert!(registration.is_err());
+
+    bob.call(contract.id(), "register_consumer")
+        .args_json(json!({ "namespace": 1 }))
+        .deposit(NearToken::from_millinear(1100).as_yoctonear())
+        .transact()
+        .await?
+        .into_result()?;
+    let deposit = contract
+        .view("deposit")
+        .args_json(json!({ "namespace": 1 }))
+        .await?
+        .json::<Value>()?;
+    let bytes = deposit["bytes"].as_u64().unwrap() as u128;
+    assert_eq!(deposit["staked"], (bytes * byte_cost * 2).to_string());
+
+    Ok(())
+}
@@ -0,0 +1,445 @@
+//! Method arguments, as a JSON object or in Borsh.
+//!
+//! Input that is a well formed JSON object is read as JSON, anything else as
+//! Borsh, which has to be consumed exactly. The JSON reader doesn't allocate:
+//! it walks the whole object, skipping the keys it wasn't asked for, and hands
+//! back the bytes of the value it was. Keys are compared as written, so an
+//! escaped key never matches.
+
+/// How deep skipped values may nest, so malformed input can't exhaust the stack.
+const MAX_DEPTH: usize = 16;
+const MIN_ACCOUNT_ID_LEN: usize = 2;
+const MAX_ACCOUNT_ID_LEN: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Value<'a> {
+    Null,
+    /// Between the quotes, with any escapes left in.
+    String(&'a [u8]),
+    /// As written.
+    Number(&'a [u8]),
+    /// A boolean, an array or an object.
+    Other,
+}
+
+struct Reader<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consumes `b` after any whitespace, if it is next.
+    fn eat_if(&mut self, b: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.peek() == Some(b);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat(&mut self, b: u8) -> Option<()> {
+        self.eat_if(b).then_some(())
+    }
+
+    fn literal(&mut self, literal: &[u8]) -> Option<()> {
+        let found = self.input.get(self.pos..)?.starts_with(literal);
+        if found {
+            self.pos += literal.len();
+        }
+        found.then_some(())
+    }
+
+    fn digits(&mut self) -> usize {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn string(&mut self) -> Option<&'a [u8]> {
+        self.eat(b'"')?;
+        let start = self.pos;
+        loop {
+            match self.peek()? {
+                b'"' => break,
+                b'\\' => {
+                    self.pos += 1;
+                    match self.peek()? {
+                        b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => self.pos += 1,
+                        b'u' => {
+                            let hex = self.input.get(self.pos + 1..self.pos + 5)?;
+                            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                                return None;
+                            }
+                            self.pos += 5;
+                        }
+                        _ => return None,
+                    }
+                }
+                0..=0x1f => return None,
+                _ => self.pos += 1,
+            }
+        }
+        let s = &self.input[start..self.pos];
+        self.pos += 1;
+        core::str::from_utf8(s).ok()?;
+        Some(s)
+    }
+
+    fn number(&mut self) -> Option<&'a [u8]> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek()? {
+            b'0' => self.pos += 1,
+            b'1'..=b'9' => {
+                self.digits();
+            }
+            _ => return None,
+        }
+        if self.literal(b".").is_some() && self.digits() == 0 {
+            return None;
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if self.digits() == 0 {
+                return None;
+            }
+        }
+        Some(&self.input[start..self.pos])
+    }
+
+    fn value(&mut self, depth: usize) -> Option<Value<'a>> {
+        self.skip_whitespace();
+        match self.peek()? {
+            b'"' => self.string().map(Value::String),
+            b'-' | b'0'..=b'9' => self.number().map(Value::Number),
+            b'n' => self.literal(b"null").map(|()| Value::Null),
+            b't' => self.literal(b"true").map(|()| Value::Other),
+            b'f' => self.literal(b"false").map(|()| Value::Other),
+            b'[' if depth < MAX_DEPTH => {
+                self.pos += 1;
+                if !self.eat_if(b']') {
+                    loop {
+                        self.value(depth + 1)?;
+                        if self.eat_if(b']') {
+                            break;
+                        }
+                        self.eat(b',')?;
+                    }
+                }
+                Some(Value::Other)
+            }
+            b'{' if depth < MAX_DEPTH => {
+                self.object(depth + 1, |_, _| Some(()))?;
+                Some(Value::Other)
+            }
+            _ => None,
+        }
+    }
+
+    /// Reads an object, handing each key and value to `f`, which can reject
+    /// the input by returning `None`.
+    fn object(
+        &mut self,
+        depth: usize,
+        mut f: impl FnMut(&'a [u8], Value<'a>) -> Option<()>,
+    ) -> Option<()> {
+        self.eat(b'{')?;
+        if self.eat_if(b'}') {
+            return Some(());
+        }
+        loop {
+            let key = self.string()?;
+            self.eat(b':')?;
+            let value = self.value(depth)?;
+            f(key, value)?;
+            if self.eat_if(b'}') {
+                return Some(());
+            }
+            self.eat(b',')?;
+        }
+    }
+}
+
+/// Looks `key` up in a JSON object. `None` if the input isn't a well formed
+/// object or has `key` more than once, `Some(None)` if it doesn't have `key`.
+pub(crate) fn json_field<'a>(input: &'a [u8], key: &[u8]) -> Option<Option<Value<'a>>> {
+    let mut reader = Reader { input, pos: 0 };
+    let mut found = None;
+    reader.object(0, |k, v| {
+        if k == key && found.replace(v).is_some() {
+            return None;
+        }
+        Some(())
+    })?;
+    reader.skip_whitespace();
+    (reader.pos == input.len()).then_some(found)
+}
+
+/// The same rules as NEAR: 2 to 64 characters, lowercase letters and digits
+/// in parts separated by a single `-`, `_` or `.`.
+pub(crate) fn is_valid_account_id(account_id: &[u8]) -> bool {
+    if !(MIN_ACCOUNT_ID_LEN..=MAX_ACCOUNT_ID_LEN).contains(&account_id.len()) {
+        return false;
+    }
+    let mut after_separator = true;
+    for c in account_id {
+        let separator = matches!(c, b'-' | b'_' | b'.');
+        if separator && after_separator
+            || !(separator || c.is_ascii_lowercase() || c.is_ascii_digit())
+        {
+            return false;
+        }
+        after_separator = separator;
+    }
+    !after_separator
+}
+
+fn borsh_string(input: &[u8]) -> Option<&[u8]> {
+    match input {
+        [a, b, c, d, s @ ..] if u32::from_le_bytes([*a, *b, *c, *d]) as usize == s.len() => Some(s),
+        _ => None,
+    }
+}
+
+/// `{"account_id":"..."}` or a Borsh string, holding a valid account id.
+pub(crate) fn account_id(input: &[u8]) -> Option<&[u8]> {
+    let account_id = match json_field(input, b"account_id") {
+        Some(Some(Value::String(s))) => s,
+        Some(_) => return None,
+        None => borsh_string(input)?,
+    };
+    is_valid_account_id(account_id).then_some(account_id)
+}
+
+/// Like [`account_id`], but `{}`, `{"account_id":null}` and a Borsh `None`
+/// are no account id.
+pub(crate) fn optional_account_id(input: &[u8]) -> Option<Option<&[u8]>> {
+    let account_id = match json_field(input, b"account_id") {
+        Some(None | Some(Value::Null)) => return Some(None),
+        Some(Some(Value::String(s))) => s,
+        Some(Some(_)) => return None,
+        None => match input {
+            [0] => return Some(None),
+            [1, rest @ ..] => borsh_string(rest)?,
+            _ => return None,
+        },
+    };
+    is_valid_account_id(account_id).then_some(Some(account_id))
+}
+
+/// `{"<key>":N}` or a Borsh `u32`.
+pub(crate) fn u32_arg(input: &[u8], key: &[u8]) -> Option<u32> {
+    match json_field(input, key) {
+        Some(Some(Value::Number(digits))) => digits.iter().try_fold(0u32, |n, d| {
+            d.is_ascii_digit()
+                .then(|| n.checked_mul(10)?.checked_add((d - b'0') as u32))
+                .flatten()
+        }),
+        Some(_) => None,
+        None => Some(u32::from_le_bytes(input.try_into().ok()?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::{serde_json, AccountId};
+
+    /// xorshift, so failures reproduce.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+
+        fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+            &items[self.below(items.len())]
+        }
+    }
+
+    fn mutate(rng: &mut Rng, input: &[u8]) -> Vec<u8> {
+        let mut input = input.to_vec();
+        for _ in 0..=rng.below(3) {
+            let at = rng.below(input.len() + 1);
+            match rng.below(4) {
+                0 => input.truncate(at),
+                1 if at < input.len() => input[at] = rng.next() as u8,
+                2 => input.insert(at, *rng.pick(b"{}[]\":,\\ \x00-.a0nte")),
+                _ if at < input.len() => {
+                    input.remove(at);
+                }
+                _ => {}
+            }
+        }
+        input
+    }
+
+    #[test]
+    fn test_json_field() {
+        let input = br#" { "memo" : [1, {"a": "}"}, -2.5e+3, true, null], "account_id":"bob.near" }
+"#;
+        assert_eq!(
+            json_field(input, b"account_id"),
+            Some(Some(Value::String(b"bob.near")))
+        );
+        assert_eq!(json_field(input, b"other"), Some(None));
+        assert_eq!(
+            json_field(br#"{"size":12}"#, b"size"),
+            Some(Some(Value::Number(b"12")))
+        );
+        assert_eq!(
+            json_field(b"{\"a\\\"b\":\"\xc3\xa9\\n\"}", b"a\\\"b"),
+            Some(Some(Value::String(b"\xc3\xa9\\n")))
+        );
+        assert_eq!(json_field(b"{}", b"size"), Some(None));
+
+        for malformed in [
+            &b""[..],
+            b"{",
+            b"{}}",
+            b"[]",
+            b"null",
+            br#"{"size":1,}"#,
+            br#"{"size":01}"#,
+            br#"{"size":1.}"#,
+            br#"{"size":1 2}"#,
+            br#"{"size" 1}"#,
+            br#"{size:1}"#,
+            br#"{"size":1}{}"#,
+            br#"{"size":nul}"#,
+            br#"{"size":1,"size":2}"#,
+            br#"{"a":"\x"}"#,
+            br#"{"a":"\u12"}"#,
+            b"{\"a\":\"\x01\"}",
+            b"{\"a\":\"\xff\"}",
+        ] {
+            assert_eq!(json_field(malformed, b"size"), None, "{malformed:?}");
+        }
+
+        let deep = [vec![b'{', b'"', b'a', b'"', b':'], vec![b'['; 10_000]].concat();
+        assert_eq!(json_field(&deep, b"size"), None);
+    }
+
+    #[test]
+    fn test_args() {
+        assert_eq!(
+            account_id(br#"{"account_id":"bob.near"}"#),
+            Some(&b"bob.near"[..])
+        );
+        assert_eq!(
+            account_id(&borsh::to_vec("bob.near").unwrap()),
+            Some(&b"bob.near"[..])
+        );
+        assert_eq!(account_id(br#"{"account_id":"Bob"}"#), None);
+        assert_eq!(account_id(br#"{"account_id":"b"}"#), None);
+        assert_eq!(account_id(br#"{"account_id":null}"#), None);
+        assert_eq!(account_id(b"{}"), None);
+
+        assert_eq!(optional_account_id(b"{}"), Some(None));
+        assert_eq!(optional_account_id(br#"{"account_id":null}"#), Some(None));
+        assert_eq!(
+            optional_account_id(&borsh::to_vec(&Some("bob.near")).unwrap()),
+            Some(Some(&b"bob.near"[..]))
+        );
+        assert_eq!(
+            optional_account_id(&borsh::to_vec(&None::<String>).unwrap()),
+            Some(None)
+        );
+        assert_eq!(optional_account_id(br#"{"account_id":7}"#), None);
+
+        assert_eq!(u32_arg(br#"{"size": 4294967295}"#, b"size"), Some(u32::MAX));
+        assert_eq!(u32_arg(br#"{"size":4294967296}"#, b"size"), None);
+        assert_eq!(u32_arg(br#"{"size":-1}"#, b"size"), None);
+        assert_eq!(u32_arg(br#"{"size":1e2}"#, b"size"), None);
+        assert_eq!(u32_arg(br#"{"size":"1"}"#, b"size"), None);
+        assert_eq!(u32_arg(&123u32.to_le_bytes(), b"size"), Some(123));
+        assert_eq!(u32_arg(&[1, 0, 0], b"size"), None);
+    }
+
+    #[test]
+    fn test_account_id_matches_near() {
+        let mut rng = Rng(0x5eed);
+        for _ in 0..20_000 {
+            let len = rng.below(MAX_ACCOUNT_ID_LEN + 4);
+            let account_id: Vec<u8> = (0..len).map(|_| *rng.pick(b"ab9-_.A@")).collect();
+            let expected = String::from_utf8(account_id.clone())
+                .unwrap()
+                .parse::<AccountId>()
+                .is_ok();
+            assert_eq!(
+                is_valid_account_id(&account_id),
+                expected,
+                "{:?}",
+                String::from_utf8_lossy(&account_id)
+            );
+        }
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        let borsh_account_id = borsh::to_vec("bob.near").unwrap();
+        let borsh_optional_account_id = borsh::to_vec(&Some("bob.near")).unwrap();
+        let seeds = [
+            &br#"{"account_id":"bob.near"}"#[..],
+            br#"{ "memo": {"x": [1, "a"]}, "account_id" : "alice.testnet" }"#,
+            br#"{"account_id":null}"#,
+            br#"{"size":256}"#,
+            &borsh_account_id,
+            &borsh_optional_account_id,
+        ];
+        let mut rng = Rng(0xda);
+        for _ in 0..50_000 {
+            let seed = *rng.pick(&seeds);
+            let input = mutate(&mut rng, seed);
+            if let Some(account_id) = account_id(&input) {
+                let account_id = core::str::from_utf8(account_id).unwrap();
+                assert!(account_id.parse::<AccountId>().is_ok(), "{input:?}");
+            }
+            if let Some(Some(account_id)) = optional_account_id(&input) {
+                let account_id = core::str::from_utf8(account_id).unwrap();
+                assert!(account_id.parse::<AccountId>().is_ok(), "{input:?}");
+            }
+            u32_arg(&input, b"size");
+        }
+
+        // agrees with serde_json on which inputs are JSON objects
+        for _ in 0..50_000 {
+            let seed = *rng.pick(&seeds[..4]);
+            let input = mutate(&mut rng, seed);
+            let expected = serde_json::from_slice::<serde_json::Value>(&input)
+                .is_ok_and(|value| value.is_object());
+            assert_eq!(
+                json_field(&input, b"size").is_some(),
+                expected,
+                "{:?}",
+                String::from_utf8_lossy(&input)
+            );
+        }
+    }
+}
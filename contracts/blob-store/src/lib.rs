@@ -1,23 +1,53 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 
+extern crate alloc;
+
+mod args;
+
+use alloc::vec::Vec;
 use near_sdk::{assert_one_yocto, env, AccountId};
 
 const ERR_CONTRACT_NOT_INITIALIZED: &str = "Contract is not initialized.";
 const ERR_CONTRACT_ALREADY_INITIALIZED: &str = "Contract already initialized.";
 const ERR_NOT_OWNER: &str = "Predecessor is not owner.";
+const ERR_NOT_SUBMITTER: &str = "Predecessor is not owner or submitter.";
 const ERR_NO_PROPOSED_OWNER: &str = "No proposed owner.";
 const ERR_NOT_PROPOSED_OWNER: &str = "Predecessor is not proposed owner.";
 const ERR_MISSING_INVALID_INPUT: &str = "Missing or invalid input.";
+const ERR_COMMITMENT_LOG_TOO_LARGE: &str = "Commitment log size is too large.";
 const JSON_NULL: &[u8] = b"null";
 const JSON_DOUBLE_QUOTE: &[u8] = b"\"";
 // storage keys
-const KEY_INITIALIZED: &[u8; 1] = &[0];
+const KEY_INITIALIZED: &[u8; 1] = &[0]; //       one byte of FLAG_* bits
 const KEY_OWNER: &[u8; 1] = &[1]; //            serialized with .as_bytes() NOT Borsh
 const KEY_PROPOSED_OWNER: &[u8; 1] = &[2]; //   ditto. Not guaranteed to be a valid AccountId.
+const KEY_COMMITMENT_LOG_SIZE: &[u8; 1] = &[3]; // u32 LE, absent when the log is off
+const KEY_COMMITMENT_COUNT: &[u8; 1] = &[4]; //    u64 LE, commitments logged since it was sized
+const KEY_COMMITMENT_PREFIX: u8 = 5; //             followed by the u32 LE slot
+const KEY_SUBMITTERS: &[u8; 1] = &[6]; //          valid AccountIds joined by SUBMITTER_SEPARATOR
+
+// flags stored under KEY_INITIALIZED, which every call reads anyway
+const FLAG_INITIALIZED: u8 = 1;
+const FLAG_COMMITMENT_LOG: u8 = 2; // so `submit` only reads the log size when it is on
+
+/// Can't appear in an account id.
+const SUBMITTER_SEPARATOR: u8 = b',';
 
-fn require_initialized() {
-    if !env::storage_has_key(KEY_INITIALIZED) {
-        env::panic_str(ERR_CONTRACT_NOT_INITIALIZED);
+/// Upper bound on the ring buffer, so `recent_commitments` stays cheap to view.
+const MAX_COMMITMENT_LOG_SIZE: u32 = 256;
+/// sha256 of the payload, its length, whether there is a namespace, its
+/// version and id, and the block height.
+const COMMITMENT_LEN: usize = 32 + 4 + 1 + 1 + 4 + 8;
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+/// A namespace's version and id.
+type Namespace = (u8, u32);
+
+/// Returns the flags stored when the contract was initialized.
+fn require_initialized() -> u8 {
+    match env::storage_read(KEY_INITIALIZED).as_deref() {
+        Some([flags]) => *flags,
+        _ => env::panic_str(ERR_CONTRACT_NOT_INITIALIZED),
     }
 }
 
@@ -30,13 +60,26 @@ fn require_owner(predecessor: &AccountId) {
     }
 }
 
+fn require_submitter(predecessor: &AccountId) {
+    let predecessor = predecessor.as_bytes();
+    if env::storage_read(KEY_OWNER).is_some_and(|v| v == predecessor) {
+        return;
+    }
+    if !env::storage_read(KEY_SUBMITTERS).is_some_and(|v| {
+        v.split(|b| *b == SUBMITTER_SEPARATOR)
+            .any(|submitter| submitter == predecessor)
+    }) {
+        env::panic_str(ERR_NOT_SUBMITTER);
+    }
+}
+
 #[no_mangle]
 pub fn new() {
     if env::storage_has_key(KEY_INITIALIZED) {
         env::panic_str(ERR_CONTRACT_ALREADY_INITIALIZED);
     }
 
-    env::storage_write(KEY_INITIALIZED, &[1]);
+    env::storage_write(KEY_INITIALIZED, &[FLAG_INITIALIZED]);
 
     let predecessor_account_id = env::predecessor_account_id();
 
@@ -45,14 +88,209 @@ pub fn new() {
 
 #[no_mangle]
 pub fn submit() {
-    require_initialized();
-    require_owner(&env::predecessor_account_id());
+    let flags = require_initialized();
+    require_submitter(&env::predecessor_account_id());
 
-    if env::input().is_none() {
+    let Some(input) = env::input() else {
         env::panic_str(ERR_MISSING_INVALID_INPUT);
+    };
+
+    if flags & FLAG_COMMITMENT_LOG != 0 {
+        if let Some(size) = log_size() {
+            log_commitment(size, &input);
+        }
+    }
+}
+
+fn read_u32(key: &[u8]) -> Option<u32> {
+    let v = env::storage_read(key)?;
+    Some(u32::from_le_bytes(v.try_into().ok()?))
+}
+
+fn read_u64(key: &[u8]) -> Option<u64> {
+    let v = env::storage_read(key)?;
+    Some(u64::from_le_bytes(v.try_into().ok()?))
+}
+
+fn log_size() -> Option<u32> {
+    read_u32(KEY_COMMITMENT_LOG_SIZE).filter(|size| *size > 0)
+}
+
+fn commitment_key(slot: u32) -> [u8; 5] {
+    let [a, b, c, d] = slot.to_le_bytes();
+    [KEY_COMMITMENT_PREFIX, a, b, c, d]
+}
+
+/// Splits a borsh `SubmitRequest` into its namespace and blob data. Anything
+/// else is committed to whole, without a namespace.
+fn submit_request(input: &[u8]) -> Option<(Option<Namespace>, &[u8])> {
+    let (namespace, rest) = match input {
+        [0, rest @ ..] => (None, rest),
+        [1, version, a, b, c, d, rest @ ..] => {
+            (Some((*version, u32::from_le_bytes([*a, *b, *c, *d]))), rest)
+        }
+        _ => return None,
+    };
+    match rest {
+        [a, b, c, d, data @ ..] if u32::from_le_bytes([*a, *b, *c, *d]) as usize == data.len() => {
+            Some((namespace, data))
+        }
+        _ => None,
     }
 }
 
+fn log_commitment(size: u32, input: &[u8]) {
+    let (namespace, payload) = submit_request(input).unwrap_or((None, input));
+
+    let mut entry = [0u8; COMMITMENT_LEN];
+    entry[..32].copy_from_slice(&env::sha256(payload));
+    entry[32..36].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+    if let Some((version, id)) = namespace {
+        entry[36] = 1;
+        entry[37] = version;
+        entry[38..42].copy_from_slice(&id.to_le_bytes());
+    }
+    entry[42..].copy_from_slice(&env::block_height().to_le_bytes());
+
+    let index = read_u64(KEY_COMMITMENT_COUNT).unwrap_or(0);
+    env::storage_write(&commitment_key((index % size as u64) as u32), &entry);
+    env::storage_write(KEY_COMMITMENT_COUNT, &(index + 1).to_le_bytes());
+
+    let mut event = Vec::from(
+        *br#"EVENT_JSON:{"standard":"near-da-blob-store","version":"1.0.0","event":"commitment","data":"#,
+    );
+    push_commitment_json(&mut event, index, &entry);
+    event.push(b'}');
+    env::log_str(core::str::from_utf8(&event).unwrap_or_default());
+}
+
+/// Appends `n` in decimal, without pulling in `core::fmt`.
+fn push_decimal(r: &mut Vec<u8>, mut n: u64) {
+    let mut digits = [0u8; 20];
+    let mut i = digits.len();
+    loop {
+        i -= 1;
+        digits[i] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+    r.extend_from_slice(&digits[i..]);
+}
+
+fn push_commitment_json(r: &mut Vec<u8>, index: u64, entry: &[u8]) {
+    r.extend_from_slice(br#"{"index":"#);
+    push_decimal(r, index);
+    r.extend_from_slice(br#","hash":""#);
+    for b in &entry[..32] {
+        r.push(HEX[(b >> 4) as usize]);
+        r.push(HEX[(b & 0xf) as usize]);
+    }
+    r.extend_from_slice(br#"","length":"#);
+    push_decimal(
+        r,
+        u32::from_le_bytes([entry[32], entry[33], entry[34], entry[35]]).into(),
+    );
+    r.extend_from_slice(br#","namespace":"#);
+    if entry[36] == 1 {
+        r.extend_from_slice(br#"{"version":"#);
+        push_decimal(r, entry[37].into());
+        r.extend_from_slice(br#","id":"#);
+        push_decimal(
+            r,
+            u32::from_le_bytes([entry[38], entry[39], entry[40], entry[41]]).into(),
+        );
+        r.push(b'}');
+    } else {
+        r.extend_from_slice(JSON_NULL);
+    }
+    let mut height = [0u8; 8];
+    height.copy_from_slice(&entry[42..]);
+    r.extend_from_slice(br#","block_height":"#);
+    push_decimal(r, u64::from_le_bytes(height));
+    r.push(b'}');
+}
+
+/// Sizes the ring buffer of commitments that `submit` keeps, and clears it. A
+/// size of 0 turns the log off. Takes `{"size":N}` or a Borsh `u32`. A store
+/// upgraded from before the log was flagged has to size it again.
+#[no_mangle]
+pub fn set_commitment_log() {
+    let flags = require_initialized();
+    assert_one_yocto();
+    require_owner(&env::predecessor_account_id());
+
+    let size = env::input()
+        .and_then(|payload| args::u32_arg(&payload, b"size"))
+        .unwrap_or_else(|| env::panic_str(ERR_MISSING_INVALID_INPUT));
+    if size > MAX_COMMITMENT_LOG_SIZE {
+        env::panic_str(ERR_COMMITMENT_LOG_TOO_LARGE);
+    }
+
+    if let Some(old_size) = log_size() {
+        let count = read_u64(KEY_COMMITMENT_COUNT).unwrap_or(0);
+        for slot in 0..count.min(old_size as u64) as u32 {
+            env::storage_remove(&commitment_key(slot));
+        }
+    }
+    env::storage_remove(KEY_COMMITMENT_COUNT);
+
+    if size == 0 {
+        env::storage_remove(KEY_COMMITMENT_LOG_SIZE);
+        env::storage_write(KEY_INITIALIZED, &[flags & !FLAG_COMMITMENT_LOG]);
+    } else {
+        env::storage_write(KEY_COMMITMENT_LOG_SIZE, &size.to_le_bytes());
+        env::storage_write(KEY_INITIALIZED, &[flags | FLAG_COMMITMENT_LOG]);
+    }
+}
+
+#[no_mangle]
+pub fn commitment_log_size() {
+    require_initialized();
+
+    let mut r = Vec::new();
+    push_decimal(&mut r, log_size().unwrap_or(0).into());
+    env::value_return(&r);
+}
+
+/// The number of commitments logged since the log was last sized, including
+/// the ones the ring buffer has dropped.
+#[no_mangle]
+pub fn commitment_count() {
+    require_initialized();
+
+    let mut r = Vec::new();
+    push_decimal(&mut r, read_u64(KEY_COMMITMENT_COUNT).unwrap_or(0));
+    env::value_return(&r);
+}
+
+/// The commitments still in the ring buffer, newest first.
+#[no_mangle]
+pub fn recent_commitments() {
+    require_initialized();
+
+    let mut r = Vec::from(*b"[");
+    if let Some(size) = log_size() {
+        let count = read_u64(KEY_COMMITMENT_COUNT).unwrap_or(0);
+        for index in (count.saturating_sub(size as u64)..count).rev() {
+            let Some(entry) = env::storage_read(&commitment_key((index % size as u64) as u32))
+            else {
+                continue;
+            };
+            if entry.len() != COMMITMENT_LEN {
+                continue;
+            }
+            if r.len() > 1 {
+                r.push(b',');
+            }
+            push_commitment_json(&mut r, index, &entry);
+        }
+    }
+    r.push(b']');
+    env::value_return(&r);
+}
+
 fn return_json_string(v: Option<&[u8]>) {
     let r = v.map_or_else(
         || JSON_NULL.to_vec(),
@@ -79,6 +317,8 @@ pub fn own_get_proposed_owner() {
     return_json_string(current_proposed_owner.as_deref());
 }
 
+/// Takes `{"account_id":"..."}`, or `{}` to withdraw the proposal. A Borsh
+/// `Option<String>` works too.
 #[no_mangle]
 pub fn own_propose_owner() {
     require_initialized();
@@ -88,16 +328,8 @@ pub fn own_propose_owner() {
 
     let payload = env::input().unwrap_or_else(|| env::panic_str(ERR_MISSING_INVALID_INPUT));
 
-    let new_proposed_owner = if payload == b"{}" {
-        None
-    } else if let Some(account_id) = payload
-        .strip_prefix(br#"{"account_id":""#) // jank JSON "parsing"
-        .and_then(|s| s.strip_suffix(br#""}"#))
-    {
-        Some(account_id)
-    } else {
-        env::panic_str(ERR_MISSING_INVALID_INPUT);
-    };
+    let new_proposed_owner = args::optional_account_id(&payload)
+        .unwrap_or_else(|| env::panic_str(ERR_MISSING_INVALID_INPUT));
 
     match new_proposed_owner {
         Some(new_proposed_owner) => {
@@ -135,3 +367,74 @@ pub fn own_renounce_owner() {
     env::storage_remove(KEY_OWNER);
     env::storage_remove(KEY_PROPOSED_OWNER);
 }
+
+/// Reads `{"account_id":"..."}` or a Borsh string, panicking unless the
+/// account id is valid.
+fn account_id_arg() -> Vec<u8> {
+    env::input()
+        .and_then(|payload| args::account_id(&payload).map(<[u8]>::to_vec))
+        .unwrap_or_else(|| env::panic_str(ERR_MISSING_INVALID_INPUT))
+}
+
+fn write_submitters<'a>(submitters: impl Iterator<Item = &'a [u8]>) {
+    let submitters = submitters.collect::<Vec<_>>();
+    if submitters.is_empty() {
+        env::storage_remove(KEY_SUBMITTERS);
+    } else {
+        env::storage_write(KEY_SUBMITTERS, &submitters.join(&SUBMITTER_SEPARATOR));
+    }
+}
+
+/// Lets an account submit alongside the owner.
+#[no_mangle]
+pub fn add_submitter() {
+    require_initialized();
+    assert_one_yocto();
+    require_owner(&env::predecessor_account_id());
+
+    let account_id = account_id_arg();
+    let submitters = env::storage_read(KEY_SUBMITTERS).unwrap_or_default();
+    let current = submitters
+        .split(|b| *b == SUBMITTER_SEPARATOR)
+        .filter(|submitter| !submitter.is_empty());
+    if current.clone().any(|submitter| submitter == account_id) {
+        return;
+    }
+
+    write_submitters(current.chain([account_id.as_slice()]));
+}
+
+#[no_mangle]
+pub fn remove_submitter() {
+    require_initialized();
+    assert_one_yocto();
+    require_owner(&env::predecessor_account_id());
+
+    let account_id = account_id_arg();
+    let submitters = env::storage_read(KEY_SUBMITTERS).unwrap_or_default();
+
+    write_submitters(
+        submitters
+            .split(|b| *b == SUBMITTER_SEPARATOR)
+            .filter(|submitter| !submitter.is_empty() && *submitter != account_id),
+    );
+}
+
+/// The accounts other than the owner that may submit, in the order they were
+/// added.
+#[no_mangle]
+pub fn submitters() {
+    require_initialized();
+
+    let mut r = Vec::from(*b"[");
+    if let Some(submitters) = env::storage_read(KEY_SUBMITTERS) {
+        for (i, submitter) in submitters.split(|b| *b == SUBMITTER_SEPARATOR).enumerate() {
+            if i > 0 {
+                r.push(b',');
+            }
+            r.extend_from_slice(&[JSON_DOUBLE_QUOTE, submitter, JSON_DOUBLE_QUOTE].concat());
+        }
+    }
+    r.push(b']');
+    env::value_return(&r);
+}
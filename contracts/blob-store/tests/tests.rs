@@ -1,4 +1,5 @@
-use near_da_primitives::Blob;
+use near_da_primitives::{proof::hash, Blob, Namespace, SubmitRequest};
+use near_sdk::serde_json::{json, Value};
 
 #[tokio::test]
 async fn test() -> anyhow::Result<()> {
@@ -53,7 +54,7 @@ async fn test() -> anyhow::Result<()> {
 
     alice
         .call(contract.id(), "own_propose_owner")
-        .args_json(near_sdk::serde_json::json!({
+        .args_json(json!({
             "account_id": bob.id(),
         }))
         .deposit(1)
@@ -75,3 +76,263 @@ async fn test() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn commitment_log() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let wasm = near_workspaces::compile_project(".").await?;
+    let contract = worker.dev_deploy(&wasm).await?;
+    let alice = worker.dev_create_account().await?;
+
+    alice
+        .call(contract.id(), "new")
+        .transact()
+        .await?
+        .into_result()?;
+
+    let submit = |data: Vec<u8>| {
+        let args = borsh::to_vec(&SubmitRequest {
+            namespace: Some(Namespace::new(1, 7)),
+            data,
+        })
+        .unwrap();
+        alice.call(contract.id(), "submit").args(args).transact()
+    };
+
+    // off by default
+    submit(vec![1; 8]).await?.into_result()?;
+    let size = contract.view("commitment_log_size").await?.json::<u32>()?;
+    assert_eq!(size, 0);
+    let recent = contract
+        .view("recent_commitments")
+        .await?
+        .json::<Vec<Value>>()?;
+    assert!(recent.is_empty());
+
+    let bob = worker.dev_create_account().await?;
+    let result = bob
+        .call(contract.id(), "set_commitment_log")
+        .args_json(json!({ "size": 2 }))
+        .deposit(1)
+        .transact()
+        .await?;
+    assert!(result.is_failure(), "only the owner can size the log");
+
+    alice
+        .call(contract.id(), "set_commitment_log")
+        .args_json(json!({ "size": 2 }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+
+    let mut heights = vec![];
+    for i in 0..3u8 {
+        let outcome = submit(vec![i; 16 + i as usize]).await?.into_result()?;
+        let log = &outcome.logs()[0];
+        let event: Value =
+            near_sdk::serde_json::from_str(log.strip_prefix("EVENT_JSON:").unwrap())?;
+        assert_eq!(event["event"], "commitment");
+        assert_eq!(event["data"]["index"], i as u64);
+        heights.push(event["data"]["block_height"].clone());
+    }
+
+    let count = contract.view("commitment_count").await?.json::<u64>()?;
+    assert_eq!(count, 3);
+
+    // the oldest has been overwritten
+    let recent = contract
+        .view("recent_commitments")
+        .await?
+        .json::<Vec<Value>>()?;
+    assert_eq!(recent.len(), 2);
+    for (commitment, i) in recent.iter().zip([2u8, 1]) {
+        let data = vec![i; 16 + i as usize];
+        let hex: String = hash(&data).iter().map(|b| format!("{b:02x}")).collect();
+        assert_eq!(commitment["index"], i as u64);
+        assert_eq!(commitment["hash"], hex);
+        assert_eq!(commitment["length"], data.len());
+        assert_eq!(commitment["namespace"], json!({ "version": 1, "id": 7 }));
+        assert_eq!(commitment["block_height"], heights[i as usize]);
+    }
+
+    alice
+        .call(contract.id(), "set_commitment_log")
+        .args_json(json!({ "size": 0 }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+    let recent = contract
+        .view("recent_commitments")
+        .await?
+        .json::<Vec<Value>>()?;
+    assert!(recent.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn submitters() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let wasm = near_workspaces::compile_project(".").await?;
+    let contract = worker.dev_deploy(&wasm).await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let carol = worker.dev_create_account().await?;
+
+    alice
+        .call(contract.id(), "new")
+        .transact()
+        .await?
+        .into_result()?;
+
+    let blob = borsh::to_vec(&SubmitRequest {
+        namespace: None,
+        data: vec![3; 256],
+    })?;
+
+    let result = bob
+        .call(contract.id(), "submit")
+        .args(blob.clone())
+        .transact()
+        .await?;
+    assert!(result.is_failure(), "bob is not a submitter yet");
+
+    for submitter in [&bob, &carol] {
+        alice
+            .call(contract.id(), "add_submitter")
+            .args_json(json!({ "account_id": submitter.id() }))
+            .deposit(1)
+            .transact()
+            .await?
+            .into_result()?;
+    }
+
+    let result = bob
+        .call(contract.id(), "add_submitter")
+        .args_json(json!({ "account_id": bob.id() }))
+        .deposit(1)
+        .transact()
+        .await?;
+    assert!(result.is_failure(), "only the owner manages submitters");
+
+    let result = alice
+        .call(contract.id(), "add_submitter")
+        .args_json(json!({ "account_id": "Not Valid!" }))
+        .deposit(1)
+        .transact()
+        .await?;
+    assert!(result.is_failure(), "invalid account ids are rejected");
+
+    let submitters = contract.view("submitters").await?.json::<Vec<String>>()?;
+    assert_eq!(submitters, [bob.id().as_str(), carol.id().as_str()]);
+
+    for submitter in [&alice, &bob, &carol] {
+        submitter
+            .call(contract.id(), "submit")
+            .args(blob.clone())
+            .transact()
+            .await?
+            .into_result()?;
+    }
+
+    alice
+        .call(contract.id(), "remove_submitter")
+        .args_json(json!({ "account_id": bob.id() }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+
+    let submitters = contract.view("submitters").await?.json::<Vec<String>>()?;
+    assert_eq!(submitters, [carol.id().as_str()]);
+
+    let result = bob
+        .call(contract.id(), "submit")
+        .args(blob)
+        .transact()
+        .await?;
+    assert!(result.is_failure(), "bob was removed");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn ownership_args() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let wasm = near_workspaces::compile_project(".").await?;
+    let contract = worker.dev_deploy(&wasm).await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+
+    alice
+        .call(contract.id(), "new")
+        .transact()
+        .await?
+        .into_result()?;
+
+    let propose = |args: Vec<u8>| {
+        alice
+            .call(contract.id(), "own_propose_owner")
+            .args(args)
+            .deposit(1)
+            .transact()
+    };
+
+    for malformed in [
+        &br#"{"account_id":"Not Valid!"}"#[..],
+        br#"{"account_id":"bob.test.near""#,
+        br#"{"account_id":1}"#,
+        br#"{"account_id":"a","account_id":"b"}"#,
+        b"garbage",
+    ] {
+        let result = propose(malformed.to_vec()).await?;
+        assert!(
+            result.is_failure(),
+            "{}",
+            String::from_utf8_lossy(malformed)
+        );
+    }
+
+    // whitespace and other keys are fine
+    let args = format!(r#" {{ "memo": [1, "x"], "account_id" : "{}" }} "#, bob.id());
+    propose(args.into_bytes()).await?.into_result()?;
+    let proposed = contract
+        .view("own_get_proposed_owner")
+        .await?
+        .json::<Option<String>>()?;
+    assert_eq!(proposed.as_deref(), Some(bob.id().as_str()));
+
+    propose(br#"{"account_id":null}"#.to_vec())
+        .await?
+        .into_result()?;
+    let proposed = contract
+        .view("own_get_proposed_owner")
+        .await?
+        .json::<Option<String>>()?;
+    assert_eq!(proposed, None);
+
+    // Borsh works too
+    propose(borsh::to_vec(&Some(bob.id().to_string()))?)
+        .await?
+        .into_result()?;
+    bob.call(contract.id(), "own_accept_owner")
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+    let owner = contract.view("own_get_owner").await?.json::<String>()?;
+    assert_eq!(owner, bob.id().as_str());
+
+    bob.call(contract.id(), "set_commitment_log")
+        .args(borsh::to_vec(&4u32)?)
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+    let size = contract.view("commitment_log_size").await?.json::<u32>()?;
+    assert_eq!(size, 4);
+
+    Ok(())
+}
@@ -120,6 +120,7 @@ unsafe fn init_client<F: FnOnce() -> config::KeyType>(
                 network,
                 namespace,
                 mode: Default::default(), // TODO: for now we don't expose mode to the client
+                registry: None,
             };
 
             Box::into_raw(Box::new(Client::new(&config)))
@@ -145,7 +146,7 @@ pub unsafe extern "C" fn new_client(
     let account_id = FfiStr::from_raw(account_id).into_string();
     let secret_key = FfiStr::from_raw(secret_key).into_string();
 
-    let key_type = || config::KeyType::SecretKey(account_id, secret_key);
+    let key_type = || config::KeyType::SecretKey(account_id, secret_key.into());
     init_client(contract, network, namespace_version, namespace, key_type)
 }
 
@@ -362,11 +363,12 @@ pub mod test {
         let account = env::var("TEST_NEAR_ACCOUNT").unwrap();
         let secret = env::var("TEST_NEAR_SECRET").unwrap();
         let config = Config {
-            key: config::KeyType::SecretKey(account.clone(), secret),
+            key: config::KeyType::SecretKey(account.clone(), secret.into()),
             contract: account.to_string(),
             network: Network::Testnet,
             namespace: None,
             mode: Default::default(),
+            registry: None,
         };
         let client = Client::new(&config);
         (client, config)
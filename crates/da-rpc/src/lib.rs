@@ -1,7 +1,7 @@
 use eyre::Result;
 pub use near_da_primitives::{Blob, BlobRef, Commitment, Namespace};
-pub use near_primitives::hash::CryptoHash;
 use near_primitives::types::BlockHeight;
+pub use near_primitives::{hash::CryptoHash, types::AccountId};
 use serde::{Deserialize, Serialize};
 
 pub mod near;
@@ -22,10 +22,35 @@ pub struct ReadAll(pub Vec<(BlockHeight, Blob)>);
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndexRead(pub Blob);
 
+/// Returned by [`DataAvailability::get`] when there is no transaction with
+/// the id, as opposed to the DA layer being unreachable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotFound(pub CryptoHash);
+
+impl std::fmt::Display for NotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "blob {} not found", self.0)
+    }
+}
+
+impl std::error::Error for NotFound {}
+
 #[async_trait::async_trait]
 pub trait DataAvailability {
     /// Submit blobs to the da layer
     async fn submit(&self, blob: Blob) -> Result<SubmitResult>;
+    /// Submit blobs, calling `signed` with the transaction id once it is
+    /// signed and before it is sent, so callers can track transactions that
+    /// never return.
+    async fn submit_signed(
+        &self,
+        blob: Blob,
+        signed: &(dyn Fn(CryptoHash) + Send + Sync),
+    ) -> Result<SubmitResult> {
+        let result = self.submit(blob).await?;
+        signed(CryptoHash(result.0.transaction_id));
+        Ok(result)
+    }
     /// Read blob by namespace and height
     async fn get(&self, transaction_id: CryptoHash) -> Result<Read>;
 }
@@ -0,0 +1,145 @@
+//! Deploying the contracts and managing the blob store's ownership.
+//!
+//! Contracts are deployed to the signer's own account, so the key has to be a
+//! full access key for it. The blob store methods are called on
+//! [`Config::contract`]. Deploying and initializing in one transaction leaves
+//! nobody a window to initialize the contract first.
+//!
+//! [`Config::contract`]: super::config::Config::contract
+
+use super::{Client, GAS_LIMIT};
+use eyre::{eyre, Result};
+use near_primitives::{
+    hash::CryptoHash,
+    transaction::{Action, DeployContractAction, FunctionCallAction},
+    types::AccountId,
+    views::{FinalExecutionOutcomeViewEnum, FinalExecutionStatus},
+};
+use serde_json::json;
+use tracing::debug;
+
+/// Attached to the blob store's ownership methods, which need a full access
+/// key.
+pub const ONE_YOCTO: u128 = 1;
+
+impl Client {
+    /// Deploy `code` to the signer's account.
+    pub async fn deploy(&self, code: Vec<u8>) -> Result<CryptoHash> {
+        self.deploy_with(code, vec![]).await
+    }
+
+    /// Deploy the blob store `code` to the signer's account and initialize it,
+    /// with the signer as its owner, in the same transaction.
+    pub async fn deploy_store(&self, code: Vec<u8>) -> Result<CryptoHash> {
+        self.deploy_with(code, vec![function_call("new", vec![], 0)])
+            .await
+    }
+
+    /// Deploy the registry `code` to the signer's account and initialize it,
+    /// owned by `owner`, in the same transaction.
+    pub async fn deploy_registry(&self, code: Vec<u8>, owner: &AccountId) -> Result<CryptoHash> {
+        let args = serde_json::to_vec(&json!({ "owner_id": owner }))?;
+        self.deploy_with(code, vec![function_call("new", args, 0)])
+            .await
+    }
+
+    async fn deploy_with(&self, code: Vec<u8>, init: Vec<Action>) -> Result<CryptoHash> {
+        let signer = self.signer().await?;
+        let mut actions = vec![Action::DeployContract(DeployContractAction { code })];
+        actions.extend(init);
+        self.transact(signer.account_id(), actions, "deploy").await
+    }
+
+    /// Initialize the blob store, with the signer as its owner.
+    pub async fn init_store(&self) -> Result<CryptoHash> {
+        self.call_function(&self.store()?, "new", vec![], 0).await
+    }
+
+    /// Propose `account_id` as the next owner of the blob store, or withdraw
+    /// the proposal with `None`. Only the owner can.
+    pub async fn propose_owner(&self, account_id: Option<&AccountId>) -> Result<CryptoHash> {
+        let args = match account_id {
+            Some(account_id) => json!({ "account_id": account_id }),
+            None => json!({}),
+        };
+        self.call_function(
+            &self.store()?,
+            "own_propose_owner",
+            serde_json::to_vec(&args)?,
+            ONE_YOCTO,
+        )
+        .await
+    }
+
+    /// Take over the blob store, if the signer was proposed as its owner.
+    pub async fn accept_owner(&self) -> Result<CryptoHash> {
+        self.call_function(&self.store()?, "own_accept_owner", vec![], ONE_YOCTO)
+            .await
+    }
+
+    fn store(&self) -> Result<AccountId> {
+        self.config
+            .contract
+            .parse()
+            .map_err(|e| eyre!("invalid contract account: {e}"))
+    }
+
+    pub(crate) async fn call_function(
+        &self,
+        contract: &AccountId,
+        method_name: &str,
+        args: Vec<u8>,
+        deposit: u128,
+    ) -> Result<CryptoHash> {
+        self.transact(
+            contract,
+            vec![function_call(method_name, args, deposit)],
+            method_name,
+        )
+        .await
+    }
+
+    /// Sign `actions` for `receiver` and wait for them as the mode says.
+    async fn transact(
+        &self,
+        receiver: &AccountId,
+        actions: Vec<Action>,
+        what: &str,
+    ) -> Result<CryptoHash> {
+        let (signer, latest_hash, current_nonce) = self.get_nonce_signer().await?;
+        let req = Client::build_transaction(
+            signer.as_ref(),
+            receiver,
+            &latest_hash,
+            current_nonce,
+            actions,
+            &self.config.mode,
+        )
+        .await?;
+
+        let outcome = self
+            .client
+            .call(&req)
+            .await?
+            .final_execution_outcome
+            .map(FinalExecutionOutcomeViewEnum::into_outcome)
+            .ok_or_else(|| eyre!("Transaction not ready yet"))?;
+        match outcome.status {
+            FinalExecutionStatus::SuccessValue(_) => {
+                debug!("{what} on {receiver}: {}", outcome.transaction.hash);
+                Ok(outcome.transaction.hash)
+            }
+            FinalExecutionStatus::Failure(e) => Err(eyre!("{what} on {receiver} failed: {e:?}")),
+            _ => Err(eyre!("Transaction not ready yet")),
+        }
+    }
+}
+
+fn function_call(method_name: &str, args: Vec<u8>, deposit: u128) -> Action {
+    Action::FunctionCall(Box::new(FunctionCallAction {
+        method_name: method_name.to_string(),
+        args,
+        gas: GAS_LIMIT,
+        deposit,
+    }))
+}
@@ -1,4 +1,6 @@
-use near_da_primitives::{Mode, Namespace};
+use eyre::{eyre, Report};
+use near_da_http_api_data::{ConfigureClientRequest, KeySource};
+use near_da_primitives::{Mode, Namespace, Secret};
 use serde::{Deserialize, Deserializer};
 use std::{fmt::Display, path::PathBuf};
 use url::Url;
@@ -6,8 +8,36 @@ use url::Url;
 #[derive(Debug, Clone, Deserialize)]
 pub enum KeyType {
     File(PathBuf),
-    Seed(String, String),
-    SecretKey(String, String),
+    Seed(String, Secret<String>),
+    SecretKey(String, Secret<String>),
+    /// An encrypted [`super::keystore::Keystore`] and its password.
+    Keystore(PathBuf, Secret<String>),
+    /// Account, public key and socket of an [`super::signer::ExternalSigner`],
+    /// the secret key never enters this process.
+    External(String, String, PathBuf),
+    /// Only the account, to read its submissions without holding its key.
+    /// Submitting fails.
+    ReadOnly(String),
+}
+
+impl KeyType {
+    /// The key for `account_id` from a client configuration request. A
+    /// keystore's password is read from its environment variable.
+    pub fn from_source(account_id: String, key: KeySource) -> eyre::Result<Self> {
+        Ok(match key {
+            KeySource::File { path } => Self::File(path.into()),
+            KeySource::Seed { seed } => Self::Seed(account_id, seed),
+            KeySource::SecretKey { secret_key } => Self::SecretKey(account_id, secret_key),
+            KeySource::Keystore { path, password_env } => {
+                let password = std::env::var(&password_env)
+                    .map_err(|_| eyre!("keystore password variable {password_env} is not set"))?;
+                Self::Keystore(path.into(), password.into())
+            }
+            KeySource::External { socket, public_key } => {
+                Self::External(account_id, public_key, socket.into())
+            }
+        })
+    }
 }
 
 #[cfg(test)]
@@ -25,6 +55,37 @@ pub struct Config {
     pub network: Network,
     pub namespace: Option<Namespace>,
     pub mode: Mode,
+    /// A `blob-registry` contract that submissions are recorded in, under the
+    /// namespace, see [`super::registry`].
+    #[serde(default)]
+    pub registry: Option<String>,
+}
+
+impl TryFrom<ConfigureClientRequest> for Config {
+    type Error = Report;
+
+    fn try_from(request: ConfigureClientRequest) -> Result<Self, Self::Error> {
+        let key = request
+            .key
+            .or(request
+                .secret_key
+                .map(|secret_key| KeySource::SecretKey { secret_key }))
+            .ok_or_else(|| eyre!("no signing key, set `secret_key` or `key`"))?;
+        Ok(Config {
+            key: KeyType::from_source(request.account_id, key)?,
+            contract: request.contract_id,
+            network: request
+                .network
+                .as_str()
+                .try_into()
+                .map_err(|e: String| eyre!(e))?,
+            namespace: request
+                .namespace
+                .map(|ns| Namespace::new(ns.version, ns.id)),
+            mode: request.mode.unwrap_or_default(),
+            registry: request.registry_id,
+        })
+    }
 }
 
 // TODO: stole from near-light-client, create primitives to share this
@@ -0,0 +1,211 @@
+//! Readiness checks, so the client can report whether it is able to submit
+//! before a submission fails.
+
+use super::Client;
+use eyre::{bail, eyre, Result};
+use near_jsonrpc_client::methods::{query::RpcQueryRequest, status::RpcStatusRequest};
+use near_jsonrpc_primitives::types::query::QueryResponseKind;
+use near_primitives::{
+    hash::CryptoHash,
+    types::{AccountId, Balance, BlockReference},
+    views::{AccessKeyPermissionView, QueryRequest},
+};
+use serde::{Deserialize, Serialize};
+use std::{future::Future, time::Duration};
+
+/// A function call access key needs at least this much allowance left, 0.05 NEAR
+/// covers a handful of submissions at the current gas price.
+pub const MIN_ALLOWANCE: Balance = 50_000_000_000_000_000_000_000;
+
+/// How long a single check may take before it is considered failed.
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Check {
+    pub name: String,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Check {
+    async fn run(name: &str, check: impl Future<Output = Result<()>>) -> Self {
+        let result = tokio::time::timeout(CHECK_TIMEOUT, check)
+            .await
+            .unwrap_or_else(|_| Err(eyre!("timed out after {CHECK_TIMEOUT:?}")));
+        Self {
+            name: name.to_string(),
+            ok: result.is_ok(),
+            error: result.err().map(|e| format!("{e:#}")),
+        }
+    }
+}
+
+impl Client {
+    /// Run all readiness checks concurrently.
+    pub async fn readiness(&self) -> Vec<Check> {
+        let (rpc, archive, access_key, contract) = tokio::join!(
+            Check::run("rpc", self.check_rpc()),
+            Check::run("archive", self.check_archive()),
+            Check::run("access_key", self.check_access_key()),
+            Check::run("contract", self.check_contract()),
+        );
+        vec![rpc, archive, access_key, contract]
+    }
+
+    pub async fn check_rpc(&self) -> Result<()> {
+        self.client.call(RpcStatusRequest).await?;
+        Ok(())
+    }
+
+    pub async fn check_archive(&self) -> Result<()> {
+        self.archive.call(RpcStatusRequest).await?;
+        Ok(())
+    }
+
+    /// The signer's access key exists and, for function call keys, may call
+    /// `submit` on the contract with enough allowance left.
+    pub async fn check_access_key(&self) -> Result<()> {
+        let signer = self.signer().await?;
+        let response = self
+            .client
+            .call(RpcQueryRequest {
+                block_reference: BlockReference::latest(),
+                request: QueryRequest::ViewAccessKey {
+                    account_id: signer.account_id().clone(),
+                    public_key: signer.public_key(),
+                },
+            })
+            .await
+            .map_err(|e| {
+                eyre!(
+                    "access key {} for {} not found: {e}",
+                    signer.public_key(),
+                    signer.account_id()
+                )
+            })?;
+        match response.kind {
+            QueryResponseKind::AccessKey(access_key) => {
+                check_permission(&access_key.permission, &self.config.contract)
+            }
+            _ => bail!("unexpected response to access key query"),
+        }
+    }
+
+    /// The contract account exists and has code deployed.
+    pub async fn check_contract(&self) -> Result<()> {
+        let account_id: AccountId = self.config.contract.parse()?;
+        let response = self
+            .client
+            .call(RpcQueryRequest {
+                block_reference: BlockReference::latest(),
+                request: QueryRequest::ViewAccount {
+                    account_id: account_id.clone(),
+                },
+            })
+            .await
+            .map_err(|e| eyre!("contract account {account_id} not found: {e}"))?;
+        match response.kind {
+            QueryResponseKind::ViewAccount(account)
+                if account.code_hash == CryptoHash::default() =>
+            {
+                bail!("no contract deployed to {account_id}")
+            }
+            QueryResponseKind::ViewAccount(_) => Ok(()),
+            _ => bail!("unexpected response to account query"),
+        }
+    }
+}
+
+fn check_permission(permission: &AccessKeyPermissionView, contract: &str) -> Result<()> {
+    match permission {
+        AccessKeyPermissionView::FullAccess => Ok(()),
+        AccessKeyPermissionView::FunctionCall {
+            allowance,
+            receiver_id,
+            method_names,
+        } => {
+            if receiver_id != contract {
+                bail!("access key can only call {receiver_id}, not {contract}");
+            }
+            // An empty list allows every method
+            if !method_names.is_empty() && !method_names.iter().any(|name| name == "submit") {
+                bail!(
+                    "access key can only call {} on {contract}, not submit",
+                    method_names.join(", ")
+                );
+            }
+            match allowance {
+                Some(allowance) if *allowance < MIN_ALLOWANCE => bail!(
+                    "access key allowance {allowance} is below the minimum of {MIN_ALLOWANCE}"
+                ),
+                _ => Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn function_call(allowance: Option<Balance>, receiver_id: &str) -> AccessKeyPermissionView {
+        with_methods(allowance, receiver_id, &[])
+    }
+
+    fn with_methods(
+        allowance: Option<Balance>,
+        receiver_id: &str,
+        method_names: &[&str],
+    ) -> AccessKeyPermissionView {
+        AccessKeyPermissionView::FunctionCall {
+            allowance,
+            receiver_id: receiver_id.to_string(),
+            method_names: method_names.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_check_permission() {
+        let contract = "blobstore.testnet";
+        assert!(check_permission(&AccessKeyPermissionView::FullAccess, contract).is_ok());
+        assert!(check_permission(&function_call(None, contract), contract).is_ok());
+        assert!(check_permission(&function_call(Some(MIN_ALLOWANCE), contract), contract).is_ok());
+    }
+
+    #[test]
+    fn test_check_permission_low_allowance() {
+        let contract = "blobstore.testnet";
+        let err = check_permission(&function_call(Some(MIN_ALLOWANCE - 1), contract), contract)
+            .unwrap_err();
+        assert!(err.to_string().contains("allowance"));
+    }
+
+    #[test]
+    fn test_check_permission_other_receiver() {
+        let err = check_permission(&function_call(None, "other.testnet"), "blobstore.testnet")
+            .unwrap_err();
+        assert!(err.to_string().contains("other.testnet"));
+    }
+
+    #[test]
+    fn test_check_permission_methods() {
+        let contract = "blobstore.testnet";
+        let allowed = with_methods(None, contract, &["get", "submit"]);
+        assert!(check_permission(&allowed, contract).is_ok());
+
+        let err = check_permission(&with_methods(None, contract, &["get"]), contract).unwrap_err();
+        assert!(err.to_string().contains("not submit"));
+    }
+
+    #[tokio::test]
+    async fn test_check_run() {
+        let check = Check::run("ok", async { Ok(()) }).await;
+        assert!(check.ok);
+        assert_eq!(check.error, None);
+
+        let check = Check::run("failed", async { Err(eyre!("unreachable")) }).await;
+        assert!(!check.ok);
+        assert_eq!(check.error.as_deref(), Some("unreachable"));
+    }
+}
@@ -0,0 +1,301 @@
+//! Password encrypted keystore for NEAR secret keys. The layout follows the
+//! Ethereum v3 keystore with pbkdf2: the key is derived with
+//! pbkdf2-hmac-sha256, the secret key is encrypted with aes-128-ctr and the
+//! password is checked with a keccak256 MAC before decrypting.
+
+use aes::{
+    cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit},
+    Aes128,
+};
+use eyre::{bail, eyre, Result, WrapErr};
+use hmac::Hmac;
+use near_crypto::{InMemorySigner, PublicKey, SecretKey};
+use near_primitives::types::AccountId;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+use std::path::Path;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+pub const KEYSTORE_VERSION: u8 = 1;
+/// PBKDF2 rounds used when encrypting, the same as the Ethereum keystore spec.
+pub const DEFAULT_ROUNDS: u32 = 262_144;
+/// Most PBKDF2 rounds a keystore may ask for, so a crafted file can't stall
+/// startup for hours. A few seconds of work on current hardware.
+pub const MAX_ROUNDS: u32 = 10_000_000;
+
+const CIPHER: &str = "aes-128-ctr";
+const KDF: &str = "pbkdf2";
+const PRF: &str = "hmac-sha256";
+const DKLEN: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Keystore {
+    pub version: u8,
+    pub account_id: AccountId,
+    pub public_key: PublicKey,
+    pub crypto: KeystoreCrypto,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KeystoreCrypto {
+    pub cipher: String,
+    pub cipherparams: CipherParams,
+    #[serde_as(as = "serde_with::hex::Hex")]
+    pub ciphertext: Vec<u8>,
+    pub kdf: String,
+    pub kdfparams: KdfParams,
+    #[serde_as(as = "serde_with::hex::Hex")]
+    pub mac: [u8; 32],
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CipherParams {
+    #[serde_as(as = "serde_with::hex::Hex")]
+    pub iv: [u8; 16],
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KdfParams {
+    pub c: u32,
+    pub dklen: usize,
+    pub prf: String,
+    #[serde_as(as = "serde_with::hex::Hex")]
+    pub salt: Vec<u8>,
+}
+
+impl Keystore {
+    /// Encrypt the secret key with the password, using `rounds` of PBKDF2.
+    pub fn encrypt(
+        account_id: AccountId,
+        secret_key: &SecretKey,
+        password: &str,
+        rounds: u32,
+    ) -> Self {
+        let mut rng = rand::thread_rng();
+        let mut salt = vec![0u8; 32];
+        rng.fill_bytes(&mut salt);
+        let mut iv = [0u8; 16];
+        rng.fill_bytes(&mut iv);
+
+        let key = derive_key(password, &salt, rounds);
+        let mut ciphertext = secret_key.to_string().into_bytes();
+        aes_128_ctr(&key, &iv, &mut ciphertext);
+        let mac = mac(&key, &ciphertext);
+
+        Self {
+            version: KEYSTORE_VERSION,
+            account_id,
+            public_key: secret_key.public_key(),
+            crypto: KeystoreCrypto {
+                cipher: CIPHER.to_string(),
+                cipherparams: CipherParams { iv },
+                ciphertext,
+                kdf: KDF.to_string(),
+                kdfparams: KdfParams {
+                    c: rounds,
+                    dklen: DKLEN,
+                    prf: PRF.to_string(),
+                    salt,
+                },
+                mac,
+            },
+        }
+    }
+
+    /// Decrypt the keystore into a signer. Fails without decrypting if the
+    /// password doesn't match the MAC.
+    pub fn decrypt(&self, password: &str) -> Result<InMemorySigner> {
+        let crypto = &self.crypto;
+        if self.version != KEYSTORE_VERSION {
+            bail!("unsupported keystore version {}", self.version);
+        }
+        if crypto.cipher != CIPHER || crypto.kdf != KDF || crypto.kdfparams.prf != PRF {
+            bail!(
+                "unsupported keystore encryption {}/{}/{}, expected {CIPHER}/{KDF}/{PRF}",
+                crypto.cipher,
+                crypto.kdf,
+                crypto.kdfparams.prf
+            );
+        }
+        if crypto.kdfparams.dklen != DKLEN {
+            bail!("unsupported keystore key length {}", crypto.kdfparams.dklen);
+        }
+        if !(1..=MAX_ROUNDS).contains(&crypto.kdfparams.c) {
+            bail!(
+                "keystore asks for {} PBKDF2 rounds, expected 1 to {MAX_ROUNDS}",
+                crypto.kdfparams.c
+            );
+        }
+
+        // the key and plaintext are cleared however this returns
+        let key = derive_key(password, &crypto.kdfparams.salt, crypto.kdfparams.c);
+        if !bool::from(mac(&key, &crypto.ciphertext)[..].ct_eq(&crypto.mac[..])) {
+            bail!("invalid keystore password");
+        }
+
+        let mut plaintext = Zeroizing::new(crypto.ciphertext.clone());
+        aes_128_ctr(&key, &crypto.cipherparams.iv, &mut plaintext);
+        let secret_key = std::str::from_utf8(&plaintext)
+            .ok()
+            .and_then(|s| s.parse::<SecretKey>().ok())
+            .ok_or_else(|| eyre!("keystore does not contain a valid secret key"))?;
+
+        if secret_key.public_key() != self.public_key {
+            bail!(
+                "keystore secret key does not match public key {}",
+                self.public_key
+            );
+        }
+        Ok(InMemorySigner::from_secret_key(
+            self.account_id.clone(),
+            secret_key,
+        ))
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read keystore {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .wrap_err_with(|| format!("failed to parse keystore {}", path.display()))
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)
+            .wrap_err_with(|| format!("failed to write keystore {}", path.display()))
+    }
+}
+
+fn derive_key(password: &str, salt: &[u8], rounds: u32) -> Zeroizing<[u8; DKLEN]> {
+    let mut key = Zeroizing::new([0u8; DKLEN]);
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, rounds, &mut key[..]);
+    key
+}
+
+/// keccak256 of the second half of the derived key and the ciphertext.
+fn mac(key: &[u8; DKLEN], ciphertext: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(&key[16..]);
+    hasher.update(ciphertext);
+    hasher.finalize().into()
+}
+
+/// AES-128 in CTR mode with a 128 bit big endian counter, keyed with the first
+/// half of the derived key. Encryption and decryption are the same operation.
+fn aes_128_ctr(key: &[u8; DKLEN], iv: &[u8; 16], data: &mut [u8]) {
+    let cipher = Aes128::new(GenericArray::from_slice(&key[..16]));
+    let mut counter = u128::from_be_bytes(*iv);
+    for chunk in data.chunks_mut(16) {
+        let mut block = GenericArray::from(counter.to_be_bytes());
+        cipher.encrypt_block(&mut block);
+        chunk.iter_mut().zip(block).for_each(|(b, k)| *b ^= k);
+        counter = counter.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROUNDS: u32 = 1024;
+
+    fn keystore(password: &str) -> (Keystore, SecretKey) {
+        let secret_key = SecretKey::from_random(near_crypto::KeyType::ED25519);
+        let keystore = Keystore::encrypt(
+            "alice.testnet".parse().unwrap(),
+            &secret_key,
+            password,
+            ROUNDS,
+        );
+        (keystore, secret_key)
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let (keystore, secret_key) = keystore("hunter2");
+        let signer = keystore.decrypt("hunter2").unwrap();
+        assert_eq!(signer.secret_key, secret_key);
+        assert_eq!(signer.account_id.as_str(), "alice.testnet");
+    }
+
+    #[test]
+    fn test_roundtrip_file() {
+        let (keystore, _) = keystore("hunter2");
+        let path = std::env::temp_dir().join("near-da-rpc-keystore-test.json");
+        keystore.write_to_file(&path).unwrap();
+        assert_eq!(Keystore::from_file(&path).unwrap(), keystore);
+    }
+
+    #[test]
+    fn test_wrong_password() {
+        let (keystore, _) = keystore("hunter2");
+        let err = keystore.decrypt("hunter3").unwrap_err();
+        assert_eq!(err.to_string(), "invalid keystore password");
+    }
+
+    #[test]
+    fn test_secret_key_not_in_keystore() {
+        let (keystore, secret_key) = keystore("hunter2");
+        let json = serde_json::to_string(&keystore).unwrap();
+        assert!(!json.contains(&secret_key.to_string()));
+    }
+
+    #[test]
+    fn test_tampered_ciphertext() {
+        let (mut keystore, _) = keystore("hunter2");
+        keystore.crypto.ciphertext[0] ^= 1;
+        assert!(keystore.decrypt("hunter2").is_err());
+    }
+
+    #[test]
+    fn test_too_many_rounds() {
+        let (mut keystore, _) = keystore("hunter2");
+        keystore.crypto.kdfparams.c = u32::MAX;
+        let err = keystore.decrypt("hunter2").unwrap_err();
+        assert!(err.to_string().contains("PBKDF2 rounds"), "{err}");
+
+        keystore.crypto.kdfparams.c = 0;
+        assert!(keystore.decrypt("hunter2").is_err());
+    }
+
+    #[test]
+    fn test_mismatched_public_key() {
+        let (mut keystore, _) = keystore("hunter2");
+        keystore.public_key = SecretKey::from_random(near_crypto::KeyType::ED25519).public_key();
+        assert!(keystore.decrypt("hunter2").is_err());
+    }
+
+    // Test vector from the Ethereum Web3 Secret Storage definition, which
+    // shares the key derivation, cipher and MAC.
+    #[test]
+    fn test_web3_secret_storage_vector() {
+        let salt = hex("ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd");
+        let key = derive_key("testpassword", &salt, 262_144);
+
+        let mut data = hex("5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46");
+        assert_eq!(
+            mac(&key, &data).to_vec(),
+            hex("517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2")
+        );
+
+        let iv: [u8; 16] = hex("6087dab2f9fdbbfaddc31a909735c1e6").try_into().unwrap();
+        aes_128_ctr(&key, &iv, &mut data);
+        assert_eq!(
+            data,
+            hex("7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d")
+        );
+    }
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+}
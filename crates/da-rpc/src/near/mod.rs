@@ -1,10 +1,13 @@
-use std::str::FromStr;
+use std::{
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
 
 use super::{Blob, DataAvailability};
-use crate::{Read, SubmitResult};
+use crate::{NotFound, Read, SubmitResult};
 use config::Config;
 use eyre::{eyre, Result};
-use near_crypto::{InMemorySigner, Signer};
+use near_crypto::InMemorySigner;
 use near_da_primitives::{LegacyBlob, Mode, SubmitRequest};
 use near_jsonrpc_client::{
     methods::{
@@ -12,7 +15,10 @@ use near_jsonrpc_client::{
     },
     JsonRpcClient,
 };
-use near_jsonrpc_primitives::types::{query::QueryResponseKind, transactions::TransactionInfo};
+use near_jsonrpc_primitives::types::{
+    query::QueryResponseKind,
+    transactions::{RpcTransactionError, TransactionInfo},
+};
 use near_primitives::{
     borsh,
     views::{FinalExecutionOutcomeViewEnum, FinalExecutionStatus},
@@ -20,15 +26,23 @@ use near_primitives::{
 use near_primitives::{
     borsh::{BorshDeserialize, BorshSerialize},
     hash::CryptoHash,
-    transaction::{Action, FunctionCallAction, Transaction},
+    transaction::{Action, FunctionCallAction, SignedTransaction, Transaction},
     types::{AccountId, BlockReference, Nonce},
-    views::{ActionView, TxExecutionStatus},
+    views::{ActionView, LightClientBlockLiteView, TxExecutionStatus},
 };
 use serde::{Deserialize, Serialize};
-use tokio::pin;
+use signer::Signer;
+use tokio::{pin, sync::OnceCell};
 use tracing::{debug, error, trace};
 
+pub mod admin;
 pub mod config;
+pub mod health;
+pub mod keystore;
+pub mod proof;
+pub mod registry;
+pub mod signer;
+pub mod status;
 
 pub const GAS_LIMIT: u64 = 20_000_000_000_000; // usually 15tgas for 1.5mb
 
@@ -36,6 +50,11 @@ pub struct Client {
     pub config: Config,
     pub client: JsonRpcClient,
     pub archive: JsonRpcClient,
+    signer: OnceCell<Arc<dyn Signer>>,
+    /// The light client block that [`Client::prove`] proves against.
+    trusted_head: RwLock<Option<LightClientBlockLiteView>>,
+    /// Set once the registry has allowed the signer to submit.
+    registry_checked: OnceCell<()>,
 }
 
 impl Client {
@@ -44,9 +63,30 @@ impl Client {
             config: config.clone(),
             client: JsonRpcClient::connect(config.network.to_endpoint()),
             archive: JsonRpcClient::connect(config.network.archive_endpoint()),
+            signer: OnceCell::new(),
+            trusted_head: RwLock::new(None),
+            registry_checked: OnceCell::new(),
         }
     }
 
+    /// Create a client with a signer that was already built, e.g. to validate
+    /// the key before the client is used.
+    pub fn with_signer(config: &Config, signer: Arc<dyn Signer>) -> Self {
+        Self {
+            signer: OnceCell::new_with(Some(signer)),
+            ..Self::new(config)
+        }
+    }
+
+    /// The signer for the configured key, built on first use so keystores are
+    /// only decrypted once.
+    pub async fn signer(&self) -> Result<Arc<dyn Signer>> {
+        self.signer
+            .get_or_try_init(|| async { signer::from_config(&self.config) })
+            .await
+            .cloned()
+    }
+
     async fn get_current_nonce(
         &self,
         account_id: &AccountId,
@@ -75,10 +115,10 @@ impl Client {
         }
     }
 
-    pub async fn get_nonce_signer(&self) -> Result<(InMemorySigner, CryptoHash, Nonce)> {
-        let signer = get_signer(&self.config)?;
+    pub async fn get_nonce_signer(&self) -> Result<(Arc<dyn Signer>, CryptoHash, Nonce)> {
+        let signer = self.signer().await?;
         if let Some((latest_hash, current_nonce)) = self
-            .get_current_nonce(&signer.account_id, &signer.public_key)
+            .get_current_nonce(signer.account_id(), &signer.public_key())
             .await?
         {
             Ok((signer, latest_hash, current_nonce))
@@ -87,7 +127,7 @@ impl Client {
         }
     }
 
-    pub async fn no_signer(&self) -> Result<impl Signer> {
+    pub async fn no_signer(&self) -> Result<impl near_crypto::Signer> {
         Ok(near_crypto::EmptySigner {})
     }
 
@@ -101,41 +141,74 @@ impl Client {
         }
     }
 
-    pub fn build_function_call_transaction<S: Signer>(
-        signer: &S,
-        signer_account_id: &AccountId,
+    pub async fn build_function_call_transaction(
+        signer: &dyn Signer,
         contract: &AccountId,
         latest_hash: &CryptoHash,
         current_nonce: Nonce,
         action: FunctionCallAction,
         mode: &Mode,
-    ) -> RpcSendTransactionRequest {
+    ) -> Result<RpcSendTransactionRequest> {
+        Self::build_transaction(
+            signer,
+            contract,
+            latest_hash,
+            current_nonce,
+            vec![Action::FunctionCall(Box::new(action))],
+            mode,
+        )
+        .await
+    }
+
+    /// Sign `actions` for `receiver` with the nonce after `current_nonce`.
+    pub async fn build_transaction(
+        signer: &dyn Signer,
+        receiver: &AccountId,
+        latest_hash: &CryptoHash,
+        current_nonce: Nonce,
+        actions: Vec<Action>,
+        mode: &Mode,
+    ) -> Result<RpcSendTransactionRequest> {
         let tx = Transaction {
-            signer_id: signer_account_id.clone(),
+            signer_id: signer.account_id().clone(),
             public_key: signer.public_key(),
             nonce: current_nonce + 1,
-            receiver_id: contract.clone(),
+            receiver_id: receiver.clone(),
             block_hash: *latest_hash,
-            actions: vec![Action::FunctionCall(Box::new(action))],
+            actions,
         };
-        RpcSendTransactionRequest {
-            signed_transaction: tx.sign(signer),
+        let signature = signer.sign(tx.get_hash_and_size().0.as_ref()).await?;
+        Ok(RpcSendTransactionRequest {
+            signed_transaction: SignedTransaction::new(signature, tx),
             wait_until: wait_submit(mode),
-        }
+        })
     }
 }
 
 pub fn get_signer(config: &Config) -> Result<InMemorySigner> {
     Ok(match config.key {
         config::KeyType::File(ref path) => InMemorySigner::from_file(path)?,
-        config::KeyType::Seed(ref account_id, ref seed) => {
-            InMemorySigner::from_seed(account_id.parse()?, near_crypto::KeyType::ED25519, seed)
-        }
+        config::KeyType::Seed(ref account_id, ref seed) => InMemorySigner::from_seed(
+            account_id.parse()?,
+            near_crypto::KeyType::ED25519,
+            seed.expose(),
+        ),
         config::KeyType::SecretKey(ref account_id, ref secret_key) => {
-            InMemorySigner::from_secret_key(
-                account_id.parse()?,
-                near_crypto::SecretKey::from_str(secret_key)?,
-            )
+            // The parse error can quote parts of the key, so it isn't passed on
+            let secret_key = near_crypto::SecretKey::from_str(secret_key.expose())
+                .map_err(|_| eyre!("invalid secret key for {account_id}"))?;
+            InMemorySigner::from_secret_key(account_id.parse()?, secret_key)
+        }
+        config::KeyType::Keystore(ref path, ref password) => {
+            keystore::Keystore::from_file(path)?.decrypt(password.expose())?
+        }
+        config::KeyType::External(..) => {
+            return Err(eyre!(
+                "external signers do not hold the secret key in memory"
+            ))
+        }
+        config::KeyType::ReadOnly(ref account_id) => {
+            return Err(eyre!("{account_id} is read only, there is no key"))
         }
     })
 }
@@ -149,6 +222,23 @@ struct LegacyRequest {
 #[async_trait::async_trait]
 impl DataAvailability for Client {
     async fn submit(&self, blob: Blob) -> Result<SubmitResult> {
+        self.submit_signed(blob, &|_| {}).await
+    }
+
+    async fn submit_signed(
+        &self,
+        blob: Blob,
+        signed: &(dyn Fn(CryptoHash) + Send + Sync),
+    ) -> Result<SubmitResult> {
+        let registry_namespace = match (&self.config.registry, self.config.namespace) {
+            (None, _) => None,
+            (Some(_), Some(namespace)) => Some(namespace.id),
+            (Some(_), None) => return Err(eyre!("submitting to a registry needs a namespace")),
+        };
+        if let Some(namespace) = registry_namespace {
+            self.check_submitter(namespace).await?;
+        }
+
         let (signer, latest_hash, current_nonce) = self.get_nonce_signer().await?;
 
         let submit_req = SubmitRequest {
@@ -156,8 +246,7 @@ impl DataAvailability for Client {
             data: blob.data,
         };
         let req = Client::build_function_call_transaction(
-            &signer,
-            &signer.account_id,
+            signer.as_ref(),
             &self.config.contract.parse()?,
             &latest_hash,
             current_nonce,
@@ -168,9 +257,11 @@ impl DataAvailability for Client {
                 deposit: 0,
             },
             &self.config.mode,
-        );
+        )
+        .await?;
+        signed(req.signed_transaction.get_hash());
 
-        match self
+        let transaction_id = match self
             .client
             .call(&req)
             .await?
@@ -180,24 +271,36 @@ impl DataAvailability for Client {
             Some(v) => match v.status {
                 FinalExecutionStatus::SuccessValue(r) => {
                     debug!("Transaction submitted, result: {:?}", r);
-                    Ok(SubmitResult(v.transaction.hash.0.into()))
+                    v.transaction.hash
                 }
                 FinalExecutionStatus::Failure(e) => {
                     error!("Error submitting transaction: {:?}", e);
-                    Err(eyre!("Error submitting transaction: {:?}", e))
+                    return Err(eyre!("Error submitting transaction: {:?}", e));
+                }
+                _ => {
+                    return Err(eyre!(
+                        "Transaction not ready yet, this should not be reachable"
+                    ))
                 }
-                _ => Err(eyre!(
-                    "Transaction not ready yet, this should not be reachable"
-                )),
             },
-            None => Err(eyre!("Transaction not ready yet")),
+            None => return Err(eyre!("Transaction not ready yet")),
+        };
+
+        if let Some(namespace) = registry_namespace {
+            // The blob is already posted, so a failed record doesn't fail the submission
+            if let Err(e) = self.record(namespace, transaction_id).await {
+                error!(
+                    "blob {transaction_id} was submitted, but not recorded in the registry: {e:#}"
+                );
+            }
         }
+        Ok(SubmitResult(transaction_id.0.into()))
     }
 
     async fn get(&self, transaction_id: CryptoHash) -> Result<Read> {
-        let (signer, _, _) = self.get_nonce_signer().await?;
+        let signer = self.signer().await?;
 
-        let req = Client::build_view_call(transaction_id, signer.account_id);
+        let req = Client::build_view_call(transaction_id, signer.account_id().clone());
 
         let std = self.client.call(&req);
         pin!(std);
@@ -207,6 +310,9 @@ impl DataAvailability for Client {
         let (result, _rest) = futures::future::select_ok([std, archive])
             .await
             .map_err(|e| {
+                if let Some(RpcTransactionError::UnknownTransaction { .. }) = e.handler_error() {
+                    return NotFound(transaction_id).into();
+                }
                 eyre!(
                     "Error getting blob: {:?} - check the transaction was included, this usually happens if the transaction failed and didn't reach finality",
                     e
@@ -290,7 +396,10 @@ mod tests {
     fn test_get_signer() {
         let account_id = "throwawaykey.testnet";
         let signer = get_signer(&Config {
-            key: config::KeyType::Seed(account_id.parse().unwrap(), "ed25519:test".to_string()),
+            key: config::KeyType::Seed(
+                account_id.parse().unwrap(),
+                "ed25519:test".to_string().into(),
+            ),
             ..Default::default()
         })
         .unwrap();
@@ -303,7 +412,7 @@ mod tests {
         let signer = get_signer(&Config {
             key: config::KeyType::SecretKey(
                 account_id.parse().unwrap(),
-                "ed25519:2T3R1CBAsKQN1Xa9fN9aL1epRwnxgbvk5RAy3sNAdh1n4nfkD9gyGKDLECBMVkwg1zPeewPG9eoX8XVRC6tr6nDt".to_string(),
+                "ed25519:2T3R1CBAsKQN1Xa9fN9aL1epRwnxgbvk5RAy3sNAdh1n4nfkD9gyGKDLECBMVkwg1zPeewPG9eoX8XVRC6tr6nDt".to_string().into(),
             ),
             ..Default::default()
         })
@@ -315,6 +424,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_secrets_redacted() {
+        let secret = "ed25519:2T3R1CBAsKQN1Xa9fN9aL1epRwnxgbvk5RAy3sNAdh1n4nfkD9gyGKDLECBMVkwg1zPeewPG9eoX8XVRC6tr6nDt";
+        for key in [
+            config::KeyType::SecretKey(
+                "throwawaykey.testnet".to_string(),
+                secret.to_string().into(),
+            ),
+            config::KeyType::Seed(
+                "throwawaykey.testnet".to_string(),
+                secret.to_string().into(),
+            ),
+            config::KeyType::Keystore("keystore.json".into(), secret.to_string().into()),
+        ] {
+            let config = Config {
+                key,
+                ..Default::default()
+            };
+            assert!(!format!("{:?}", config).contains(secret));
+            assert!(!format!("{:#?}", config).contains(secret));
+        }
+    }
+
+    #[test]
+    fn test_invalid_secret_key_not_in_error() {
+        let secret = "ed25519:not-a-valid-key-l0O";
+        let err = get_signer(&Config {
+            key: config::KeyType::SecretKey(
+                "throwawaykey.testnet".to_string(),
+                secret.to_string().into(),
+            ),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert!(!format!("{err:?}").contains("not-a-valid-key"));
+    }
+
     #[tokio::test]
     async fn test_live_failed() {
         tracing_subscriber::fmt()
@@ -327,11 +473,12 @@ mod tests {
         let secret = "ed25519:2FPg5DHbr3oFLMKGiEhUsKUyf7vCy81qYHqdHNEHqTAaRzv2tJi2NWPLvbLoeTXzQP9jX6pNzfc83k3nSNNrpqQx";
 
         let config = Config {
-            key: config::KeyType::SecretKey(account.to_string(), secret.to_string()),
+            key: config::KeyType::SecretKey(account.to_string(), secret.to_string().into()),
             contract: "blarg233.testnet".to_string(),
             network: Network::Testnet,
             namespace: None,
             mode: Mode::Standard,
+            registry: None,
         };
         let client = Client::new(&config);
 
@@ -353,11 +500,12 @@ mod tests {
         let secret = "ed25519:2FPg5DHbr3oFLMKGiEhUsKUyf7vCy81qYHqdHNEHqTAaRzv2tJi2NWPLvbLoeTXzQP9jX6pNzfc83k3nSNNrpqQx";
 
         let config = Config {
-            key: config::KeyType::SecretKey(account.to_string(), secret.to_string()),
+            key: config::KeyType::SecretKey(account.to_string(), secret.to_string().into()),
             contract: "blarg233.testnet".to_string(),
             network: Network::Testnet,
             namespace: None,
             mode: Mode::Standard,
+            registry: None,
         };
         let client = Client::new(&config);
 
@@ -379,11 +527,12 @@ mod tests {
         let secret = "ed25519:2FPg5DHbr3oFLMKGiEhUsKUyf7vCy81qYHqdHNEHqTAaRzv2tJi2NWPLvbLoeTXzQP9jX6pNzfc83k3nSNNrpqQx";
 
         let config = Config {
-            key: config::KeyType::SecretKey(account.to_string(), secret.to_string()),
+            key: config::KeyType::SecretKey(account.to_string(), secret.to_string().into()),
             contract: "throwawaykey.testnet".to_string(),
             network: Network::Testnet,
             namespace: None,
             mode: Mode::Standard,
+            registry: None,
         };
         let client = Client::new(&config);
 
@@ -405,11 +554,12 @@ mod tests {
         let secret = "ed25519:2FPg5DHbr3oFLMKGiEhUsKUyf7vCy81qYHqdHNEHqTAaRzv2tJi2NWPLvbLoeTXzQP9jX6pNzfc83k3nSNNrpqQx";
 
         let config = Config {
-            key: config::KeyType::SecretKey(account.to_string(), secret.to_string()),
+            key: config::KeyType::SecretKey(account.to_string(), secret.to_string().into()),
             contract: "throwawaykey.testnet".to_string(),
             network: Network::Testnet,
             namespace: None,
             mode: Mode::Standard,
+            registry: None,
         };
         let client = Client::new(&config);
 
@@ -431,6 +581,16 @@ mod tests {
     #[test]
     fn test_build_submit() {}
 
+    #[tokio::test]
+    async fn test_registry_needs_namespace() {
+        let client = Client::new(&Config {
+            registry: Some("registry.testnet".to_string()),
+            ..Default::default()
+        });
+        let err = client.submit(Blob::new(vec![1, 2, 3])).await.unwrap_err();
+        assert!(err.to_string().contains("needs a namespace"), "{err}");
+    }
+
     #[test]
     fn test_serialise_submit_no_namespace() {
         let req = SubmitRequest {
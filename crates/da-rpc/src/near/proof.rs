@@ -0,0 +1,555 @@
+//! Inclusion proofs for submitted blobs, from `EXPERIMENTAL_light_client_proof`.
+//!
+//! A proof links the outcome of the submission to a light client block the
+//! caller already trusts, e.g. the head of their own light client. It is
+//! checked here before it is returned, and can be checked again by whoever
+//! receives it with [`BlobProof::verify`], or offline as a
+//! [`near_da_primitives::proof::InclusionProof`].
+
+use super::Client;
+use eyre::{bail, eyre, Result};
+use near_crypto::{KeyType, PublicKey};
+use near_da_primitives::proof as offline;
+use near_jsonrpc_client::methods::light_client_proof::RpcLightClientExecutionProofRequest;
+use near_primitives::{
+    hash::CryptoHash,
+    merkle::{compute_root_from_path, compute_root_from_path_and_item, Direction, MerklePath},
+    transaction::Transaction,
+    types::{AccountId, TransactionOrReceiptId},
+    views::{
+        ActionView, ExecutionOutcomeWithIdView, ExecutionStatusView, FinalExecutionOutcomeViewEnum,
+        LightClientBlockLiteView, SignedTransactionView,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+/// Proof that a transaction was executed in a block that the trusted head
+/// commits to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlobProof {
+    pub transaction_id: CryptoHash,
+    pub sender: AccountId,
+    /// The submission, as `tx` returns it.
+    pub transaction: SignedTransactionView,
+    /// The trusted light client block the proof leads to.
+    pub head: LightClientBlockLiteView,
+    /// The outcome, with its path to the chunk outcome root.
+    pub outcome_proof: ExecutionOutcomeWithIdView,
+    /// From the chunk outcome root to the block outcome root.
+    pub outcome_root_proof: MerklePath,
+    /// The block the transaction was executed in.
+    pub block_header_lite: LightClientBlockLiteView,
+    /// From that block to the head's block merkle root.
+    pub block_proof: MerklePath,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidProof {
+    /// The outcome is not the transaction's.
+    Transaction,
+    /// The outcome does not lead to the block's outcome root.
+    OutcomeRoot,
+    /// The block does not lead to the head's block merkle root.
+    BlockRoot,
+}
+
+impl std::fmt::Display for InvalidProof {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Transaction => write!(f, "proof is for another transaction"),
+            Self::OutcomeRoot => write!(f, "outcome is not in the block's outcome root"),
+            Self::BlockRoot => write!(f, "block is not in the trusted head's block merkle root"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidProof {}
+
+impl BlobProof {
+    pub fn verify(&self) -> std::result::Result<(), InvalidProof> {
+        if self.outcome_proof.id != self.transaction_id
+            || self.transaction.hash != self.transaction_id
+        {
+            return Err(InvalidProof::Transaction);
+        }
+        let outcome_root = outcome_root(
+            self.outcome_proof.to_hashes(),
+            &self.outcome_proof.proof,
+            &self.outcome_root_proof,
+        );
+        if outcome_root != self.block_header_lite.inner_lite.outcome_root {
+            return Err(InvalidProof::OutcomeRoot);
+        }
+        let block_root =
+            compute_root_from_path_and_item(&self.block_proof, &self.block_header_lite.hash());
+        if block_root != self.head.inner_lite.block_merkle_root {
+            return Err(InvalidProof::BlockRoot);
+        }
+        Ok(())
+    }
+
+    /// The proof in the types [`offline::InclusionProof::verify`] checks.
+    ///
+    /// The transaction view leaves out the block hash the transaction was
+    /// signed against, which is part of its id, so it is passed in by the
+    /// submitter. It fails if the rebuilt transaction isn't the proven one.
+    pub fn to_inclusion_proof(&self, block_hash: CryptoHash) -> Result<offline::InclusionProof> {
+        let transaction = signed_transaction(&self.transaction, block_hash)?;
+        if transaction.hash() != self.transaction_id.0 {
+            bail!(
+                "{} was not signed against block {block_hash}",
+                self.transaction_id
+            );
+        }
+        Ok(offline::InclusionProof {
+            transaction,
+            outcome_proof: outcome_proof(&self.outcome_proof),
+            outcome_root_proof: merkle_path(&self.outcome_root_proof),
+            block_header_lite: light_client_block(&self.block_header_lite),
+            block_proof: merkle_path(&self.block_proof),
+        })
+    }
+}
+
+pub fn light_client_block(view: &LightClientBlockLiteView) -> offline::LightClientBlockLite {
+    let inner = &view.inner_lite;
+    offline::LightClientBlockLite {
+        prev_block_hash: view.prev_block_hash.0,
+        inner_rest_hash: view.inner_rest_hash.0,
+        inner_lite: offline::BlockHeaderInnerLite {
+            height: inner.height,
+            epoch_id: inner.epoch_id.0,
+            next_epoch_id: inner.next_epoch_id.0,
+            prev_state_root: inner.prev_state_root.0,
+            outcome_root: inner.outcome_root.0,
+            // `timestamp` is the same, kept for older clients
+            timestamp: inner.timestamp_nanosec,
+            next_bp_hash: inner.next_bp_hash.0,
+            block_merkle_root: inner.block_merkle_root.0,
+        },
+    }
+}
+
+pub fn merkle_path(path: &MerklePath) -> offline::MerklePath {
+    path.iter()
+        .map(|item| offline::MerklePathItem {
+            hash: item.hash.0,
+            direction: match item.direction {
+                Direction::Left => offline::Direction::Left,
+                Direction::Right => offline::Direction::Right,
+            },
+        })
+        .collect()
+}
+
+pub fn outcome_proof(view: &ExecutionOutcomeWithIdView) -> offline::OutcomeProof {
+    let outcome = &view.outcome;
+    offline::OutcomeProof {
+        proof: merkle_path(&view.proof),
+        block_hash: view.block_hash.0,
+        id: view.id.0,
+        outcome: offline::ExecutionOutcome {
+            logs: outcome.logs.clone(),
+            receipt_ids: outcome.receipt_ids.iter().map(|id| id.0).collect(),
+            gas_burnt: outcome.gas_burnt,
+            tokens_burnt: outcome.tokens_burnt,
+            executor_id: outcome.executor_id.to_string(),
+            status: match &outcome.status {
+                ExecutionStatusView::Unknown => offline::ExecutionStatus::Unknown,
+                ExecutionStatusView::Failure(_) => offline::ExecutionStatus::Failure,
+                ExecutionStatusView::SuccessValue(value) => {
+                    offline::ExecutionStatus::SuccessValue(value.clone())
+                }
+                ExecutionStatusView::SuccessReceiptId(id) => {
+                    offline::ExecutionStatus::SuccessReceiptId(id.0)
+                }
+            },
+        },
+    }
+}
+
+/// The transaction `view` shows, signed against `block_hash`.
+pub fn signed_transaction(
+    view: &SignedTransactionView,
+    block_hash: CryptoHash,
+) -> Result<offline::Transaction> {
+    offline_transaction(
+        &view.signer_id,
+        &view.public_key,
+        view.nonce,
+        &view.receiver_id,
+        block_hash,
+        view.actions.iter().cloned(),
+    )
+}
+
+/// The transaction in the types the offline verifier hashes.
+pub fn transaction(transaction: &Transaction) -> Result<offline::Transaction> {
+    offline_transaction(
+        &transaction.signer_id,
+        &transaction.public_key,
+        transaction.nonce,
+        &transaction.receiver_id,
+        transaction.block_hash,
+        transaction.actions.iter().cloned().map(ActionView::from),
+    )
+}
+
+fn offline_transaction(
+    signer_id: &AccountId,
+    public_key: &PublicKey,
+    nonce: u64,
+    receiver_id: &AccountId,
+    block_hash: CryptoHash,
+    actions: impl Iterator<Item = ActionView>,
+) -> Result<offline::Transaction> {
+    let key = public_key.key_data();
+    let public_key = match public_key.key_type() {
+        KeyType::ED25519 => offline::PublicKey::Ed25519(key.try_into()?),
+        KeyType::SECP256K1 => offline::PublicKey::Secp256k1(key.try_into()?),
+    };
+    Ok(offline::Transaction {
+        signer_id: signer_id.to_string(),
+        public_key,
+        nonce,
+        receiver_id: receiver_id.to_string(),
+        block_hash: block_hash.0,
+        actions: actions.map(action).collect::<Result<_>>()?,
+    })
+}
+
+fn action(view: ActionView) -> Result<offline::Action> {
+    Ok(match view {
+        ActionView::CreateAccount => offline::Action::CreateAccount,
+        ActionView::DeployContract { code } => offline::Action::DeployContract { code },
+        ActionView::FunctionCall {
+            method_name,
+            args,
+            gas,
+            deposit,
+        } => offline::Action::FunctionCall(offline::FunctionCall {
+            method_name,
+            args: args.into(),
+            gas,
+            deposit,
+        }),
+        other => bail!("the offline verifier does not decode {other:?}"),
+    })
+}
+
+/// The block outcome root an outcome leads to, through its chunk's root.
+fn outcome_root(
+    outcome_hashes: Vec<CryptoHash>,
+    outcome_path: &MerklePath,
+    chunk_path: &MerklePath,
+) -> CryptoHash {
+    let outcome_hash = CryptoHash::hash_borsh(outcome_hashes);
+    let chunk_outcome_root = compute_root_from_path(outcome_path, outcome_hash);
+    compute_root_from_path_and_item(chunk_path, &chunk_outcome_root)
+}
+
+impl Client {
+    /// Trust `head` for the proofs that follow. It has to be a final block
+    /// after the transactions that are proven, so it is usually moved along
+    /// by a light client.
+    pub fn set_trusted_head(&self, head: LightClientBlockLiteView) {
+        *self.trusted_head.write().unwrap() = Some(head);
+    }
+
+    pub fn trusted_head(&self) -> Option<LightClientBlockLiteView> {
+        self.trusted_head.read().unwrap().clone()
+    }
+
+    /// Prove that a submission from this client's account was executed,
+    /// against the trusted head.
+    pub async fn prove(&self, transaction_id: CryptoHash) -> Result<BlobProof> {
+        let head = self
+            .trusted_head()
+            .ok_or_else(|| eyre!("no trusted light client head, see `set_trusted_head`"))?;
+        let sender = self.signer().await?.account_id().clone();
+
+        let req = RpcLightClientExecutionProofRequest {
+            id: TransactionOrReceiptId::Transaction {
+                transaction_hash: transaction_id,
+                sender_id: sender.clone(),
+            },
+            light_client_head: head.hash(),
+        };
+        // Only archival nodes keep outcomes for long
+        let response = match self.client.call(&req).await {
+            Ok(response) => response,
+            Err(_) => self
+                .archive
+                .call(&req)
+                .await
+                .map_err(|e| eyre!("failed to get a proof for {transaction_id}: {e:?}"))?,
+        };
+
+        let transaction = self.transaction_view(transaction_id, &sender).await?;
+        let proof = BlobProof {
+            transaction_id,
+            sender,
+            transaction,
+            head,
+            outcome_proof: response.outcome_proof,
+            outcome_root_proof: response.outcome_root_proof,
+            block_header_lite: response.block_header_lite,
+            block_proof: response.block_proof,
+        };
+        proof.verify()?;
+        Ok(proof)
+    }
+
+    async fn transaction_view(
+        &self,
+        transaction_id: CryptoHash,
+        sender: &AccountId,
+    ) -> Result<SignedTransactionView> {
+        let req = Client::build_view_call(transaction_id, sender.clone());
+        let response = match self.client.call(&req).await {
+            Ok(response) => response,
+            Err(_) => self
+                .archive
+                .call(&req)
+                .await
+                .map_err(|e| eyre!("failed to get transaction {transaction_id}: {e:?}"))?,
+        };
+        response
+            .final_execution_outcome
+            .map(|outcome| FinalExecutionOutcomeViewEnum::into_outcome(outcome).transaction)
+            .ok_or_else(|| eyre!("transaction {transaction_id} is not executed yet"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::near::GAS_LIMIT;
+    use near_crypto::{SecretKey, Signature};
+    use near_da_primitives::SubmitRequest;
+    use near_primitives::{
+        errors::{InvalidTxError, TxExecutionError},
+        merkle::MerklePathItem,
+        transaction::{
+            Action, CreateAccountAction, DeployContractAction, FunctionCallAction,
+            SignedTransaction,
+        },
+        views::{BlockHeaderInnerLiteView, ExecutionOutcomeView},
+    };
+
+    const CONTRACT: &str = "blobstore.testnet";
+
+    fn item(hash: CryptoHash, direction: Direction) -> MerklePathItem {
+        MerklePathItem { hash, direction }
+    }
+
+    fn header(outcome_root: CryptoHash, block_merkle_root: CryptoHash) -> LightClientBlockLiteView {
+        LightClientBlockLiteView {
+            prev_block_hash: CryptoHash([1; 32]),
+            inner_rest_hash: CryptoHash([2; 32]),
+            inner_lite: BlockHeaderInnerLiteView {
+                height: 100,
+                epoch_id: CryptoHash([3; 32]),
+                next_epoch_id: CryptoHash([4; 32]),
+                prev_state_root: CryptoHash([5; 32]),
+                outcome_root,
+                timestamp: 1,
+                timestamp_nanosec: 1,
+                next_bp_hash: CryptoHash([6; 32]),
+                block_merkle_root,
+            },
+        }
+    }
+
+    fn submission(nonce: u64, data: &[u8]) -> SignedTransaction {
+        let request = SubmitRequest {
+            namespace: None,
+            data: data.to_vec(),
+        };
+        SignedTransaction::new(
+            Signature::empty(KeyType::ED25519),
+            Transaction {
+                signer_id: "rollup.testnet".parse().unwrap(),
+                public_key: PublicKey::from_seed(KeyType::ED25519, "rollup"),
+                nonce,
+                receiver_id: CONTRACT.parse().unwrap(),
+                block_hash: CryptoHash([8; 32]),
+                actions: vec![Action::FunctionCall(Box::new(FunctionCallAction {
+                    method_name: "submit".to_string(),
+                    args: near_primitives::borsh::to_vec(&request).unwrap(),
+                    gas: GAS_LIMIT,
+                    deposit: 0,
+                }))],
+            },
+        )
+    }
+
+    /// A proof for `signed`, one sibling away at each level.
+    fn prove(signed: SignedTransaction) -> BlobProof {
+        let id = signed.get_hash();
+        let outcome_proof = ExecutionOutcomeWithIdView {
+            proof: vec![item(CryptoHash([10; 32]), Direction::Left)],
+            block_hash: CryptoHash([11; 32]),
+            id,
+            outcome: ExecutionOutcomeView {
+                logs: vec!["submitted".to_string()],
+                receipt_ids: vec![CryptoHash([12; 32])],
+                gas_burnt: 1,
+                tokens_burnt: 2,
+                executor_id: signed.transaction.signer_id.clone(),
+                status: ExecutionStatusView::SuccessReceiptId(CryptoHash([12; 32])),
+                metadata: Default::default(),
+            },
+        };
+        let outcome_root_proof = vec![item(CryptoHash([13; 32]), Direction::Right)];
+        let block_header_lite = header(
+            outcome_root(
+                outcome_proof.to_hashes(),
+                &outcome_proof.proof,
+                &outcome_root_proof,
+            ),
+            CryptoHash::default(),
+        );
+        let block_proof = vec![item(CryptoHash([14; 32]), Direction::Left)];
+        let head = header(
+            CryptoHash::default(),
+            compute_root_from_path_and_item(&block_proof, &block_header_lite.hash()),
+        );
+        BlobProof {
+            transaction_id: id,
+            sender: signed.transaction.signer_id.clone(),
+            transaction: signed.into(),
+            head,
+            outcome_proof,
+            outcome_root_proof,
+            block_header_lite,
+            block_proof,
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let proof = prove(submission(1, b"blob"));
+        assert_eq!(proof.verify(), Ok(()));
+
+        let inclusion = proof.to_inclusion_proof(CryptoHash([8; 32])).unwrap();
+        let head = light_client_block(&proof.head);
+        assert_eq!(
+            inclusion.verify(&head, CONTRACT, b"blob"),
+            Ok(proof.transaction_id.0)
+        );
+        // Any other block hash makes it another transaction
+        assert!(proof.to_inclusion_proof(CryptoHash([9; 32])).is_err());
+    }
+
+    #[test]
+    fn test_verify_transaction_mismatch() {
+        let proof = prove(submission(1, b"blob"));
+
+        let mut other_id = proof.clone();
+        other_id.transaction_id = CryptoHash([1; 32]);
+        assert_eq!(other_id.verify(), Err(InvalidProof::Transaction));
+
+        let mut other_transaction = proof.clone();
+        other_transaction.transaction = submission(2, b"blob").into();
+        assert_eq!(other_transaction.verify(), Err(InvalidProof::Transaction));
+
+        // A consistent proof of another transaction
+        let mut other_outcome = prove(submission(2, b"other"));
+        other_outcome.transaction_id = proof.transaction_id;
+        assert_eq!(other_outcome.verify(), Err(InvalidProof::Transaction));
+    }
+
+    /// The offline types hash like the ones NEAR signs and merkelizes.
+    #[test]
+    fn test_offline_hashes() {
+        let proof = prove(submission(1, b"blob"));
+        let block = light_client_block(&proof.block_header_lite);
+        assert_eq!(block.hash(), proof.block_header_lite.hash().0);
+
+        let outcome = outcome_proof(&proof.outcome_proof);
+        let hashes = |hashes: Vec<CryptoHash>| hashes.into_iter().map(|h| h.0).collect::<Vec<_>>();
+        assert_eq!(
+            outcome.outcome.to_hashes(outcome.id),
+            hashes(proof.outcome_proof.to_hashes())
+        );
+        let mut failed = proof.outcome_proof.clone();
+        failed.outcome.status =
+            ExecutionStatusView::Failure(TxExecutionError::InvalidTxError(InvalidTxError::Expired));
+        let converted = outcome_proof(&failed);
+        assert_eq!(
+            converted.outcome.to_hashes(converted.id),
+            hashes(failed.to_hashes())
+        );
+
+        assert_eq!(
+            offline::compute_root_from_path_and_item(
+                &merkle_path(&proof.block_proof),
+                &block.hash()
+            ),
+            compute_root_from_path_and_item(&proof.block_proof, &proof.block_header_lite.hash()).0
+        );
+
+        for key_type in [KeyType::ED25519, KeyType::SECP256K1] {
+            let mut signed = submission(1, b"blob");
+            signed.transaction.public_key = SecretKey::from_seed(key_type, "rollup").public_key();
+            signed.transaction.actions.insert(
+                0,
+                Action::DeployContract(DeployContractAction { code: vec![1, 2] }),
+            );
+            signed
+                .transaction
+                .actions
+                .insert(0, Action::CreateAccount(CreateAccountAction {}));
+            assert_eq!(
+                transaction(&signed.transaction).unwrap().hash(),
+                signed.transaction.get_hash_and_size().0 .0
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_block_root_mismatch() {
+        let proof = prove(submission(1, b"blob"));
+
+        let mut other_head = proof.clone();
+        other_head.head = header(CryptoHash::default(), CryptoHash([15; 32]));
+        assert_eq!(other_head.verify(), Err(InvalidProof::BlockRoot));
+
+        let mut flipped = proof.clone();
+        flipped.block_proof[0].direction = Direction::Right;
+        assert_eq!(flipped.verify(), Err(InvalidProof::BlockRoot));
+
+        let mut other_block = proof;
+        other_block.block_header_lite.inner_lite.height += 1;
+        assert_eq!(other_block.verify(), Err(InvalidProof::BlockRoot));
+    }
+
+    #[test]
+    fn test_outcome_root() {
+        let hashes = vec![
+            CryptoHash::hash_bytes(b"id"),
+            CryptoHash::hash_bytes(b"receipt"),
+        ];
+        let sibling = CryptoHash::hash_bytes(b"sibling");
+        let chunk_sibling = CryptoHash::hash_bytes(b"other chunk");
+
+        let outcome_hash = CryptoHash::hash_borsh(hashes.clone());
+        let chunk_root = CryptoHash::hash_borsh((sibling, outcome_hash));
+        let expected = CryptoHash::hash_borsh((CryptoHash::hash_borsh(chunk_root), chunk_sibling));
+
+        let outcome_path = vec![item(sibling, Direction::Left)];
+        let chunk_path = vec![item(chunk_sibling, Direction::Right)];
+        assert_eq!(
+            outcome_root(hashes.clone(), &outcome_path, &chunk_path),
+            expected
+        );
+
+        // Any change to the outcome or the paths moves the root
+        let mut tampered = hashes.clone();
+        tampered[1] = CryptoHash::hash_bytes(b"other receipt");
+        assert_ne!(outcome_root(tampered, &outcome_path, &chunk_path), expected);
+        let flipped = vec![item(sibling, Direction::Right)];
+        assert_ne!(outcome_root(hashes, &flipped, &chunk_path), expected);
+    }
+}
@@ -0,0 +1,227 @@
+//! Namespaces on a `blob-registry` contract.
+//!
+//! When [`Config::registry`](super::config::Config::registry) is set, a
+//! submission is only posted if the signer maintains the configured namespace
+//! or owns the registry, and its transaction id is then recorded under the
+//! namespace, so consumers can list the namespace's blobs from the registry.
+//! A blob that was posted but couldn't be recorded is logged, not failed.
+
+use super::Client;
+use eyre::{eyre, Result};
+use near_jsonrpc_client::methods::query::RpcQueryRequest;
+use near_jsonrpc_primitives::types::query::QueryResponseKind;
+use near_primitives::{
+    hash::CryptoHash,
+    types::{AccountId, BlockReference, Finality, FunctionArgs},
+    views::QueryRequest,
+};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+/// Attached to `register_consumer`, 0.1 NEAR. What the registration fee and
+/// storage don't use is refunded.
+pub const REGISTRATION_DEPOSIT: u128 = 100_000_000_000_000_000_000_000;
+/// Attached to calls that store more in the registry, 0.01 NEAR. What the
+/// storage doesn't use is refunded.
+pub const STORAGE_DEPOSIT: u128 = 10_000_000_000_000_000_000_000;
+
+impl Client {
+    fn registry(&self) -> Result<AccountId> {
+        self.config
+            .registry
+            .as_deref()
+            .ok_or_else(|| eyre!("no registry configured"))?
+            .parse()
+            .map_err(|e| eyre!("invalid registry account: {e}"))
+    }
+
+    /// Initialize the registry, owned by `owner`.
+    pub async fn init_registry(&self, owner: &AccountId) -> Result<CryptoHash> {
+        self.call_registry("new", json!({ "owner_id": owner }), 0)
+            .await
+    }
+
+    /// Register `namespace` on the registry, with the signer as its maintainer.
+    pub async fn register_namespace(&self, namespace: u32) -> Result<CryptoHash> {
+        self.call_registry(
+            "register_consumer",
+            json!({ "namespace": namespace }),
+            REGISTRATION_DEPOSIT,
+        )
+        .await
+    }
+
+    /// Let `maintainer` submit for `namespace` too. The signer has to maintain
+    /// it already, or own the registry.
+    pub async fn add_maintainer(
+        &self,
+        namespace: u32,
+        maintainer: &AccountId,
+    ) -> Result<CryptoHash> {
+        self.call_registry(
+            "add_maintainer",
+            json!({ "namespace": namespace, "maintainer": maintainer.as_bytes() }),
+            STORAGE_DEPOSIT,
+        )
+        .await
+    }
+
+    /// Whether `account` maintains `namespace`, an unregistered namespace has
+    /// no maintainers.
+    pub async fn is_maintainer(&self, namespace: u32, account: &AccountId) -> Result<bool> {
+        let maintainers: Option<HashSet<Vec<u8>>> = serde_json::from_value(
+            self.view_registry("maintainers", json!({ "namespace": namespace }))
+                .await?,
+        )?;
+        Ok(maintainers.is_some_and(|maintainers| maintainers.contains(account.as_bytes())))
+    }
+
+    /// The owner of the registry, who can submit for every namespace.
+    pub async fn registry_owner(&self) -> Result<Option<AccountId>> {
+        Ok(serde_json::from_value(
+            self.view_registry("own_get_owner", json!({})).await?,
+        )?)
+    }
+
+    /// Fail unless the signer can submit for `namespace`. Only checked until
+    /// it passes once, so later submissions don't pay for the views.
+    pub(crate) async fn check_submitter(&self, namespace: u32) -> Result<()> {
+        self.registry_checked
+            .get_or_try_init(|| async {
+                let signer = self.signer().await?;
+                let (maintainers, owner) = futures::try_join!(
+                    self.view_registry("maintainers", json!({ "namespace": namespace })),
+                    self.registry_owner(),
+                )?;
+                check_submitter(
+                    namespace,
+                    signer.account_id(),
+                    serde_json::from_value(maintainers)?,
+                    owner,
+                )
+            })
+            .await
+            .copied()
+    }
+
+    /// Record a submitted blob under `namespace`.
+    pub(crate) async fn record(
+        &self,
+        namespace: u32,
+        transaction_id: CryptoHash,
+    ) -> Result<CryptoHash> {
+        self.call_registry(
+            "submit",
+            json!({
+                "namespace": namespace,
+                "transaction_ids": [hex::encode(transaction_id.0)],
+            }),
+            STORAGE_DEPOSIT,
+        )
+        .await
+    }
+
+    async fn view_registry(&self, method_name: &str, args: Value) -> Result<Value> {
+        let response = self
+            .client
+            .call(RpcQueryRequest {
+                block_reference: BlockReference::Finality(Finality::Final),
+                request: QueryRequest::CallFunction {
+                    account_id: self.registry()?,
+                    method_name: method_name.to_string(),
+                    args: FunctionArgs::from(serde_json::to_vec(&args)?),
+                },
+            })
+            .await?;
+        match response.kind {
+            QueryResponseKind::CallResult(result) => Ok(serde_json::from_slice(&result.result)?),
+            _ => Err(eyre!("unexpected response to {method_name}")),
+        }
+    }
+
+    async fn call_registry(
+        &self,
+        method_name: &str,
+        args: Value,
+        deposit: u128,
+    ) -> Result<CryptoHash> {
+        self.call_function(
+            &self.registry()?,
+            method_name,
+            serde_json::to_vec(&args)?,
+            deposit,
+        )
+        .await
+    }
+}
+
+/// Fail unless `account` is in the `maintainers` of `namespace`, which is
+/// unregistered if there are none, or is the registry `owner`.
+fn check_submitter(
+    namespace: u32,
+    account: &AccountId,
+    maintainers: Option<HashSet<Vec<u8>>>,
+    owner: Option<AccountId>,
+) -> Result<()> {
+    let Some(maintainers) = maintainers else {
+        return Err(eyre!("namespace {namespace} is not registered"));
+    };
+    if maintainers.contains(account.as_bytes()) || owner.as_ref() == Some(account) {
+        Ok(())
+    } else {
+        Err(eyre!(
+            "{account} does not maintain namespace {namespace} on the registry"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(id: &str) -> AccountId {
+        id.parse().unwrap()
+    }
+
+    fn maintainers(ids: &[&str]) -> Option<HashSet<Vec<u8>>> {
+        Some(ids.iter().map(|id| id.as_bytes().to_vec()).collect())
+    }
+
+    #[test]
+    fn test_check_submitter() {
+        let alice = account("alice.testnet");
+        assert!(check_submitter(1, &alice, maintainers(&["alice.testnet"]), None).is_ok());
+        let err = check_submitter(1, &alice, maintainers(&["bob.testnet"]), None).unwrap_err();
+        assert!(
+            err.to_string().contains("does not maintain namespace 1"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_check_submitter_owner() {
+        let owner = account("registry.testnet");
+        assert!(check_submitter(
+            1,
+            &owner,
+            maintainers(&["bob.testnet"]),
+            Some(owner.clone())
+        )
+        .is_ok());
+        let err = check_submitter(
+            1,
+            &account("alice.testnet"),
+            maintainers(&["bob.testnet"]),
+            Some(owner),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("does not maintain"), "{err}");
+    }
+
+    #[test]
+    fn test_check_submitter_unregistered() {
+        let owner = account("registry.testnet");
+        let err = check_submitter(1, &owner, None, Some(owner.clone())).unwrap_err();
+        assert!(err.to_string().contains("not registered"), "{err}");
+    }
+}
@@ -0,0 +1,249 @@
+//! Transaction signing. Besides keys held in memory, signing can be delegated
+//! to an external process, e.g. a bridge to an HSM or KMS, so the secret key
+//! never enters this process.
+//!
+//! The external signer listens on a unix socket. For each signature the client
+//! connects, writes a [`SignRequest`] as a single line of JSON and reads a
+//! [`SignResponse`] line back.
+
+use super::config::{Config, KeyType};
+use async_trait::async_trait;
+use eyre::{bail, eyre, Result, WrapErr};
+use near_crypto::{InMemorySigner, PublicKey, Signature};
+use near_primitives::types::AccountId;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use std::{path::PathBuf, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::UnixStream,
+};
+
+/// How long to wait for the external signer before failing the submission.
+pub const DEFAULT_EXTERNAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[async_trait]
+pub trait Signer: Send + Sync {
+    fn account_id(&self) -> &AccountId;
+    fn public_key(&self) -> PublicKey;
+    /// Sign a message, for transactions this is the transaction hash.
+    async fn sign(&self, message: &[u8]) -> Result<Signature>;
+}
+
+#[async_trait]
+impl Signer for InMemorySigner {
+    fn account_id(&self) -> &AccountId {
+        &self.account_id
+    }
+
+    fn public_key(&self) -> PublicKey {
+        self.public_key.clone()
+    }
+
+    async fn sign(&self, message: &[u8]) -> Result<Signature> {
+        Ok(near_crypto::Signer::sign(self, message))
+    }
+}
+
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SignRequest {
+    pub account_id: AccountId,
+    pub public_key: PublicKey,
+    #[serde_as(as = "serde_with::hex::Hex")]
+    pub message: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SignResponse {
+    Signature(Signature),
+    Error(String),
+}
+
+/// Delegates signing to an external process listening on a unix socket.
+pub struct ExternalSigner {
+    account_id: AccountId,
+    public_key: PublicKey,
+    socket: PathBuf,
+    timeout: Duration,
+}
+
+impl ExternalSigner {
+    pub fn new(account_id: AccountId, public_key: PublicKey, socket: PathBuf) -> Self {
+        Self {
+            account_id,
+            public_key,
+            socket,
+            timeout: DEFAULT_EXTERNAL_TIMEOUT,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn request(&self, message: &[u8]) -> Result<Signature> {
+        let stream = UnixStream::connect(&self.socket).await.wrap_err_with(|| {
+            format!(
+                "failed to connect to external signer at {}",
+                self.socket.display()
+            )
+        })?;
+        let (read, mut write) = stream.into_split();
+
+        let mut request = serde_json::to_vec(&SignRequest {
+            account_id: self.account_id.clone(),
+            public_key: self.public_key.clone(),
+            message: message.to_vec(),
+        })?;
+        request.push(b'\n');
+        write.write_all(&request).await?;
+
+        let mut response = String::new();
+        BufReader::new(read).read_line(&mut response).await?;
+        match serde_json::from_str(&response).wrap_err("invalid response from external signer")? {
+            SignResponse::Signature(signature) => Ok(signature),
+            SignResponse::Error(e) => Err(eyre!("external signer refused to sign: {e}")),
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for ExternalSigner {
+    fn account_id(&self) -> &AccountId {
+        &self.account_id
+    }
+
+    fn public_key(&self) -> PublicKey {
+        self.public_key.clone()
+    }
+
+    async fn sign(&self, message: &[u8]) -> Result<Signature> {
+        let signature = tokio::time::timeout(self.timeout, self.request(message))
+            .await
+            .map_err(|_| eyre!("external signer timed out after {:?}", self.timeout))??;
+        if !signature.verify(message, &self.public_key) {
+            bail!(
+                "external signer returned an invalid signature for {}",
+                self.public_key
+            );
+        }
+        Ok(signature)
+    }
+}
+
+/// Knows the account, so its submissions can be read, but can't sign.
+pub struct ReadOnlySigner {
+    account_id: AccountId,
+}
+
+impl ReadOnlySigner {
+    pub fn new(account_id: AccountId) -> Self {
+        Self { account_id }
+    }
+}
+
+#[async_trait]
+impl Signer for ReadOnlySigner {
+    fn account_id(&self) -> &AccountId {
+        &self.account_id
+    }
+
+    fn public_key(&self) -> PublicKey {
+        PublicKey::empty(near_crypto::KeyType::ED25519)
+    }
+
+    async fn sign(&self, _message: &[u8]) -> Result<Signature> {
+        bail!("{} is read only, it can't sign", self.account_id)
+    }
+}
+
+/// Build the signer for the configured key.
+pub fn from_config(config: &Config) -> Result<Arc<dyn Signer>> {
+    Ok(match config.key {
+        KeyType::External(ref account_id, ref public_key, ref socket) => Arc::new(
+            ExternalSigner::new(account_id.parse()?, public_key.parse()?, socket.clone()),
+        ),
+        KeyType::ReadOnly(ref account_id) => Arc::new(ReadOnlySigner::new(account_id.parse()?)),
+        _ => Arc::new(super::get_signer(config)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::{KeyType, SecretKey};
+    use tokio::net::UnixListener;
+
+    /// Serve a single signing request with `key`.
+    fn serve(name: &str, key: SecretKey) -> PathBuf {
+        let socket =
+            std::env::temp_dir().join(format!("near-da-signer-{name}-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, mut write) = stream.into_split();
+            let mut line = String::new();
+            BufReader::new(read).read_line(&mut line).await.unwrap();
+            let request: SignRequest = serde_json::from_str(&line).unwrap();
+
+            let response = SignResponse::Signature(key.sign(&request.message));
+            let mut response = serde_json::to_vec(&response).unwrap();
+            response.push(b'\n');
+            write.write_all(&response).await.unwrap();
+        });
+        socket
+    }
+
+    #[tokio::test]
+    async fn test_external_signer() {
+        let secret_key = SecretKey::from_random(KeyType::ED25519);
+        let socket = serve("valid", secret_key.clone());
+
+        let signer = ExternalSigner::new(
+            "alice.testnet".parse().unwrap(),
+            secret_key.public_key(),
+            socket,
+        );
+        let signature = signer.sign(b"transaction hash").await.unwrap();
+        assert!(signature.verify(b"transaction hash", &secret_key.public_key()));
+    }
+
+    #[tokio::test]
+    async fn test_external_signer_wrong_key() {
+        let socket = serve("wrong-key", SecretKey::from_random(KeyType::ED25519));
+
+        let signer = ExternalSigner::new(
+            "alice.testnet".parse().unwrap(),
+            SecretKey::from_random(KeyType::ED25519).public_key(),
+            socket,
+        );
+        assert!(signer.sign(b"transaction hash").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_external_signer_unreachable() {
+        let signer = ExternalSigner::new(
+            "alice.testnet".parse().unwrap(),
+            SecretKey::from_random(KeyType::ED25519).public_key(),
+            "/does/not/exist.sock".into(),
+        );
+        let err = signer.sign(b"transaction hash").await.unwrap_err();
+        assert!(err.to_string().contains("/does/not/exist.sock"));
+    }
+
+    #[tokio::test]
+    async fn test_read_only() {
+        let config = Config {
+            key: super::KeyType::ReadOnly("alice.testnet".to_string()),
+            ..Default::default()
+        };
+        let signer = from_config(&config).unwrap();
+        assert_eq!(signer.account_id().as_str(), "alice.testnet");
+        assert!(signer.sign(b"transaction hash").await.is_err());
+    }
+}
@@ -0,0 +1,89 @@
+//! Transaction status without a signer, for following the submissions of
+//! another account.
+
+use eyre::{eyre, Result};
+use near_jsonrpc_client::{methods::tx::RpcTransactionStatusRequest, JsonRpcClient};
+use near_jsonrpc_primitives::types::transactions::{RpcTransactionError, TransactionInfo};
+use near_primitives::{
+    hash::CryptoHash,
+    types::AccountId,
+    views::{FinalExecutionOutcomeViewEnum, FinalExecutionStatus, TxExecutionStatus},
+};
+use tokio::pin;
+
+use super::config::Network;
+use crate::NotFound;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// Included, but the block is not final or the receipts are still running.
+    Pending,
+    /// Executed successfully in a final block.
+    Final,
+    /// Final, but the submission failed, so the data was never stored.
+    Failed(String),
+}
+
+pub struct Observer {
+    client: JsonRpcClient,
+    archive: JsonRpcClient,
+    sender: AccountId,
+}
+
+impl Observer {
+    /// Follow the transactions signed by `sender`.
+    pub fn new(network: &Network, sender: &str) -> Result<Self> {
+        Ok(Self {
+            client: JsonRpcClient::connect(network.to_endpoint()),
+            archive: JsonRpcClient::connect(network.archive_endpoint()),
+            sender: sender
+                .parse()
+                .map_err(|e| eyre!("invalid sender account {sender}: {e}"))?,
+        })
+    }
+
+    pub fn sender(&self) -> &AccountId {
+        &self.sender
+    }
+
+    /// Where the transaction is, without waiting for it to progress. Fails
+    /// with [`NotFound`] if neither node knows about it.
+    pub async fn status(&self, transaction_id: CryptoHash) -> Result<Status> {
+        let req = RpcTransactionStatusRequest {
+            transaction_info: TransactionInfo::TransactionId {
+                tx_hash: transaction_id,
+                sender_account_id: self.sender.clone(),
+            },
+            wait_until: TxExecutionStatus::Included,
+        };
+
+        let std = self.client.call(&req);
+        pin!(std);
+        let archive = self.archive.call(&req);
+        pin!(archive);
+
+        let (result, _rest) = futures::future::select_ok([std, archive])
+            .await
+            .map_err(|e| {
+                if let Some(RpcTransactionError::UnknownTransaction { .. }) = e.handler_error() {
+                    return NotFound(transaction_id).into();
+                }
+                eyre!("failed to get the status of {transaction_id}: {e:?}")
+            })?;
+
+        if !matches!(result.final_execution_status, TxExecutionStatus::Final) {
+            return Ok(Status::Pending);
+        }
+        Ok(
+            match result
+                .final_execution_outcome
+                .map(FinalExecutionOutcomeViewEnum::into_outcome)
+                .map(|outcome| outcome.status)
+            {
+                Some(FinalExecutionStatus::SuccessValue(_)) => Status::Final,
+                Some(FinalExecutionStatus::Failure(e)) => Status::Failed(format!("{e:?}")),
+                _ => Status::Pending,
+            },
+        )
+    }
+}
@@ -0,0 +1,417 @@
+//! Talks to `NearDataAvailability.sol` on Ethereum, which rollups such as
+//! Polygon CDK check to see that their batches were posted to NEAR.
+
+use ethers::{
+    abi::Detokenize,
+    contract::{ContractCall, EthEvent, LogMeta},
+    middleware::SignerMiddleware,
+    prelude::abigen,
+    providers::{Http, Middleware, PendingTransaction, Provider},
+    signers::{LocalWallet, Signer},
+    types::{Address, BlockNumber, Bytes, TransactionReceipt, TxHash, U256},
+};
+use eyre::{bail, eyre, Result, WrapErr};
+use futures::future::try_join_all;
+use near_da_primitives::{BlobRef, Secret, BLOB_REF_SIZE};
+use std::sync::Arc;
+
+pub mod notifier;
+
+pub use notifier::Notifier;
+
+// Built with `forge build` in `eth/`, see `make build-contracts`
+abigen!(
+    NearDataAvailability,
+    "$CARGO_MANIFEST_DIR/../../eth/out/NearDataAvailability.sol/NearDataAvailability.json"
+);
+
+/// The `dataAvailabilityBatch` the contract decodes: the NEAR transaction ids
+/// holding each batch, in order.
+pub fn data_availability_message(refs: &[BlobRef]) -> Vec<u8> {
+    refs.iter()
+        .flat_map(|blob_ref| blob_ref.transaction_id)
+        .collect()
+}
+
+pub fn parse_data_availability_message(message: &[u8]) -> Result<Vec<BlobRef>> {
+    let ids = message.chunks_exact(BLOB_REF_SIZE);
+    if message.is_empty() || !ids.remainder().is_empty() {
+        bail!(
+            "data availability message must be one or more {BLOB_REF_SIZE} byte transaction ids, got {} bytes",
+            message.len()
+        );
+    }
+    Ok(ids.map(|id| BlobRef::new(id.try_into().unwrap())).collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Ethereum JSON-RPC endpoint.
+    pub rpc: String,
+    /// Address of the `NearDataAvailability` contract.
+    pub contract: Address,
+    /// Hex encoded secp256k1 key of an account with the notifier role.
+    pub key: Secret<String>,
+}
+
+pub type SignedProvider = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+/// A `Submitted` event, emitted by `notifySubmitted` for each transaction id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmittedEvent {
+    /// Where the id is kept in `submittedBatches`, until it is overwritten.
+    pub bucket_idx: u64,
+    pub transaction_id: [u8; 32],
+    pub block_number: u64,
+    pub transaction_hash: TxHash,
+}
+
+/// An `IsAvailable` event, emitted by `notifyAvailable`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AvailableEvent {
+    /// Where the batch is kept in `batchInfo`, until it is overwritten.
+    pub bucket_idx: u64,
+    pub batch: VerifiedBatch,
+    pub block_number: u64,
+    pub transaction_hash: TxHash,
+}
+
+pub struct Client<M = SignedProvider> {
+    contract: NearDataAvailability<M>,
+}
+
+impl Client {
+    /// Connect with a signer, to send notifications.
+    pub async fn new(config: &Config) -> Result<Self> {
+        let provider = provider(&config.rpc)?;
+        let chain_id = provider
+            .get_chainid()
+            .await
+            .wrap_err("failed to get the L1 chain id")?;
+        let wallet = config
+            .key
+            .expose()
+            .parse::<LocalWallet>()
+            .map_err(|_| eyre!("invalid L1 secret key"))?
+            .with_chain_id(chain_id.as_u64());
+
+        Ok(Self::with_middleware(
+            config.contract,
+            Arc::new(SignerMiddleware::new(provider, wallet)),
+        ))
+    }
+}
+
+impl Client<Provider<Http>> {
+    /// Connect without a signer, for reading the contract.
+    pub fn read_only(rpc: &str, contract: Address) -> Result<Self> {
+        Ok(Self::with_middleware(contract, Arc::new(provider(rpc)?)))
+    }
+}
+
+fn provider(rpc: &str) -> Result<Provider<Http>> {
+    Provider::<Http>::try_from(rpc).wrap_err_with(|| format!("invalid L1 RPC url {rpc}"))
+}
+
+impl<M: Middleware + 'static> Client<M> {
+    pub fn with_middleware(contract: Address, middleware: Arc<M>) -> Self {
+        Self {
+            contract: NearDataAvailability::new(contract, middleware),
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        self.contract.address()
+    }
+
+    pub fn contract(&self) -> &NearDataAvailability<M> {
+        &self.contract
+    }
+
+    /// Record on L1 that `refs` were submitted to NEAR, waiting until the
+    /// transaction is included. The sender needs the notifier role.
+    pub async fn notify_submitted(&self, refs: &[BlobRef]) -> Result<TxHash> {
+        let call = self
+            .contract
+            .notify_submitted(Bytes::from(data_availability_message(refs)));
+        transact(call, "notifySubmitted").await
+    }
+
+    /// Record on L1 that a batch was proven available. The sender needs the
+    /// verifier role.
+    pub async fn notify_available(&self, batch: VerifiedBatch) -> Result<TxHash> {
+        transact(self.contract.notify_available(batch), "notifyAvailable").await
+    }
+
+    /// Send `notifyAvailable` with `nonce` without waiting for it, so the
+    /// caller can record the transaction first. See [`Client::confirm`].
+    pub async fn send_notify_available(&self, batch: VerifiedBatch, nonce: u64) -> Result<TxHash> {
+        let call = self.contract.notify_available(batch).nonce(nonce);
+        send(&call, "notifyAvailable").await
+    }
+
+    /// Wait until `tx` is included, failing if it reverted.
+    pub async fn confirm(&self, tx: TxHash) -> Result<TxHash> {
+        let client = self.contract.client();
+        let pending = PendingTransaction::new(tx, client.provider());
+        check_receipt(pending.await?, tx)
+    }
+
+    /// Whether `tx` succeeded, or `None` if it is not included.
+    pub async fn receipt_status(&self, tx: TxHash) -> Result<Option<bool>> {
+        let receipt = self
+            .contract
+            .client()
+            .get_transaction_receipt(tx)
+            .await
+            .map_err(|e| eyre!("failed to get the receipt for {tx:?}: {e}"))?;
+        Ok(receipt.map(|receipt| receipt.status == Some(1.into())))
+    }
+
+    /// The sender's next nonce as of the latest block, and counting its
+    /// transactions still in the mempool.
+    pub async fn nonces(&self) -> Result<(u64, u64)> {
+        let client = self.contract.client();
+        let sender = client
+            .default_sender()
+            .ok_or_else(|| eyre!("the L1 client has no signer"))?;
+        let count = |block: BlockNumber| {
+            let client = client.clone();
+            async move {
+                client
+                    .get_transaction_count(sender, Some(block.into()))
+                    .await
+                    .map(|count| count.as_u64())
+                    .map_err(|e| eyre!("failed to get the nonce of {sender:?}: {e}"))
+            }
+        };
+        futures::try_join!(count(BlockNumber::Latest), count(BlockNumber::Pending))
+    }
+
+    /// Grant `roles`, e.g. `_NOTIFIER`, to `user`. Owner only.
+    pub async fn grant_roles(&self, user: Address, roles: U256) -> Result<TxHash> {
+        transact(self.contract.grant_roles(user, roles), "grantRoles").await
+    }
+
+    pub async fn block_number(&self) -> Result<u64> {
+        Ok(self
+            .contract
+            .client()
+            .get_block_number()
+            .await
+            .map_err(|e| eyre!("failed to get the L1 block number: {e}"))?
+            .as_u64())
+    }
+
+    pub async fn batch_info(&self, bucket_idx: u64) -> Result<VerifiedBatch> {
+        let (id, verify_tx_hash, submit_tx_id) = self
+            .contract
+            .batch_info(bucket_idx.into())
+            .call()
+            .await
+            .map_err(|e| eyre!("failed to read batchInfo({bucket_idx}): {e}"))?;
+        Ok(VerifiedBatch {
+            id,
+            verify_tx_hash,
+            submit_tx_id,
+        })
+    }
+
+    pub async fn submitted_batch(&self, bucket_idx: u64) -> Result<[u8; 32]> {
+        self.contract
+            .submitted_batches(bucket_idx.into())
+            .call()
+            .await
+            .map_err(|e| eyre!("failed to read submittedBatches({bucket_idx}): {e}"))
+    }
+
+    /// The batches the contract currently holds as available.
+    pub async fn available_batches(&self) -> Result<Vec<VerifiedBatch>> {
+        let stored = self
+            .contract
+            .stored_batch_amt()
+            .call()
+            .await
+            .map_err(|e| eyre!("failed to read _STORED_BATCH_AMT: {e}"))?;
+        let batches = try_join_all((0..stored.as_u64()).map(|i| self.batch_info(i))).await?;
+        Ok(batches
+            .into_iter()
+            .filter(|batch| batch.id != [0; 32])
+            .collect())
+    }
+
+    /// Whether `id` is one of the batches the contract holds as available.
+    pub async fn is_available(&self, id: [u8; 32]) -> Result<bool> {
+        Ok(self
+            .available_batches()
+            .await?
+            .iter()
+            .any(|batch| batch.id == id))
+    }
+
+    /// `Submitted` events between the blocks, inclusive. `to` defaults to the
+    /// latest block.
+    pub async fn submitted_events(
+        &self,
+        from: u64,
+        to: Option<u64>,
+    ) -> Result<Vec<SubmittedEvent>> {
+        Ok(self
+            .events::<SubmittedFilter>(from, to)
+            .await?
+            .into_iter()
+            .map(|(event, meta)| SubmittedEvent {
+                bucket_idx: event.bucket_idx.as_u64(),
+                transaction_id: event.submit_tx_id,
+                block_number: meta.block_number.as_u64(),
+                transaction_hash: meta.transaction_hash,
+            })
+            .collect())
+    }
+
+    /// `IsAvailable` events between the blocks, inclusive. `to` defaults to
+    /// the latest block.
+    pub async fn available_events(
+        &self,
+        from: u64,
+        to: Option<u64>,
+    ) -> Result<Vec<AvailableEvent>> {
+        Ok(self
+            .events::<IsAvailableFilter>(from, to)
+            .await?
+            .into_iter()
+            .map(|(event, meta)| AvailableEvent {
+                bucket_idx: event.bucket_idx.as_u64(),
+                batch: event.batch,
+                block_number: meta.block_number.as_u64(),
+                transaction_hash: meta.transaction_hash,
+            })
+            .collect())
+    }
+
+    async fn events<E: EthEvent>(&self, from: u64, to: Option<u64>) -> Result<Vec<(E, LogMeta)>> {
+        let to = to.map_or(BlockNumber::Latest, BlockNumber::from);
+        self.contract
+            .event::<E>()
+            .from_block(from)
+            .to_block(to)
+            .query_with_meta()
+            .await
+            .map_err(|e| eyre!("failed to query {} events: {e}", E::name()))
+    }
+}
+
+/// Send `call` and wait until it is included, failing if it reverted.
+async fn transact<M: Middleware + 'static, D: Detokenize>(
+    call: ContractCall<M, D>,
+    method: &str,
+) -> Result<TxHash> {
+    let pending = call
+        .send()
+        .await
+        .map_err(|e| eyre!("failed to send {method}: {e}"))?;
+    let tx = *pending;
+    check_receipt(pending.await?, tx).map_err(|e| eyre!("{method} transaction {e}"))
+}
+
+async fn send<M: Middleware + 'static, D: Detokenize>(
+    call: &ContractCall<M, D>,
+    method: &str,
+) -> Result<TxHash> {
+    Ok(*call
+        .send()
+        .await
+        .map_err(|e| eyre!("failed to send {method}: {e}"))?)
+}
+
+fn check_receipt(receipt: Option<TransactionReceipt>, tx: TxHash) -> Result<TxHash> {
+    match receipt {
+        None => bail!("{tx:?} was dropped"),
+        Some(receipt) if receipt.status != Some(1.into()) => bail!("{tx:?} reverted"),
+        Some(receipt) => Ok(receipt.transaction_hash),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{AbiDecode, AbiEncode, RawLog};
+
+    #[test]
+    fn test_data_availability_message() {
+        let refs = vec![BlobRef::new([1; 32]), BlobRef::new([2; 32])];
+        let message = data_availability_message(&refs);
+        assert_eq!(message.len(), 64);
+        assert_eq!(&message[..32], &[1; 32]);
+
+        let parsed = parse_data_availability_message(&message).unwrap();
+        assert_eq!(
+            parsed.iter().map(|r| r.transaction_id).collect::<Vec<_>>(),
+            vec![[1; 32], [2; 32]]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_message() {
+        assert!(parse_data_availability_message(&[]).is_err());
+        assert!(parse_data_availability_message(&[1; 33]).is_err());
+    }
+
+    #[test]
+    fn test_notify_submitted_calldata() {
+        let call = NotifySubmittedCall {
+            batches: Bytes::from(data_availability_message(&[BlobRef::new([1; 32])])),
+        };
+        let calldata = call.clone().encode();
+        // notifySubmitted(bytes)
+        assert_eq!(
+            hex::encode(&calldata[..4]),
+            hex::encode(&ethers::utils::id("notifySubmitted(bytes)")[..4])
+        );
+        // Offset, length, then the transaction id
+        assert_eq!(calldata.len(), 4 + 32 * 3);
+        assert_eq!(&calldata[4 + 64..], &[1; 32]);
+        assert_eq!(NotifySubmittedCall::decode(&calldata).unwrap(), call);
+    }
+
+    #[test]
+    fn test_notify_available_calldata() {
+        let call = NotifyAvailableCall {
+            verified_batch: VerifiedBatch {
+                id: [1; 32],
+                verify_tx_hash: [2; 32],
+                submit_tx_id: [3; 32],
+            },
+        };
+        let calldata = call.encode();
+        // The struct is encoded inline as a tuple
+        assert_eq!(
+            hex::encode(&calldata[..4]),
+            hex::encode(&ethers::utils::id("notifyAvailable((bytes32,bytes32,bytes32))")[..4])
+        );
+        assert_eq!(&calldata[4..], [[1; 32], [2; 32], [3; 32]].concat());
+    }
+
+    #[test]
+    fn test_event_signatures() {
+        assert_eq!(
+            SubmittedFilter::signature(),
+            ethers::utils::keccak256("Submitted(uint256,bytes32)").into()
+        );
+        assert_eq!(
+            IsAvailableFilter::signature(),
+            ethers::utils::keccak256("IsAvailable(uint256,(bytes32,bytes32,bytes32))").into()
+        );
+    }
+
+    #[test]
+    fn test_decode_submitted_log() {
+        let log = RawLog {
+            topics: vec![SubmittedFilter::signature()],
+            data: [U256::from(5).encode(), vec![7; 32]].concat(),
+        };
+        let event = <SubmittedFilter as EthEvent>::decode_log(&log).unwrap();
+        assert_eq!(event.bucket_idx, U256::from(5));
+        assert_eq!(event.submit_tx_id, [7; 32]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Notifies the contract of NEAR submissions in the background, batching the
+//! transaction ids that arrive while a notification is being sent.
+
+use ethers::providers::Middleware;
+use eyre::Result;
+use near_da_primitives::BlobRef;
+use std::{future::Future, sync::Arc, time::Duration};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::{error, info, warn};
+
+use crate::Client;
+
+/// The contract only keeps this many submissions, so a notification never
+/// holds more.
+pub const MAX_BATCH: usize = 128;
+/// Ids waiting for a notification, beyond this they are dropped rather than
+/// held while L1 is unreachable.
+pub const QUEUE_SIZE: usize = MAX_BATCH * 8;
+/// Attempts per notification before its ids are dropped.
+pub const ATTEMPTS: u32 = 3;
+pub const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Clone)]
+pub struct Notifier {
+    submitted: mpsc::Sender<BlobRef>,
+}
+
+impl Notifier {
+    /// Start notifying the contract through `client`, which needs the notifier
+    /// role.
+    pub fn spawn<M: Middleware + 'static>(client: Arc<Client<M>>) -> Self {
+        Self::spawn_with(move |refs| {
+            let client = client.clone();
+            async move {
+                client
+                    .notify_submitted(&refs)
+                    .await
+                    .map(|tx| format!("{tx:?}"))
+            }
+        })
+    }
+
+    /// Start notifying with `notify`, which sends a batch of ids and returns
+    /// a description of the transaction.
+    pub fn spawn_with<F, Fut>(notify: F) -> Self
+    where
+        F: Fn(Vec<BlobRef>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        let (submitted, received) = mpsc::channel(QUEUE_SIZE);
+        tokio::spawn(run(received, notify));
+        Self { submitted }
+    }
+
+    /// Queue a NEAR transaction for the next notification.
+    pub fn submitted(&self, blob_ref: BlobRef) {
+        match self.submitted.try_send(blob_ref) {
+            Ok(()) => {}
+            Err(TrySendError::Full(blob_ref)) => warn!(
+                "L1 notifier has {QUEUE_SIZE} submissions queued, not notifying {}",
+                hex::encode(blob_ref.transaction_id)
+            ),
+            Err(TrySendError::Closed(_)) => warn!("L1 notifier has stopped, not notifying"),
+        }
+    }
+}
+
+async fn run<F, Fut>(mut received: mpsc::Receiver<BlobRef>, notify: F)
+where
+    F: Fn(Vec<BlobRef>) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    while let Some(blob_ref) = received.recv().await {
+        let mut refs = vec![blob_ref];
+        while refs.len() < MAX_BATCH {
+            match received.try_recv() {
+                Ok(blob_ref) => refs.push(blob_ref),
+                Err(_) => break,
+            }
+        }
+
+        for attempt in 1..=ATTEMPTS {
+            match notify(refs.clone()).await {
+                Ok(tx) => {
+                    info!("notified L1 of {} submissions in {tx}", refs.len());
+                    break;
+                }
+                Err(e) if attempt < ATTEMPTS => {
+                    warn!("failed to notify L1, attempt {attempt}/{ATTEMPTS}: {e:#}");
+                    tokio::time::sleep(RETRY_DELAY * attempt).await;
+                }
+                Err(e) => error!(
+                    "giving up notifying L1 of {} submissions: {e:#}",
+                    refs.len()
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// The first byte of each id, per notification.
+    type Calls = Arc<Mutex<Vec<Vec<u8>>>>;
+
+    /// A notifier that records its calls, failing the first `fail` of them.
+    /// `done` receives a message after each call.
+    fn recording(fail: u32) -> (Notifier, Calls, mpsc::UnboundedReceiver<()>) {
+        let calls = Arc::new(Mutex::new(vec![]));
+        let (done, done_rx) = mpsc::unbounded_channel();
+        let failures = Arc::new(Mutex::new(fail));
+        let notifier = {
+            let calls = calls.clone();
+            Notifier::spawn_with(move |refs: Vec<BlobRef>| {
+                let (calls, done, failures) = (calls.clone(), done.clone(), failures.clone());
+                async move {
+                    let ids = refs.iter().map(|r| r.transaction_id[0]).collect();
+                    calls.lock().unwrap().push(ids);
+                    let _ = done.send(());
+                    let mut failures = failures.lock().unwrap();
+                    if *failures > 0 {
+                        *failures -= 1;
+                        eyre::bail!("reverted");
+                    }
+                    Ok("0x01".to_string())
+                }
+            })
+        };
+        (notifier, calls, done_rx)
+    }
+
+    #[tokio::test]
+    async fn test_batches_queued_ids() {
+        let (notifier, calls, mut done) = recording(0);
+        for id in 1..=3 {
+            notifier.submitted(BlobRef::new([id; 32]));
+        }
+        done.recv().await.unwrap();
+        // Everything queued before the task ran goes in one notification
+        assert_eq!(*calls.lock().unwrap(), vec![vec![1, 2, 3]]);
+
+        notifier.submitted(BlobRef::new([4; 32]));
+        done.recv().await.unwrap();
+        assert_eq!(calls.lock().unwrap()[1], vec![4]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_size_limited() {
+        let (notifier, calls, mut done) = recording(0);
+        for id in 0..MAX_BATCH + 1 {
+            notifier.submitted(BlobRef::new([id as u8; 32]));
+        }
+        done.recv().await.unwrap();
+        done.recv().await.unwrap();
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls[0].len(), MAX_BATCH);
+        assert_eq!(calls[1], vec![MAX_BATCH as u8]);
+    }
+
+    #[tokio::test]
+    async fn test_queue_bounded() {
+        let (notifier, calls, mut done) = recording(0);
+        // The notifier task doesn't run until this one yields
+        for id in 0..QUEUE_SIZE + 1 {
+            notifier.submitted(BlobRef::new([id as u8; 32]));
+        }
+        for _ in 0..QUEUE_SIZE / MAX_BATCH {
+            done.recv().await.unwrap();
+        }
+        let notified: usize = calls.lock().unwrap().iter().map(Vec::len).sum();
+        assert_eq!(notified, QUEUE_SIZE);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retries() {
+        let (notifier, calls, mut done) = recording(1);
+        notifier.submitted(BlobRef::new([1; 32]));
+        done.recv().await.unwrap();
+        done.recv().await.unwrap();
+        assert_eq!(*calls.lock().unwrap(), vec![vec![1], vec![1]]);
+    }
+}
@@ -0,0 +1,150 @@
+//! Runs the client against `NearDataAvailability.sol` on a local anvil node.
+//! Needs `anvil` on the path and the contract built with `forge build` in
+//! `eth/`, so the tests are ignored by default:
+//!
+//! ```sh
+//! (cd eth && forge build) && cargo test -p near-da-eth -- --ignored
+//! ```
+
+use ethers::{
+    abi::Abi,
+    contract::ContractFactory,
+    middleware::SignerMiddleware,
+    providers::{Http, Provider},
+    signers::{LocalWallet, Signer},
+    types::Bytes,
+    utils::{Anvil, AnvilInstance},
+};
+use near_da_eth::{Client, Config, Notifier, VerifiedBatch};
+use near_da_primitives::BlobRef;
+use std::{path::Path, sync::Arc, time::Duration};
+
+type Signed = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+/// Deploy the contract from the first anvil account, which owns it.
+async fn deploy() -> (AnvilInstance, Client<Signed>) {
+    let anvil = Anvil::new().spawn();
+    let wallet = LocalWallet::from(anvil.keys()[0].clone()).with_chain_id(anvil.chain_id());
+    let provider = Provider::<Http>::try_from(anvil.endpoint())
+        .unwrap()
+        .interval(Duration::from_millis(10));
+    let middleware = Arc::new(SignerMiddleware::new(provider, wallet));
+
+    let artifact = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../eth/out/NearDataAvailability.sol/NearDataAvailability.json");
+    let artifact: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(&artifact).expect("contract not built, run `forge build` in eth/"),
+    )
+    .unwrap();
+    let abi: Abi = serde_json::from_value(artifact["abi"].clone()).unwrap();
+    let bytecode = artifact["bytecode"]["object"].as_str().unwrap();
+    let bytecode = Bytes::from(hex::decode(bytecode.trim_start_matches("0x")).unwrap());
+
+    let contract = ContractFactory::new(abi, bytecode, middleware.clone())
+        .deploy(())
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    (
+        anvil,
+        Client::with_middleware(contract.address(), middleware),
+    )
+}
+
+/// A client for the second anvil account, which has no roles.
+async fn other_account(anvil: &AnvilInstance, owner: &Client<Signed>) -> Client {
+    Client::new(&Config {
+        rpc: anvil.endpoint(),
+        contract: owner.address(),
+        key: hex::encode(anvil.keys()[1].to_bytes()).into(),
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and `forge build`"]
+async fn test_notify_submitted() {
+    let (_anvil, client) = deploy().await;
+    let refs = [BlobRef::new([1; 32]), BlobRef::new([2; 32])];
+
+    let tx = client.notify_submitted(&refs).await.unwrap();
+
+    let events = client.submitted_events(0, None).await.unwrap();
+    assert_eq!(events.len(), 2);
+    for (i, event) in events.iter().enumerate() {
+        assert_eq!(event.bucket_idx, i as u64);
+        assert_eq!(event.transaction_id, refs[i].transaction_id);
+        assert_eq!(event.transaction_hash, tx);
+    }
+    assert_eq!(client.submitted_batch(1).await.unwrap(), [2; 32]);
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and `forge build`"]
+async fn test_notify_available() {
+    let (_anvil, client) = deploy().await;
+    let batch = VerifiedBatch {
+        id: [1; 32],
+        verify_tx_hash: [2; 32],
+        submit_tx_id: [3; 32],
+    };
+    assert!(!client.is_available(batch.id).await.unwrap());
+
+    client.notify_available(batch.clone()).await.unwrap();
+
+    let events = client.available_events(0, None).await.unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].bucket_idx, 0);
+    assert_eq!(events[0].batch, batch);
+    assert_eq!(client.batch_info(0).await.unwrap(), batch);
+    assert!(client.is_available(batch.id).await.unwrap());
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and `forge build`"]
+async fn test_notifier_role_required() {
+    let (anvil, owner) = deploy().await;
+    let other = other_account(&anvil, &owner).await;
+    let refs = [BlobRef::new([1; 32])];
+
+    assert!(other.notify_submitted(&refs).await.is_err());
+
+    let notifier = owner.contract().notifier().call().await.unwrap();
+    owner
+        .grant_roles(anvil.addresses()[1], notifier)
+        .await
+        .unwrap();
+    other.notify_submitted(&refs).await.unwrap();
+    assert_eq!(owner.submitted_events(0, None).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and `forge build`"]
+async fn test_notifier() {
+    let (_anvil, client) = deploy().await;
+    let client = Arc::new(client);
+    let notifier = Notifier::spawn(client.clone());
+
+    for id in 1..=3 {
+        notifier.submitted(BlobRef::new([id; 32]));
+    }
+
+    let events = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let events = client.submitted_events(0, None).await.unwrap();
+            if events.len() == 3 {
+                return events;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let ids = events
+        .iter()
+        .map(|e| e.transaction_id[0])
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![1, 2, 3]);
+}
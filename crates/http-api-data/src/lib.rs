@@ -1,24 +1,97 @@
 #![no_std]
 extern crate alloc;
 
-use alloc::string::String;
+use alloc::{string::String, vec::Vec};
 use near_da_primitives::Mode;
-pub use near_da_primitives::{Blob, BlobRef, Namespace};
+pub use near_da_primitives::{Blob, BlobRef, Namespace, Secret};
 use serde::{Deserialize, Serialize};
+use serde_with::{hex::Hex, serde_as};
 
+/// Secret material is wrapped in [`Secret`], so it is redacted when the request
+/// is logged or serialized.
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct ConfigureClientRequest {
     pub account_id: String,
-    pub secret_key: String,
+    /// An inline secret key, shorthand for `key: {"type": "secret_key", ..}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_key: Option<Secret<String>>,
+    /// Where to source the signing key from, takes precedence over `secret_key`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<KeySource>,
     pub contract_id: String,
     pub network: String,
     pub namespace: Option<Namespace>,
     pub mode: Option<Mode>,
+    /// A `blob-registry` contract to record submissions in, under `namespace`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry_id: Option<String>,
     /// How big the bytes should be for the lru lookup cache
     #[serde(default = "default_bool::<false>")]
     pub should_cache: bool,
 }
 
+/// The signing key for the client, mirroring `near_da_rpc::near::config::KeyType`.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KeySource {
+    /// A NEAR credentials file, the account is taken from the file.
+    File {
+        path: String,
+    },
+    /// A key derived from a seed phrase.
+    Seed {
+        seed: Secret<String>,
+    },
+    SecretKey {
+        secret_key: Secret<String>,
+    },
+    /// An encrypted keystore, the account is taken from the keystore. The
+    /// password is read from the environment variable `password_env`.
+    Keystore {
+        path: String,
+        password_env: String,
+    },
+    /// Delegate signing to an external process listening on `socket`.
+    External {
+        socket: String,
+        public_key: String,
+    },
+}
+
+/// Batches from the Polygon CDK sequence sender, in sequence order.
+#[serde_as]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct PostSequenceRequest {
+    #[serde_as(as = "Vec<Hex>")]
+    pub batches: Vec<Vec<u8>>,
+}
+
+#[serde_as]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct PostSequenceResponse {
+    /// The `dataAvailabilityBatch` the sequence sender passes to L1.
+    #[serde_as(as = "Hex")]
+    pub data_availability_message: Vec<u8>,
+}
+
+/// Read back a sequence posted to the DA layer, for the CDK synchronizer.
+#[serde_as]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct GetSequenceRequest {
+    /// keccak256 of each batch, in sequence order.
+    #[serde_as(as = "Vec<Hex>")]
+    pub batch_hashes: Vec<[u8; 32]>,
+    #[serde_as(as = "Hex")]
+    pub data_availability_message: Vec<u8>,
+}
+
+#[serde_as]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct GetSequenceResponse {
+    #[serde_as(as = "Vec<Hex>")]
+    pub batches: Vec<Vec<u8>>,
+}
+
 pub const fn default_bool<const V: bool>() -> bool {
     V
 }
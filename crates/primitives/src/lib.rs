@@ -6,7 +6,9 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 
-use core::ops::Deref;
+use core::{fmt, ops::Deref};
+
+pub mod proof;
 
 pub type Data = alloc::vec::Vec<u8>;
 pub type ShareVersion = u32;
@@ -118,7 +120,7 @@ impl Deref for BlobRef {
 pub struct SubmitRequest {
     pub namespace: Option<Namespace>,
     #[serde_as(as = "serde_with::hex::Hex")]
-    pub data: Vec<u8>,
+    pub data: Data,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
@@ -149,9 +151,78 @@ impl From<&str> for Mode {
     }
 }
 
+/// What a [`Secret`] is shown as.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Secret material, such as a secret key or password. It is redacted when
+/// formatted with `Debug` or `Display` and when serialized, so it can't leak
+/// into logs or responses. Deserializing reads the real value. Use
+/// [`Secret::expose`] to read it.
+#[derive(Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(transparent)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(secret: T) -> Self {
+        Self(secret)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(secret: T) -> Self {
+        Self(secret)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T> Serialize for Secret<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::{format, string::String};
+
+    #[test]
+    fn test_secret_redacted() {
+        let secret = Secret::new(String::from("ed25519:secret"));
+        assert_eq!(format!("{:?}", secret), REDACTED);
+        assert_eq!(format!("{:#?}", secret), REDACTED);
+        assert_eq!(format!("{}", secret), REDACTED);
+        assert_eq!(secret.expose(), "ed25519:secret");
+    }
+
+    #[test]
+    fn test_secret_serde() {
+        let secret: Secret<String> = serde_json::from_str(r#""ed25519:secret""#).unwrap();
+        assert_eq!(secret.expose(), "ed25519:secret");
+        assert_eq!(
+            serde_json::to_string(&secret).unwrap(),
+            format!(r#""{REDACTED}""#)
+        );
+    }
 
     #[test]
     fn test_format() {
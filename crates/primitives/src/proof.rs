@@ -0,0 +1,520 @@
+//! Offline verification of blob inclusion proofs.
+//!
+//! These are the parts of NEAR's light client types that a proof touches,
+//! with the same borsh layout, so a proof can be checked without a node or
+//! `near-primitives`, e.g. by a fisherman or inside a zk circuit.
+//!
+//! The outcome proof only commits to the transaction hash, so the verifier
+//! also needs the transaction the submitter signed to check what it carried.
+//! Submissions from before namespaces, a list of [`LegacyBlob`]s, are read
+//! like `Client::get` reads them, by their first blob.
+
+use alloc::{string::String, vec::Vec};
+use borsh::{BorshDeserialize, BorshSerialize};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sha2::{Digest, Sha256};
+
+use crate::{LegacyBlob, SubmitRequest};
+
+pub type CryptoHash = [u8; 32];
+
+/// The method of the blob store that stores blobs.
+pub const SUBMIT_METHOD: &str = "submit";
+
+pub fn hash(bytes: &[u8]) -> CryptoHash {
+    Sha256::digest(bytes).into()
+}
+
+pub fn hash_borsh<T: BorshSerialize>(value: &T) -> CryptoHash {
+    let mut hasher = Sha256::new();
+    // Writing into a hasher can't fail
+    value.serialize(&mut HashWriter(&mut hasher)).unwrap();
+    hasher.finalize().into()
+}
+
+struct HashWriter<'a>(&'a mut Sha256);
+
+impl borsh::io::Write for HashWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> borsh::io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> borsh::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn combine_hash(left: &CryptoHash, right: &CryptoHash) -> CryptoHash {
+    hash_borsh(&(left, right))
+}
+
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MerklePathItem {
+    pub hash: CryptoHash,
+    pub direction: Direction,
+}
+
+pub type MerklePath = Vec<MerklePathItem>;
+
+pub fn compute_root_from_path(path: &MerklePath, item_hash: CryptoHash) -> CryptoHash {
+    path.iter()
+        .fold(item_hash, |hash, item| match item.direction {
+            Direction::Left => combine_hash(&item.hash, &hash),
+            Direction::Right => combine_hash(&hash, &item.hash),
+        })
+}
+
+pub fn compute_root_from_path_and_item<T: BorshSerialize>(
+    path: &MerklePath,
+    item: &T,
+) -> CryptoHash {
+    compute_root_from_path(path, hash_borsh(item))
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockHeaderInnerLite {
+    pub height: u64,
+    pub epoch_id: CryptoHash,
+    pub next_epoch_id: CryptoHash,
+    pub prev_state_root: CryptoHash,
+    /// Root of the outcomes of the previous block's chunks.
+    pub outcome_root: CryptoHash,
+    pub timestamp: u64,
+    pub next_bp_hash: CryptoHash,
+    /// Root of the hashes of every block up to the previous one.
+    pub block_merkle_root: CryptoHash,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LightClientBlockLite {
+    pub prev_block_hash: CryptoHash,
+    pub inner_rest_hash: CryptoHash,
+    pub inner_lite: BlockHeaderInnerLite,
+}
+
+impl LightClientBlockLite {
+    pub fn hash(&self) -> CryptoHash {
+        combine_hash(
+            &combine_hash(&hash_borsh(&self.inner_lite), &self.inner_rest_hash),
+            &self.prev_block_hash,
+        )
+    }
+}
+
+/// An outcome's status, without the error details, which are not hashed.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ExecutionStatus {
+    Unknown,
+    Failure,
+    SuccessValue(Vec<u8>),
+    SuccessReceiptId(CryptoHash),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionOutcome {
+    pub logs: Vec<String>,
+    pub receipt_ids: Vec<CryptoHash>,
+    pub gas_burnt: u64,
+    pub tokens_burnt: u128,
+    pub executor_id: String,
+    pub status: ExecutionStatus,
+}
+
+impl ExecutionOutcome {
+    /// The leaves the outcome is merkelized from: its id, the outcome without
+    /// logs, then each log.
+    pub fn to_hashes(&self, id: CryptoHash) -> Vec<CryptoHash> {
+        let mut hashes = Vec::with_capacity(2 + self.logs.len());
+        hashes.push(id);
+        hashes.push(hash_borsh(&(
+            &self.receipt_ids,
+            self.gas_burnt,
+            self.tokens_burnt,
+            &self.executor_id,
+            &self.status,
+        )));
+        hashes.extend(self.logs.iter().map(|log| hash(log.as_bytes())));
+        hashes
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OutcomeProof {
+    /// From the outcome to its chunk's outcome root.
+    pub proof: MerklePath,
+    pub block_hash: CryptoHash,
+    pub id: CryptoHash,
+    pub outcome: ExecutionOutcome,
+}
+
+#[serde_as]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum PublicKey {
+    Ed25519([u8; 32]),
+    Secp256k1(#[serde_as(as = "serde_with::hex::Hex")] [u8; 64]),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FunctionCall {
+    pub method_name: String,
+    pub args: Vec<u8>,
+    pub gas: u64,
+    pub deposit: u128,
+}
+
+/// The actions up to function calls. A submission has a single function
+/// call, so a transaction with any later action fails to decode.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    CreateAccount,
+    DeployContract { code: Vec<u8> },
+    FunctionCall(FunctionCall),
+}
+
+/// A transaction as signed, its hash is the transaction id.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Transaction {
+    pub signer_id: String,
+    pub public_key: PublicKey,
+    pub nonce: u64,
+    pub receiver_id: String,
+    pub block_hash: CryptoHash,
+    pub actions: Vec<Action>,
+}
+
+impl Transaction {
+    pub fn hash(&self) -> CryptoHash {
+        hash_borsh(self)
+    }
+}
+
+/// What `EXPERIMENTAL_light_client_proof` returns for a transaction, with the
+/// transaction itself.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct InclusionProof {
+    pub transaction: Transaction,
+    pub outcome_proof: OutcomeProof,
+    /// From the chunk's outcome root to the block's.
+    pub outcome_root_proof: MerklePath,
+    /// The block whose outcome root holds the outcome.
+    pub block_header_lite: LightClientBlockLite,
+    /// From that block to the trusted head's block merkle root.
+    pub block_proof: MerklePath,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofError {
+    /// The outcome is not the transaction's.
+    Transaction,
+    /// The transaction failed.
+    Failed,
+    /// The transaction is not a single `submit` call to the contract.
+    NotSubmit,
+    /// The submission does not carry the payload.
+    Data,
+    /// The outcome does not lead to the block's outcome root.
+    OutcomeRoot,
+    /// The block does not lead to the head's block merkle root.
+    BlockRoot,
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Transaction => "proof is for another transaction",
+            Self::Failed => "transaction failed",
+            Self::NotSubmit => "transaction is not a submission to the contract",
+            Self::Data => "submission does not carry the payload",
+            Self::OutcomeRoot => "outcome is not in the block's outcome root",
+            Self::BlockRoot => "block is not in the trusted head's block merkle root",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ProofError {}
+
+impl InclusionProof {
+    /// Check that the transaction submitted `payload` to `contract` and was
+    /// executed in a block `head` commits to. Returns the transaction id.
+    ///
+    /// Only the transaction's own outcome is proven, not the receipt that
+    /// ran `submit` on the contract.
+    pub fn verify(
+        &self,
+        head: &LightClientBlockLite,
+        contract: &str,
+        payload: &[u8],
+    ) -> Result<CryptoHash, ProofError> {
+        let id = self.transaction.hash();
+        if self.outcome_proof.id != id {
+            return Err(ProofError::Transaction);
+        }
+        if matches!(
+            self.outcome_proof.outcome.status,
+            ExecutionStatus::Unknown | ExecutionStatus::Failure
+        ) {
+            return Err(ProofError::Failed);
+        }
+
+        let call = match self.transaction.actions.as_slice() {
+            [Action::FunctionCall(call)]
+                if self.transaction.receiver_id == contract
+                    && call.method_name == SUBMIT_METHOD =>
+            {
+                call
+            }
+            _ => return Err(ProofError::NotSubmit),
+        };
+        if submitted_data(&call.args).ok_or(ProofError::Data)? != payload {
+            return Err(ProofError::Data);
+        }
+
+        let outcome_hash = hash_borsh(&self.outcome_proof.outcome.to_hashes(id));
+        let chunk_outcome_root = compute_root_from_path(&self.outcome_proof.proof, outcome_hash);
+        let outcome_root =
+            compute_root_from_path_and_item(&self.outcome_root_proof, &chunk_outcome_root);
+        if outcome_root != self.block_header_lite.inner_lite.outcome_root {
+            return Err(ProofError::OutcomeRoot);
+        }
+
+        let block_root =
+            compute_root_from_path_and_item(&self.block_proof, &self.block_header_lite.hash());
+        if block_root != head.inner_lite.block_merkle_root {
+            return Err(ProofError::BlockRoot);
+        }
+        Ok(id)
+    }
+}
+
+/// The payload in `submit` args, a [`SubmitRequest`] or a legacy request.
+fn submitted_data(args: &[u8]) -> Option<Vec<u8>> {
+    if let Ok(request) = SubmitRequest::try_from_slice(args) {
+        return Some(request.data);
+    }
+    // A legacy request is a struct holding only the list
+    let blobs = Vec::<LegacyBlob>::try_from_slice(args).ok()?;
+    blobs.into_iter().next().map(|blob| blob.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Namespace;
+    use alloc::{borrow::ToOwned, vec};
+
+    const CONTRACT: &str = "blobstore.testnet";
+
+    fn transaction(payload: &[u8]) -> Transaction {
+        let request = SubmitRequest {
+            namespace: Some(Namespace::new(1, 1)),
+            data: payload.to_vec(),
+        };
+        with_args(borsh::to_vec(&request).unwrap())
+    }
+
+    fn with_args(args: Vec<u8>) -> Transaction {
+        Transaction {
+            signer_id: "rollup.testnet".to_owned(),
+            public_key: PublicKey::Ed25519([7; 32]),
+            nonce: 1,
+            receiver_id: CONTRACT.to_owned(),
+            block_hash: [8; 32],
+            actions: vec![Action::FunctionCall(FunctionCall {
+                method_name: SUBMIT_METHOD.to_owned(),
+                args,
+                gas: 20_000_000_000_000,
+                deposit: 0,
+            })],
+        }
+    }
+
+    fn header(outcome_root: CryptoHash, block_merkle_root: CryptoHash) -> LightClientBlockLite {
+        LightClientBlockLite {
+            prev_block_hash: [1; 32],
+            inner_rest_hash: [2; 32],
+            inner_lite: BlockHeaderInnerLite {
+                height: 100,
+                epoch_id: [3; 32],
+                next_epoch_id: [4; 32],
+                prev_state_root: [5; 32],
+                outcome_root,
+                timestamp: 1,
+                next_bp_hash: [6; 32],
+                block_merkle_root,
+            },
+        }
+    }
+
+    /// A proof for `transaction`, one sibling away at each level, and the
+    /// head it leads to.
+    fn prove(transaction: Transaction) -> (InclusionProof, LightClientBlockLite) {
+        let id = transaction.hash();
+        let outcome_proof = OutcomeProof {
+            proof: vec![MerklePathItem {
+                hash: [10; 32],
+                direction: Direction::Left,
+            }],
+            block_hash: [11; 32],
+            id,
+            outcome: ExecutionOutcome {
+                logs: vec![],
+                receipt_ids: vec![[12; 32]],
+                gas_burnt: 1,
+                tokens_burnt: 2,
+                executor_id: transaction.signer_id.clone(),
+                status: ExecutionStatus::SuccessReceiptId([12; 32]),
+            },
+        };
+        let outcome_root_proof = vec![MerklePathItem {
+            hash: [13; 32],
+            direction: Direction::Right,
+        }];
+        let chunk_outcome_root =
+            combine_hash(&[10; 32], &hash_borsh(&outcome_proof.outcome.to_hashes(id)));
+        let outcome_root = combine_hash(&hash_borsh(&chunk_outcome_root), &[13; 32]);
+        let block_header_lite = header(outcome_root, [0; 32]);
+
+        let block_proof = vec![MerklePathItem {
+            hash: [14; 32],
+            direction: Direction::Left,
+        }];
+        let head = header(
+            [0; 32],
+            combine_hash(&[14; 32], &hash_borsh(&block_header_lite.hash())),
+        );
+        (
+            InclusionProof {
+                transaction,
+                outcome_proof,
+                outcome_root_proof,
+                block_header_lite,
+                block_proof,
+            },
+            head,
+        )
+    }
+
+    #[test]
+    fn test_verify() {
+        let transaction = transaction(b"blob");
+        let id = transaction.hash();
+        let (proof, head) = prove(transaction);
+        assert_eq!(proof.verify(&head, CONTRACT, b"blob"), Ok(id));
+
+        // Through borsh and back, as it would be handed to a verifier
+        let proof = InclusionProof::try_from_slice(&borsh::to_vec(&proof).unwrap()).unwrap();
+        assert_eq!(proof.verify(&head, CONTRACT, b"blob"), Ok(id));
+    }
+
+    #[test]
+    fn test_wrong_payload_or_contract() {
+        let (proof, head) = prove(transaction(b"blob"));
+        assert_eq!(
+            proof.verify(&head, CONTRACT, b"other"),
+            Err(ProofError::Data)
+        );
+        assert_eq!(
+            proof.verify(&head, "other.testnet", b"blob"),
+            Err(ProofError::NotSubmit)
+        );
+    }
+
+    #[test]
+    fn test_legacy_request() {
+        let blob = |data: &[u8]| LegacyBlob {
+            namespace: Namespace::new(1, 1),
+            share_version: 0,
+            commitment: [0; 32],
+            data: data.to_vec(),
+        };
+        let args = borsh::to_vec(&vec![blob(b"blob"), blob(b"second")]).unwrap();
+        let transaction = with_args(args);
+        let id = transaction.hash();
+        let (proof, head) = prove(transaction);
+        assert_eq!(proof.verify(&head, CONTRACT, b"blob"), Ok(id));
+        assert_eq!(
+            proof.verify(&head, CONTRACT, b"second"),
+            Err(ProofError::Data)
+        );
+
+        let (empty, head) = prove(with_args(borsh::to_vec(&Vec::<LegacyBlob>::new()).unwrap()));
+        assert_eq!(empty.verify(&head, CONTRACT, b""), Err(ProofError::Data));
+    }
+
+    #[test]
+    fn test_tampered() {
+        let (proof, head) = prove(transaction(b"blob"));
+
+        let mut swapped = proof.clone();
+        swapped.transaction = transaction(b"other");
+        assert_eq!(
+            swapped.verify(&head, CONTRACT, b"other"),
+            Err(ProofError::Transaction)
+        );
+
+        let mut failed = proof.clone();
+        failed.outcome_proof.outcome.status = ExecutionStatus::Failure;
+        assert_eq!(
+            failed.verify(&head, CONTRACT, b"blob"),
+            Err(ProofError::Failed)
+        );
+
+        let mut burnt = proof.clone();
+        burnt.outcome_proof.outcome.gas_burnt += 1;
+        assert_eq!(
+            burnt.verify(&head, CONTRACT, b"blob"),
+            Err(ProofError::OutcomeRoot)
+        );
+
+        let mut path = proof.clone();
+        path.outcome_root_proof[0].direction = Direction::Left;
+        assert_eq!(
+            path.verify(&head, CONTRACT, b"blob"),
+            Err(ProofError::OutcomeRoot)
+        );
+
+        let other_head = header([0; 32], [15; 32]);
+        assert_eq!(
+            proof.verify(&other_head, CONTRACT, b"blob"),
+            Err(ProofError::BlockRoot)
+        );
+    }
+
+    #[test]
+    fn test_borsh_layout() {
+        // Tuples and arrays are written back to back, which the merkle
+        // hashing relies on
+        assert_eq!(
+            combine_hash(&[1; 32], &[2; 32]),
+            hash(&[[1u8; 32], [2; 32]].concat())
+        );
+        assert_eq!(
+            borsh::to_vec(&PublicKey::Ed25519([7; 32])).unwrap()[0],
+            0,
+            "ed25519 keys are tagged 0"
+        );
+        assert_eq!(
+            borsh::to_vec(&Action::FunctionCall(FunctionCall {
+                method_name: SUBMIT_METHOD.to_owned(),
+                args: vec![],
+                gas: 0,
+                deposit: 0,
+            }))
+            .unwrap()[0],
+            2,
+            "function calls are the third action"
+        );
+    }
+}